@@ -13,7 +13,7 @@ use starknet_in_rust::{
     state::{state_api::StateReader, state_cache::StorageEntry},
     utils::{parse_felt_array, Address, ClassHash, CompiledClassHash},
 };
-use std::env;
+use std::{collections::HashMap, env};
 use thiserror::Error;
 
 #[cfg(test)]
@@ -98,6 +98,16 @@ impl BlockValue {
     }
 }
 
+/// Pins the reads to the given block number, or follows the latest block when `None`.
+impl From<Option<u64>> for BlockValue {
+    fn from(block_number: Option<u64>) -> Self {
+        match block_number {
+            Some(block_number) => BlockValue::Number(json!(block_number)),
+            None => BlockValue::Tag(json!("latest")),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct RpcResponseProgram {
     result: ContractClass,
@@ -343,6 +353,7 @@ impl RpcState {
                 .unwrap(),
             gas_price: *starknet_os_config.gas_price() as u64,
             sequencer_address: starknet_os_config.fee_token_address().clone(),
+            recent_block_hashes: HashMap::new(),
         }
     }
 }
@@ -360,7 +371,8 @@ impl StateReader for RpcState {
             .rpc_call(&params)
             .map_err(|err| StateError::CustomError(err.to_string()))?;
 
-        Ok(CompiledClass::from(response.result))
+        CompiledClass::try_from(response.result)
+            .map_err(|err| StateError::MalformedContractClass(*class_hash, err.to_string()))
     }
 
     fn get_class_hash_at(&self, contract_address: &Address) -> Result<ClassHash, StateError> {
@@ -425,7 +437,18 @@ mod tests {
 
     use super::*;
     use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
-    use starknet_in_rust::felt::felt_str;
+    use starknet_in_rust::{
+        core::transaction_hash::calculate_invoke_transaction_hash, felt::felt_str,
+    };
+
+    #[test]
+    fn test_block_value_from_block_number() {
+        assert_eq!(
+            BlockValue::from(Some(168204)).to_value(),
+            json!({ "block_number": 168204 })
+        );
+        assert_eq!(BlockValue::from(None).to_value(), json!("latest"));
+    }
 
     #[test]
     fn test_get_contract_class_cairo1() {
@@ -530,6 +553,44 @@ mod tests {
         rpc_state.get_transaction(tx_hash);
     }
 
+    /// Recomputes the hash of mainnet invoke v1 transaction
+    /// https://starkscan.co/tx/0x06da92cfbdceac5e5e94a1f40772d6c79d34f011815606742658559ec77b6955
+    /// from its fields.
+    #[test]
+    fn test_calculate_invoke_transaction_hash() {
+        let rpc_state = RpcState::new(
+            RpcChain::MainNet,
+            BlockValue::Tag(serde_json::to_value("latest").unwrap()),
+        );
+        let tx_hash = "06da92cfbdceac5e5e94a1f40772d6c79d34f011815606742658559ec77b6955";
+
+        let params = ureq::json!({
+            "jsonrpc": "2.0",
+            "method": "starknet_getTransactionByHash",
+            "params": [format!("0x{}", tx_hash)],
+            "id": 1
+        });
+        let response: serde_json::Value = rpc_state.rpc_call(&params).unwrap();
+        let tx = &response["result"];
+        assert_eq!(tx["type"], "INVOKE");
+        assert_eq!(tx["version"], "0x1");
+
+        let felt_field = |name: &str| parse_felt_array(&[tx[name].clone()]).remove(0);
+        let hash = calculate_invoke_transaction_hash(
+            1.into(),
+            &Address(felt_field("sender_address")),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            &parse_felt_array(tx["calldata"].as_array().unwrap()),
+            u128::from_str_radix(tx["max_fee"].as_str().unwrap().trim_start_matches("0x"), 16)
+                .unwrap(),
+            Some(felt_field("nonce")),
+            StarknetChainId::MainNet.to_felt(),
+        )
+        .unwrap();
+
+        assert_eq!(hash, felt_str!(tx_hash, 16));
+    }
+
     #[test]
     fn test_get_block_info() {
         let rpc_state = RpcState::new(
@@ -571,6 +632,7 @@ mod tests {
                 .unwrap(),
             gas_price: gas_price_u128 as u64,
             sequencer_address: fee_token_address,
+            recent_block_hashes: HashMap::new(),
         };
 
         assert_eq!(rpc_state.get_block_info(starknet_os_config,), block_info);
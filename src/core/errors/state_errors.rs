@@ -37,6 +37,8 @@ pub enum StateError {
     MissingCasmClassCache,
     #[error("Constructor calldata is empty")]
     ConstructorCalldataEmpty(),
+    #[error("Constructor expects {0} calldata felts, got {1}")]
+    ConstructorArgumentMismatch(usize, usize),
     #[error("Error in ExecutionEntryPoint")]
     ExecutionEntryPoint(),
     #[error("No compiled class found for compiled_class_hash {0:?}")]
@@ -47,6 +49,8 @@ pub enum StateError {
     MissingCasmClass(ClassHash),
     #[error("No class hash declared in class_hash_to_contract_class")]
     MissingClassHash(),
+    #[error("Contract class for hash {0:?} is stored but could not be deserialized: {1}")]
+    MalformedContractClass(ClassHash, String),
     #[error("Uninitializes class_hash")]
     UninitiaizedClassHash,
     #[error(transparent)]
@@ -1,8 +1,11 @@
 use crate::core::errors::hash_errors::HashError;
 use crate::{
     core::contract_address::compute_deprecated_class_hash,
-    definitions::constants::CONSTRUCTOR_ENTRY_POINT_SELECTOR, hash_utils::compute_hash_on_elements,
-    services::api::contract_classes::deprecated_contract_class::ContractClass, utils::Address,
+    definitions::constants::{CONSTRUCTOR_ENTRY_POINT_SELECTOR, L1_HANDLER_VERSION},
+    hash_utils::compute_hash_on_elements,
+    services::api::contract_classes::deprecated_contract_class::ContractClass,
+    transaction::error::TransactionError,
+    utils::{split_query_version, Address},
 };
 use cairo_vm::felt::{felt_str, Felt252};
 use num_traits::Zero;
@@ -73,6 +76,81 @@ pub fn calculate_transaction_hash_common(
     compute_hash_on_elements(&data_to_hash)
 }
 
+/// Performs validation on fields related to an InvokeFunction transaction and deduces the entry
+/// point selector field and additional data used to calculate its hash.
+pub(crate) fn preprocess_invoke_function_fields(
+    entry_point_selector: Felt252,
+    nonce: Option<Felt252>,
+    version: Felt252,
+) -> Result<(Felt252, Vec<Felt252>), TransactionError> {
+    let (base_version, _) = split_query_version(&version);
+    if base_version.is_zero() {
+        match nonce {
+            Some(_) => Err(TransactionError::InvokeFunctionZeroHasNonce),
+            None => {
+                let additional_data = Vec::new();
+                let entry_point_selector_field = entry_point_selector;
+                Ok((entry_point_selector_field, additional_data))
+            }
+        }
+    } else {
+        match nonce {
+            Some(n) => {
+                let additional_data = vec![n];
+                let entry_point_selector_field = Felt252::zero();
+                Ok((entry_point_selector_field, additional_data))
+            }
+            None => Err(TransactionError::InvokeFunctionNonZeroMissingNonce),
+        }
+    }
+}
+
+/// Calculate the hash for an invoke transaction. Version 0 transactions hash the entry point
+/// selector and must not have a nonce, later versions hash their nonce instead.
+pub fn calculate_invoke_transaction_hash(
+    version: Felt252,
+    contract_address: &Address,
+    entry_point_selector: Felt252,
+    calldata: &[Felt252],
+    max_fee: u128,
+    nonce: Option<Felt252>,
+    chain_id: Felt252,
+) -> Result<Felt252, TransactionError> {
+    let (entry_point_selector_field, additional_data) =
+        preprocess_invoke_function_fields(entry_point_selector, nonce, version.clone())?;
+
+    Ok(calculate_transaction_hash_common(
+        TransactionHashPrefix::Invoke,
+        version,
+        contract_address,
+        entry_point_selector_field,
+        calldata,
+        max_fee,
+        chain_id,
+        &additional_data,
+    )?)
+}
+
+/// Calculate the hash for an L1 handler transaction.
+pub fn calculate_l1_handler_transaction_hash(
+    contract_address: &Address,
+    entry_point_selector: Felt252,
+    calldata: &[Felt252],
+    nonce: Felt252,
+    chain_id: Felt252,
+) -> Result<Felt252, HashError> {
+    calculate_transaction_hash_common(
+        TransactionHashPrefix::L1Handler,
+        L1_HANDLER_VERSION.into(),
+        contract_address,
+        entry_point_selector,
+        calldata,
+        0,
+        chain_id,
+        &[nonce],
+    )
+}
+
 /// Calculate the hash for deploying a transaction.
 pub fn calculate_deploy_transaction_hash(
     version: Felt252,
@@ -131,6 +209,26 @@ pub fn calculate_declare_transaction_hash(
     let class_hash = compute_deprecated_class_hash(contract_class)
         .map_err(|e| HashError::FailedToComputeHash(e.to_string()))?;
 
+    calculate_declare_transaction_hash_with_class_hash(
+        class_hash,
+        chain_id,
+        sender_address,
+        max_fee,
+        version,
+        nonce,
+    )
+}
+
+/// Calculate the hash for a declared transaction, given the already computed hash of the
+/// declared class.
+pub fn calculate_declare_transaction_hash_with_class_hash(
+    class_hash: Felt252,
+    chain_id: Felt252,
+    sender_address: &Address,
+    max_fee: u128,
+    version: Felt252,
+    nonce: Felt252,
+) -> Result<Felt252, HashError> {
     let (calldata, additional_data) = if !version.is_zero() {
         (vec![class_hash], vec![nonce])
     } else {
@@ -182,7 +280,8 @@ mod tests {
     use cairo_vm::felt::felt_str;
     use coverage_helper::test;
 
-    use crate::definitions::block_context::StarknetChainId;
+    use crate::definitions::{block_context::StarknetChainId, constants::QUERY_VERSION_BASE};
+    use num_traits::Num;
 
     use super::*;
 
@@ -217,6 +316,74 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn invoke_version_zero_with_non_zero_nonce_should_fail() {
+        let expected_error = preprocess_invoke_function_fields(
+            Felt252::from_str_radix(
+                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                16,
+            )
+            .unwrap(),
+            Some(1.into()),
+            0.into(),
+        )
+        .unwrap_err();
+        assert_matches!(expected_error, TransactionError::InvokeFunctionZeroHasNonce)
+    }
+
+    #[test]
+    fn preprocess_invoke_function_fields_nonce_is_none() {
+        let entry_point_selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let result =
+            preprocess_invoke_function_fields(entry_point_selector.clone(), None, 0.into());
+
+        let expected_additional_data: Vec<Felt252> = Vec::new();
+        let expected_entry_point_selector_field = entry_point_selector;
+        assert_eq!(
+            result.unwrap(),
+            (
+                expected_entry_point_selector_field,
+                expected_additional_data
+            )
+        )
+    }
+
+    #[test]
+    fn invoke_version_one_with_no_nonce_should_fail() {
+        let expected_error = preprocess_invoke_function_fields(
+            Felt252::from_str_radix(
+                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                16,
+            )
+            .unwrap(),
+            None,
+            1.into(),
+        );
+        assert!(expected_error.is_err());
+        assert_matches!(
+            expected_error.unwrap_err(),
+            TransactionError::InvokeFunctionNonZeroMissingNonce
+        )
+    }
+
+    #[test]
+    fn invoke_version_one_with_no_nonce_with_query_base_should_fail() {
+        let expected_error = preprocess_invoke_function_fields(
+            Felt252::from_str_radix(
+                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                16,
+            )
+            .unwrap(),
+            None,
+            &1.into() | &QUERY_VERSION_BASE.clone(),
+        );
+        assert!(expected_error.is_err());
+    }
+
     #[test]
     fn calculate_declare_hash_test() {
         let chain_id = StarknetChainId::MainNet;
@@ -7,16 +7,18 @@ use cairo_vm::felt::Felt252;
 use core::fmt;
 use getset::{CopyGetters, Getters, MutGetters};
 use starknet_api::block::Block;
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use super::constants::{
     DEFAULT_CAIRO_RESOURCE_FEE_WEIGHTS, DEFAULT_CONTRACT_STORAGE_COMMITMENT_TREE_HEIGHT,
     DEFAULT_GLOBAL_STATE_COMMITMENT_TREE_HEIGHT, DEFAULT_INVOKE_TX_MAX_N_STEPS,
-    DEFAULT_SEQUENCER_ADDRESS, DEFAULT_STARKNET_OS_CONFIG, DEFAULT_VALIDATE_MAX_N_STEPS,
+    DEFAULT_KECCAK_CHUNK_SIZE, DEFAULT_KECCAK_ROUND_COST, DEFAULT_MAX_CALLDATA_LENGTH,
+    DEFAULT_SEQUENCER_ADDRESS, DEFAULT_STARKNET_OS_CONFIG, DEFAULT_STRK_FEE_TOKEN_ADDRESS,
+    DEFAULT_VALIDATE_MAX_N_STEPS,
 };
 
 /// Unique identifier of a Starknet chain.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StarknetChainId {
     /// Starknet main chain
     MainNet,
@@ -24,6 +26,10 @@ pub enum StarknetChainId {
     TestNet,
     /// Starknet second test chain (Goerli 2)
     TestNet2,
+    /// Starknet Sepolia test chain
+    Sepolia,
+    /// Any other chain, given its ID as a field element
+    Custom(Felt252),
 }
 
 impl fmt::Display for StarknetChainId {
@@ -32,6 +38,8 @@ impl fmt::Display for StarknetChainId {
             StarknetChainId::MainNet => write!(f, "SN_MAIN"),
             StarknetChainId::TestNet => write!(f, "SN_GOERLI"),
             StarknetChainId::TestNet2 => write!(f, "SN_GOERLI2"),
+            StarknetChainId::Sepolia => write!(f, "SN_SEPOLIA"),
+            StarknetChainId::Custom(chain_id) => write!(f, "{chain_id}"),
         }
     }
 }
@@ -58,23 +66,64 @@ impl StarknetChainId {
     ///     felt_str!("393402129659245999442226"),
     /// );
     /// ```
-    pub fn to_felt(self) -> Felt252 {
-        Felt252::from_bytes_be(self.to_string().as_bytes())
+    pub fn to_felt(&self) -> Felt252 {
+        match self {
+            StarknetChainId::Custom(chain_id) => chain_id.clone(),
+            _ => Felt252::from_bytes_be(self.to_string().as_bytes()),
+        }
     }
 }
 
+/// Determines when the `keccak` syscall checks that there is enough gas to hash its input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeccakGasCheck {
+    /// Check the gas before hashing each chunk, consuming gas for the chunks already hashed.
+    #[default]
+    PerRound,
+    /// Check the gas required for every chunk before hashing anything.
+    Upfront,
+}
+
+/// Token in which a transaction pays its fee.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeeToken {
+    /// Pay with the ETH fee token.
+    #[default]
+    Eth,
+    /// Pay with the STRK fee token.
+    Strk,
+}
+
+/// Outcome forced on the `__validate__` step of account transactions, bypassing the call to
+/// the account contract. Meant for testing account logic without a real signature scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidateHook {
+    /// Accept every transaction without calling `__validate__`.
+    ForceAccept,
+    /// Reject every transaction with [`TransactionError::ValidationRejected`].
+    ///
+    /// [`TransactionError::ValidationRejected`]: crate::transaction::error::TransactionError::ValidationRejected
+    ForceReject,
+}
+
 #[derive(Debug, Clone, Getters, MutGetters)]
 /// Starknet OS configuration.
 pub struct StarknetOsConfig {
     /// ID of the configured chain
     #[getset(get = "pub", get_mut = "pub")]
     pub(crate) chain_id: Felt252,
-    /// Address of the token used when paying fees
+    /// Address of the ETH token used when paying fees
     #[get = "pub"]
     pub(crate) fee_token_address: Address,
-    /// Price of gas
+    /// Address of the STRK token used when paying fees
+    #[getset(get = "pub", get_mut = "pub")]
+    pub(crate) strk_fee_token_address: Address,
+    /// Price of gas in wei, paid by transactions using the ETH fee token
     #[get = "pub"]
     pub(crate) gas_price: u128,
+    /// Price of gas in fri, paid by transactions using the STRK fee token
+    #[get = "pub"]
+    pub(crate) strk_gas_price: u128,
 }
 
 impl StarknetOsConfig {
@@ -83,13 +132,31 @@ impl StarknetOsConfig {
     /// # Arguments
     ///
     /// * `chain_id` - [`Felt252`] of the configured chain.
-    /// * `fee_token_address` - Address of the token used when paying fees.
-    /// * `gas_price` - Price of gas.
+    /// * `fee_token_address` - Address of the ETH token used when paying fees.
+    /// * `gas_price` - Price of gas, used for both fee tokens.
     pub fn new(chain_id: Felt252, fee_token_address: Address, gas_price: u128) -> Self {
         StarknetOsConfig {
             chain_id,
             fee_token_address,
+            strk_fee_token_address: DEFAULT_STRK_FEE_TOKEN_ADDRESS.clone(),
             gas_price,
+            strk_gas_price: gas_price,
+        }
+    }
+
+    /// Returns the address of the token contract used when paying fees in `fee_token`.
+    pub fn fee_token_address_for(&self, fee_token: FeeToken) -> &Address {
+        match fee_token {
+            FeeToken::Eth => &self.fee_token_address,
+            FeeToken::Strk => &self.strk_fee_token_address,
+        }
+    }
+
+    /// Returns the price of gas for transactions paying fees in `fee_token`.
+    pub fn gas_price_for(&self, fee_token: FeeToken) -> u128 {
+        match fee_token {
+            FeeToken::Eth => self.gas_price,
+            FeeToken::Strk => self.strk_gas_price,
         }
     }
 }
@@ -121,6 +188,39 @@ pub struct BlockContext {
     #[getset(get = "pub", get_mut = "pub")]
     pub(crate) blocks: HashMap<u64, Block>,
     pub(crate) enforce_l1_handler_fee: bool,
+    /// When the `keccak` syscall checks its gas, see [`KeccakGasCheck`].
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) keccak_gas_check: KeccakGasCheck,
+    /// Number of 64-bit words absorbed by each round of the `keccak` syscall. Inputs must be a
+    /// multiple of it, and it can't exceed the 25 words of the keccak-f[1600] state.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) keccak_chunk_size: usize,
+    /// Gas charged by the `keccak` syscall for each chunk it absorbs.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) keccak_round_cost: u128,
+    /// Gas charged by the `emit_event` syscall for each event key, on top of its base cost.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) emit_event_key_gas_cost: u128,
+    /// Gas charged by the `emit_event` syscall for each data felt, on top of its base cost.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) emit_event_data_gas_cost: u128,
+    /// Gas charged by the `call_contract` and `library_call` syscalls when the called class
+    /// isn't cached yet, on top of their base cost.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) class_load_gas_cost: u128,
+    /// Maximum calldata length accepted by the `call_contract` and `library_call` syscalls,
+    /// longer calldata makes them fail without reading it.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) max_calldata_length: usize,
+    /// Instant after which running executions are aborted, checked every few VM steps.
+    pub(crate) execution_deadline: Option<Instant>,
+    /// Forced outcome of the validation step, see [`ValidateHook`]. `None` runs `__validate__`.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) validate_hook: Option<ValidateHook>,
+    /// Whether deployments check the constructor calldata length against the constructor
+    /// parameters declared in the class ABI before running the constructor.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) validate_constructor_calldata: bool,
 }
 
 impl BlockContext {
@@ -160,8 +260,70 @@ impl BlockContext {
             block_info,
             blocks,
             enforce_l1_handler_fee,
+            keccak_gas_check: KeccakGasCheck::default(),
+            keccak_chunk_size: DEFAULT_KECCAK_CHUNK_SIZE,
+            keccak_round_cost: DEFAULT_KECCAK_ROUND_COST,
+            emit_event_key_gas_cost: 0,
+            emit_event_data_gas_cost: 0,
+            class_load_gas_cost: 0,
+            max_calldata_length: DEFAULT_MAX_CALLDATA_LENGTH,
+            execution_deadline: None,
+            validate_hook: None,
+            validate_constructor_calldata: false,
         }
     }
+
+    /// Sets the ID of the chain the block belongs to.
+    pub fn with_chain_id(mut self, chain_id: StarknetChainId) -> Self {
+        self.starknet_os_config.chain_id = chain_id.to_felt();
+        self
+    }
+
+    /// Sets the price of gas, in wei, paid by transactions using the ETH fee token.
+    pub fn with_gas_price_wei(mut self, gas_price: u128) -> Self {
+        self.starknet_os_config.gas_price = gas_price;
+        self
+    }
+
+    /// Sets the price of gas, in fri, paid by transactions using the STRK fee token.
+    pub fn with_gas_price_fri(mut self, gas_price: u128) -> Self {
+        self.starknet_os_config.strk_gas_price = gas_price;
+        self
+    }
+
+    /// Sets the maximum number of steps the validation of a transaction may take.
+    pub fn with_validate_max_n_steps(mut self, validate_max_n_steps: u64) -> Self {
+        self.validate_max_n_steps = validate_max_n_steps;
+        self
+    }
+
+    /// Returns the L1 gas charged per Cairo step when computing fees, i.e. the `n_steps` entry
+    /// of `cairo_resource_fee_weights`.
+    pub fn step_gas_cost(&self) -> f64 {
+        self.cairo_resource_fee_weights
+            .get("n_steps")
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the L1 gas charged per Cairo step when computing fees.
+    pub fn with_step_gas_cost(mut self, step_gas_cost: f64) -> Self {
+        self.cairo_resource_fee_weights
+            .insert("n_steps".to_string(), step_gas_cost);
+        self
+    }
+
+    /// Sets whether deployments check the constructor calldata length against the class ABI,
+    /// failing with [`TransactionError::ConstructorArgumentMismatch`] on a mismatch.
+    ///
+    /// [`TransactionError::ConstructorArgumentMismatch`]: crate::transaction::error::TransactionError::ConstructorArgumentMismatch
+    pub fn with_validate_constructor_calldata(
+        mut self,
+        validate_constructor_calldata: bool,
+    ) -> Self {
+        self.validate_constructor_calldata = validate_constructor_calldata;
+        self
+    }
 }
 
 impl Default for BlockContext {
@@ -177,6 +339,16 @@ impl Default for BlockContext {
             block_info: BlockInfo::empty(DEFAULT_SEQUENCER_ADDRESS.clone()),
             blocks: HashMap::default(),
             enforce_l1_handler_fee: true,
+            keccak_gas_check: KeccakGasCheck::default(),
+            keccak_chunk_size: DEFAULT_KECCAK_CHUNK_SIZE,
+            keccak_round_cost: DEFAULT_KECCAK_ROUND_COST,
+            emit_event_key_gas_cost: 0,
+            emit_event_data_gas_cost: 0,
+            class_load_gas_cost: 0,
+            max_calldata_length: DEFAULT_MAX_CALLDATA_LENGTH,
+            execution_deadline: None,
+            validate_hook: None,
+            validate_constructor_calldata: false,
         }
     }
 }
@@ -202,4 +374,26 @@ mod tests {
             felt_str!("393402129659245999442226"),
         );
     }
+
+    #[test]
+    fn sepolia_and_custom_chain_ids_to_felt() {
+        assert_eq!(
+            StarknetChainId::Sepolia.to_felt(),
+            felt_str!("393402133025997798000961"),
+        );
+        assert_eq!(
+            StarknetChainId::Sepolia.to_felt(),
+            Felt252::from_bytes_be(b"SN_SEPOLIA")
+        );
+        assert_eq!(
+            StarknetChainId::Custom(42.into()).to_felt(),
+            Felt252::new(42)
+        );
+
+        let block_context = BlockContext::default().with_chain_id(StarknetChainId::MainNet);
+        assert_eq!(
+            block_context.starknet_os_config().chain_id(),
+            &felt_str!("23448594291968334")
+        );
+    }
 }
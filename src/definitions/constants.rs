@@ -36,6 +36,9 @@ pub(crate) const FEE_FACTOR: u128 = 1;
 /// The (empirical) L1 gas cost of each Cairo step.
 pub(crate) const N_STEPS_FEE_WEIGHT: f64 = 0.01;
 
+/// Number of past blocks whose hashes are kept in `BlockInfo::recent_block_hashes`.
+pub const RECENT_BLOCK_HASHES_WINDOW: u64 = 1024;
+
 /// The version is considered 0 for L1-Handler transaction hash calculation purposes.
 pub(crate) const L1_HANDLER_VERSION: u64 = 0;
 
@@ -69,13 +72,19 @@ lazy_static! {
         "3711666a3506c99c9d78c4d4013409a87a962b7a0880a1c24af9fe193dafc01",
         16
     ));
+    pub static ref DEFAULT_STRK_FEE_TOKEN_ADDRESS: Address = Address(felt_str!(
+        "4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d",
+        16
+    ));
     pub static ref DEFAULT_STARKNET_OS_CONFIG: StarknetOsConfig = StarknetOsConfig {
         chain_id: StarknetChainId::TestNet.to_felt(),
         fee_token_address: Address(felt_str!(
             "4c07059285c2607d528a4c5220ef1f64d8f01273c23cfd9dec68759f61b544",
             16
         )),
+        strk_fee_token_address: DEFAULT_STRK_FEE_TOKEN_ADDRESS.clone(),
         gas_price: 0,
+        strk_gas_price: 0,
     };
 
 pub static ref DECLARE_VERSION: Felt252 = 2.into();
@@ -87,6 +96,9 @@ pub const DEFAULT_CONTRACT_STORAGE_COMMITMENT_TREE_HEIGHT: u64 = 251;
 pub const DEFAULT_GLOBAL_STATE_COMMITMENT_TREE_HEIGHT: u64 = 251;
 pub const DEFAULT_INVOKE_TX_MAX_N_STEPS: u64 = 1000000;
 pub const DEFAULT_VALIDATE_MAX_N_STEPS: u64 = 1000000;
+pub const DEFAULT_KECCAK_CHUNK_SIZE: usize = 17;
+pub const DEFAULT_KECCAK_ROUND_COST: u128 = 180000;
+pub const DEFAULT_MAX_CALLDATA_LENGTH: usize = 100_000;
 
 // Gas Cost.
 // From cairo_programs/constants.cairo.
@@ -18,7 +18,7 @@ use crate::{
         business_logic_syscall_handler::BusinessLogicSyscallHandler,
         deprecated_business_logic_syscall_handler::DeprecatedBLSyscallHandler,
         deprecated_syscall_handler::DeprecatedSyscallHintProcessor,
-        syscall_handler::SyscallHintProcessor,
+        syscall_handler::SyscallHintProcessor, syscall_trace::SyscallTrace,
     },
     transaction::error::TransactionError,
     utils::{
@@ -48,6 +48,29 @@ pub struct ExecutionResult {
     pub call_info: Option<CallInfo>,
     pub revert_error: Option<String>,
     pub n_reverted_steps: usize,
+    /// Gas left when the execution was halted by its [`StepLimit`]. `call_info` then holds the
+    /// call up to that point and the state is left untouched.
+    pub halted_remaining_gas: Option<u128>,
+}
+
+/// Point at which [`ExecutionEntryPoint::execute_with_step_limit`] halts a Cairo 1 execution.
+/// Only the syscalls performed by the executed entry point itself are considered, a call to
+/// another contract counts as a single syscall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepLimit {
+    /// Halts once this many syscalls were performed.
+    Syscalls(usize),
+    /// Halts after the first syscall leaving at most this much gas.
+    Gas(u128),
+}
+
+impl StepLimit {
+    pub(crate) fn is_reached(&self, n_syscalls: usize, remaining_gas: u128) -> bool {
+        match self {
+            StepLimit::Syscalls(max_syscalls) => n_syscalls >= *max_syscalls,
+            StepLimit::Gas(gas_threshold) => remaining_gas <= *gas_threshold,
+        }
+    }
 }
 
 /// Represents a Cairo entry point execution of a StarkNet contract.
@@ -104,6 +127,62 @@ impl ExecutionEntryPoint {
         max_steps: u64,
         enable_trace: bool,
     ) -> Result<ExecutionResult, TransactionError>
+    where
+        T: StateReader,
+    {
+        self.execute_with_optional_step_limit(
+            state,
+            block_context,
+            resources_manager,
+            tx_execution_context,
+            support_reverted,
+            max_steps,
+            enable_trace,
+            None,
+        )
+    }
+
+    /// Executes the entry point like [`ExecutionEntryPoint::execute`], halting it right after the
+    /// syscall that reaches `step_limit`. A halted execution returns the partial call info and
+    /// the remaining gas in [`ExecutionResult::halted_remaining_gas`], without applying its
+    /// changes to `state`. The limit is ignored by Cairo 0 classes.
+    pub fn execute_with_step_limit<T>(
+        &self,
+        state: &mut CachedState<T>,
+        block_context: &BlockContext,
+        resources_manager: &mut ExecutionResourcesManager,
+        tx_execution_context: &mut TransactionExecutionContext,
+        support_reverted: bool,
+        max_steps: u64,
+        enable_trace: bool,
+        step_limit: StepLimit,
+    ) -> Result<ExecutionResult, TransactionError>
+    where
+        T: StateReader,
+    {
+        self.execute_with_optional_step_limit(
+            state,
+            block_context,
+            resources_manager,
+            tx_execution_context,
+            support_reverted,
+            max_steps,
+            enable_trace,
+            Some(step_limit),
+        )
+    }
+
+    fn execute_with_optional_step_limit<T>(
+        &self,
+        state: &mut CachedState<T>,
+        block_context: &BlockContext,
+        resources_manager: &mut ExecutionResourcesManager,
+        tx_execution_context: &mut TransactionExecutionContext,
+        support_reverted: bool,
+        max_steps: u64,
+        enable_trace: bool,
+        step_limit: Option<StepLimit>,
+    ) -> Result<ExecutionResult, TransactionError>
     where
         T: StateReader,
     {
@@ -127,6 +206,7 @@ impl ExecutionEntryPoint {
                     call_info: Some(call_info),
                     revert_error: None,
                     n_reverted_steps: 0,
+                    halted_remaining_gas: None,
                 })
             }
             CompiledClass::Casm(contract_class) => {
@@ -146,14 +226,26 @@ impl ExecutionEntryPoint {
                     class_hash,
                     support_reverted,
                     enable_trace,
+                    step_limit,
                 ) {
-                    Ok(call_info) => {
-                        let state_diff = StateDiff::from_cached_state(tmp_state)?;
-                        state.apply_state_update(&state_diff)?;
+                    Ok((call_info, Some(halted_remaining_gas))) => Ok(ExecutionResult {
+                        call_info: Some(call_info),
+                        revert_error: None,
+                        n_reverted_steps: 0,
+                        halted_remaining_gas: Some(halted_remaining_gas),
+                    }),
+                    Ok((call_info, None)) => {
+                        // A failed call leaves the state untouched, including the contracts
+                        // it deployed before failing.
+                        if !call_info.failure_flag {
+                            let state_diff = StateDiff::from_cached_state(tmp_state)?;
+                            state.apply_state_update(&state_diff)?;
+                        }
                         Ok(ExecutionResult {
                             call_info: Some(call_info),
                             revert_error: None,
                             n_reverted_steps: 0,
+                            halted_remaining_gas: None,
                         })
                     }
                     Err(e) => {
@@ -167,6 +259,7 @@ impl ExecutionEntryPoint {
                             call_info: None,
                             revert_error: Some(e.to_string()),
                             n_reverted_steps,
+                            halted_remaining_gas: None,
                         })
                     }
                 }
@@ -265,10 +358,14 @@ impl ExecutionEntryPoint {
             l2_to_l1_messages,
             storage_read_values: starknet_storage_state.read_values,
             accessed_storage_keys: starknet_storage_state.accessed_keys,
+            storage_writes: starknet_storage_state.written_values,
             internal_calls,
             failure_flag: false,
             gas_consumed: 0,
             trace: vec![],
+            syscall_trace: None,
+            deploy_from_zero: None,
+            out_of_gas_syscall: None,
         })
     }
 
@@ -281,6 +378,8 @@ impl ExecutionEntryPoint {
         l2_to_l1_messages: Vec<OrderedL2ToL1Message>,
         internal_calls: Vec<CallInfo>,
         call_result: CallResult,
+        syscall_trace: Option<SyscallTrace>,
+        out_of_gas_syscall: Option<String>,
     ) -> Result<CallInfo, TransactionError> {
         let execution_resources = &resources_manager.cairo_usage - &previous_cairo_usage;
 
@@ -303,10 +402,14 @@ impl ExecutionEntryPoint {
             l2_to_l1_messages,
             storage_read_values: starknet_storage_state.read_values,
             accessed_storage_keys: starknet_storage_state.accessed_keys,
+            storage_writes: starknet_storage_state.written_values,
             internal_calls,
             failure_flag: !call_result.is_success,
             gas_consumed: call_result.gas_consumed,
             trace: vec![],
+            syscall_trace,
+            deploy_from_zero: None,
+            out_of_gas_syscall,
         })
     }
 
@@ -448,7 +551,8 @@ impl ExecutionEntryPoint {
         class_hash: [u8; 32],
         support_reverted: bool,
         enable_trace: bool,
-    ) -> Result<CallInfo, TransactionError> {
+        step_limit: Option<StepLimit>,
+    ) -> Result<(CallInfo, Option<u128>), TransactionError> {
         let previous_cairo_usage = resources_manager.cairo_usage.clone();
 
         // fetch selected entry point
@@ -479,7 +583,7 @@ impl ExecutionEntryPoint {
             _ => return Err(TransactionError::NotARelocatableValue),
         };
 
-        let syscall_handler = BusinessLogicSyscallHandler::new(
+        let mut syscall_handler = BusinessLogicSyscallHandler::new(
             tx_execution_context.clone(),
             state,
             resources_manager.clone(),
@@ -490,6 +594,10 @@ impl ExecutionEntryPoint {
             support_reverted,
             self.entry_point_selector.clone(),
         );
+        if enable_trace {
+            syscall_handler.syscall_trace = Some(SyscallTrace::default());
+        }
+        syscall_handler.step_limit = step_limit;
         // create and attach a syscall hint processor to the starknet runner.
         let hint_processor = SyscallHintProcessor::new(
             syscall_handler,
@@ -538,11 +646,42 @@ impl ExecutionEntryPoint {
         let ref_vec: Vec<&CairoArg> = entrypoint_args.iter().collect();
 
         // run the Cairo1 entrypoint
-        runner.run_from_entrypoint(
+        let run_result = runner.run_from_entrypoint(
             entry_point.offset,
             &ref_vec,
             Some(program.data_len() + program_extra_data.len()),
-        )?;
+        );
+
+        // The handler aborts the run when the step limit is reached, keep what ran so far.
+        if let Some(remaining_gas) = runner.hint_processor.syscall_handler.halted_remaining_gas {
+            // The aborted run didn't compute the segment sizes its resources are read from.
+            runner.vm.segments.compute_effective_sizes();
+            let execution_resources = runner.get_execution_resources()?;
+
+            let syscall_handler = runner.hint_processor.syscall_handler;
+            *resources_manager = syscall_handler.resources_manager.clone();
+            *tx_execution_context = syscall_handler.tx_execution_context.clone();
+            resources_manager.cairo_usage += &execution_resources;
+
+            let call_result = CallResult {
+                gas_consumed: self.initial_gas.saturating_sub(remaining_gas),
+                is_success: true,
+                retdata: Vec::new(),
+            };
+            let call_info = self.build_call_info::<S>(
+                previous_cairo_usage,
+                resources_manager,
+                syscall_handler.starknet_storage_state,
+                syscall_handler.events,
+                syscall_handler.l2_to_l1_messages,
+                syscall_handler.internal_calls,
+                call_result,
+                syscall_handler.syscall_trace,
+                syscall_handler.out_of_gas_syscall,
+            )?;
+            return Ok((call_info, Some(remaining_gas)));
+        }
+        run_result?;
 
         runner
             .vm
@@ -578,7 +717,7 @@ impl ExecutionEntryPoint {
         resources_manager.cairo_usage += &runner.get_execution_resources()?;
 
         let call_result = runner.get_call_result(self.initial_gas)?;
-        self.build_call_info::<S>(
+        let call_info = self.build_call_info::<S>(
             previous_cairo_usage,
             resources_manager,
             runner.hint_processor.syscall_handler.starknet_storage_state,
@@ -586,6 +725,165 @@ impl ExecutionEntryPoint {
             runner.hint_processor.syscall_handler.l2_to_l1_messages,
             runner.hint_processor.syscall_handler.internal_calls,
             call_result,
-        )
+            runner.hint_processor.syscall_handler.syscall_trace,
+            runner.hint_processor.syscall_handler.out_of_gas_syscall,
+        )?;
+        Ok((call_info, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::constants::TRANSACTION_VERSION,
+        execution::{CallType, StorageWrite},
+        state::in_memory_state_reader::InMemoryStateReader,
+        utils::{calculate_sn_keccak, ClassHash},
+    };
+    use num_traits::Zero;
+    use std::collections::HashMap;
+
+    #[test]
+    fn execute_with_step_limit_halts_after_the_storage_write() {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/events.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        let address = Address(1111.into());
+        let class_hash: ClassHash = [1; 32];
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(address.clone(), class_hash);
+        state_reader
+            .address_to_nonce_mut()
+            .insert(address.clone(), Felt252::zero());
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+
+        let exec_entry_point = ExecutionEntryPoint::new(
+            address,
+            vec![1.into()],
+            Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+            Address(0.into()),
+            EntryPointType::External,
+            Some(CallType::Delegate),
+            Some(class_hash),
+            100000,
+        );
+        let block_context = BlockContext::default();
+        let mut tx_execution_context = TransactionExecutionContext::new(
+            Address(0.into()),
+            Felt252::zero(),
+            Vec::new(),
+            0,
+            10.into(),
+            block_context.invoke_tx_max_n_steps(),
+            TRANSACTION_VERSION.clone(),
+        );
+
+        // emit_event(incremental: true) reads the counter, emits it, reads it again and
+        // writes it back incremented: its fourth syscall is the storage write.
+        let mut resources_manager = ExecutionResourcesManager::default();
+        let result = exec_entry_point
+            .execute_with_step_limit(
+                &mut state,
+                &block_context,
+                &mut resources_manager,
+                &mut tx_execution_context,
+                false,
+                block_context.invoke_tx_max_n_steps(),
+                false,
+                StepLimit::Syscalls(4),
+            )
+            .unwrap();
+
+        let remaining_gas = result.halted_remaining_gas.unwrap();
+        let call_info = result.call_info.unwrap();
+        assert_eq!(
+            call_info.storage_writes,
+            vec![StorageWrite::new(
+                calculate_sn_keccak(b"value"),
+                Felt252::from(1),
+                0
+            )]
+        );
+        assert_eq!(call_info.events.len(), 1);
+        assert_eq!(call_info.gas_consumed, 100000 - remaining_gas);
+        // The steps run before halting are accounted for
+        assert!(call_info.execution_resources.n_steps > 0);
+        assert_eq!(resources_manager.cairo_usage, call_info.execution_resources);
+        // The halted execution isn't applied to the state
+        assert!(state.cache.storage_writes.is_empty());
+    }
+
+    #[test]
+    fn failed_call_discards_its_deployments() {
+        let deployer_class: CasmContractClass =
+            serde_json::from_slice(include_bytes!("../../starknet_programs/cairo2/deploy.casm"))
+                .unwrap();
+        let failing_class: CasmContractClass = serde_json::from_slice(include_bytes!(
+            "../../starknet_programs/cairo2/failing_constructor.casm"
+        ))
+        .unwrap();
+
+        let address = Address(1111.into());
+        let deployer_class_hash: ClassHash = [1; 32];
+        let failing_class_hash: ClassHash = [2; 32];
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(address.clone(), deployer_class_hash);
+        state_reader
+            .address_to_nonce_mut()
+            .insert(address.clone(), Felt252::zero());
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([
+                (deployer_class_hash, deployer_class),
+                (failing_class_hash, failing_class),
+            ])),
+        );
+
+        // deploy_test registers the child, whose constructor fails, and then panics
+        let exec_entry_point = ExecutionEntryPoint::new(
+            address,
+            vec![Felt252::from_bytes_be(&failing_class_hash), 1.into()],
+            Felt252::from_bytes_be(&calculate_sn_keccak(b"deploy_test")),
+            Address(0.into()),
+            EntryPointType::External,
+            Some(CallType::Delegate),
+            Some(deployer_class_hash),
+            1_000_000,
+        );
+        let block_context = BlockContext::default();
+        let mut tx_execution_context = TransactionExecutionContext::new(
+            Address(0.into()),
+            Felt252::zero(),
+            Vec::new(),
+            0,
+            10.into(),
+            block_context.invoke_tx_max_n_steps(),
+            TRANSACTION_VERSION.clone(),
+        );
+
+        let result = exec_entry_point
+            .execute(
+                &mut state,
+                &block_context,
+                &mut ExecutionResourcesManager::default(),
+                &mut tx_execution_context,
+                true,
+                block_context.invoke_tx_max_n_steps(),
+                false,
+            )
+            .unwrap();
+
+        assert!(result.call_info.unwrap().failure_flag);
+        assert!(state.cache.class_hash_writes.is_empty());
     }
 }
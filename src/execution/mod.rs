@@ -2,13 +2,22 @@ pub mod execution_entry_point;
 pub mod gas_usage;
 pub mod os_usage;
 
+use crate::definitions::block_context::FeeToken;
 use crate::definitions::constants::QUERY_VERSION_BASE;
 use crate::services::api::contract_classes::deprecated_contract_class::EntryPointType;
 use crate::utils::parse_felt_array;
 use crate::{
-    definitions::{constants::CONSTRUCTOR_ENTRY_POINT_SELECTOR, transaction_type::TransactionType},
-    state::state_cache::StorageEntry,
-    syscalls::syscall_handler_errors::SyscallHandlerError,
+    definitions::{
+        constants::{CONSTRUCTOR_ENTRY_POINT_SELECTOR, STEP_GAS_COST},
+        transaction_type::TransactionType,
+    },
+    state::{state_api::StateReader, state_cache::StorageEntry},
+    syscalls::{
+        business_logic_syscall_handler::BusinessLogicSyscallHandler,
+        syscall_handler_errors::SyscallHandlerError,
+        syscall_response::{FailureReason, ResponseBody},
+        syscall_trace::SyscallTrace,
+    },
     transaction::error::TransactionError,
     utils::{get_big_int, get_integer, get_relocatable, Address, ClassHash},
 };
@@ -20,7 +29,7 @@ use cairo_vm::{
 use getset::Getters;
 use num_traits::{ToPrimitive, Zero};
 use serde::{Deserialize, Deserializer};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CallType {
@@ -48,10 +57,21 @@ pub struct CallInfo {
     pub l2_to_l1_messages: Vec<OrderedL2ToL1Message>,
     pub storage_read_values: Vec<Felt252>,
     pub accessed_storage_keys: HashSet<ClassHash>,
+    /// Storage writes performed by this call (excluding internal calls), in chronological order.
+    pub storage_writes: Vec<StorageWrite>,
     pub internal_calls: Vec<CallInfo>,
     pub gas_consumed: u128,
     pub failure_flag: bool,
     pub trace: Vec<(u32, u32)>,
+    /// Syscalls performed by this call (excluding internal calls), only collected for
+    /// Cairo 1 calls executed with tracing enabled.
+    pub syscall_trace: Option<SyscallTrace>,
+    /// Only set on constructor calls triggered by a deploy syscall: whether the deployment
+    /// used `deploy_from_zero` (zero deployer address) instead of the caller's address.
+    pub deploy_from_zero: Option<bool>,
+    /// Name of the first syscall performed by this call (excluding internal calls) that failed
+    /// for running out of gas.
+    pub out_of_gas_syscall: Option<String>,
 }
 
 impl CallInfo {
@@ -83,10 +103,14 @@ impl CallInfo {
             l2_to_l1_messages: Vec::new(),
             storage_read_values: Vec::new(),
             accessed_storage_keys: HashSet::new(),
+            storage_writes: Vec::new(),
             internal_calls: Vec::new(),
             gas_consumed: 0,
             failure_flag: false,
             trace: vec![],
+            syscall_trace: None,
+            deploy_from_zero: None,
+            out_of_gas_syscall: None,
         }
     }
 
@@ -120,6 +144,37 @@ impl CallInfo {
         calls
     }
 
+    /// Records the storage writes of this call and its internal calls into `storage_updates`
+    /// in execution order, skipping failed calls along with their internal calls.
+    fn apply_storage_writes(
+        &self,
+        storage_updates: &mut BTreeMap<Address, BTreeMap<ClassHash, Felt252>>,
+    ) {
+        if self.failure_flag {
+            return;
+        }
+
+        let mut internal_calls = self.internal_calls.iter();
+        let mut n_applied_calls = 0;
+        for write in &self.storage_writes {
+            // Internal calls performed before the write are applied first
+            for call in internal_calls
+                .by_ref()
+                .take(write.n_preceding_calls.saturating_sub(n_applied_calls))
+            {
+                call.apply_storage_writes(storage_updates);
+                n_applied_calls += 1;
+            }
+            storage_updates
+                .entry(self.contract_address.clone())
+                .or_default()
+                .insert(write.key, write.value.clone());
+        }
+        for call in internal_calls {
+            call.apply_storage_writes(storage_updates);
+        }
+    }
+
     /// Returns a list of Starknet Event objects collected during the execution, sorted by the order
     /// in which they were emitted.
     pub fn get_sorted_events(&self) -> Result<Vec<Event>, TransactionError> {
@@ -206,6 +261,38 @@ impl CallInfo {
             retdata: self.retdata.iter().map(|f| f.into()).collect(),
         }
     }
+
+    /// Returns the builtin instance counters of the call sorted by builtin name, unlike the
+    /// `HashMap` in `execution_resources` whose iteration order changes between runs.
+    pub fn sorted_builtin_instance_counter(&self) -> BTreeMap<String, usize> {
+        self.execution_resources
+            .builtin_instance_counter
+            .iter()
+            .map(|(builtin, count)| (builtin.clone(), *count))
+            .collect()
+    }
+
+    /// Compares two calls ignoring their execution resources and those of their internal calls,
+    /// which change whenever the VM or the compiler do.
+    pub fn semantically_eq(&self, other: &CallInfo) -> bool {
+        self.without_execution_resources() == other.without_execution_resources()
+    }
+
+    fn without_execution_resources(&self) -> CallInfo {
+        CallInfo {
+            execution_resources: ExecutionResources {
+                n_steps: 0,
+                n_memory_holes: 0,
+                builtin_instance_counter: HashMap::new(),
+            },
+            internal_calls: self
+                .internal_calls
+                .iter()
+                .map(CallInfo::without_execution_resources)
+                .collect(),
+            ..self.clone()
+        }
+    }
 }
 
 impl Default for CallInfo {
@@ -223,6 +310,7 @@ impl Default for CallInfo {
             entry_point_selector: None,
             l2_to_l1_messages: Vec::new(),
             accessed_storage_keys: HashSet::new(),
+            storage_writes: Vec::new(),
             calldata: Vec::new(),
             execution_resources: ExecutionResources {
                 n_steps: 0,
@@ -233,6 +321,9 @@ impl Default for CallInfo {
             gas_consumed: 0,
             failure_flag: false,
             trace: vec![],
+            syscall_trace: None,
+            deploy_from_zero: None,
+            out_of_gas_syscall: None,
         }
     }
 }
@@ -297,6 +388,31 @@ pub struct CallResult {
     pub retdata: Vec<MaybeRelocatable>,
 }
 
+impl CallResult {
+    /// Writes the retdata to a new read-only segment of `syscall_handler` and builds the
+    /// syscall response body pointing to it: the one returned by `success_body` if the call
+    /// succeeded, a `Failure` otherwise.
+    pub(crate) fn into_response_body<S: StateReader>(
+        self,
+        syscall_handler: &mut BusinessLogicSyscallHandler<'_, S>,
+        vm: &mut VirtualMachine,
+        success_body: impl FnOnce(Relocatable, Relocatable) -> ResponseBody,
+    ) -> Result<ResponseBody, SyscallHandlerError> {
+        let retdata_len = self.retdata.len();
+        let retdata_start = syscall_handler.allocate_segment(vm, self.retdata)?;
+        let retdata_end = (retdata_start + retdata_len)?;
+
+        Ok(if self.is_success {
+            success_body(retdata_start, retdata_end)
+        } else {
+            ResponseBody::Failure(FailureReason {
+                retdata_start,
+                retdata_end,
+            })
+        })
+    }
+}
+
 impl From<CallInfo> for CallResult {
     fn from(info: CallInfo) -> Self {
         Self {
@@ -324,6 +440,26 @@ impl OrderedEvent {
     }
 }
 
+/// A storage write performed by a call, see [`CallInfo::storage_writes`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageWrite {
+    pub key: [u8; 32],
+    pub value: Felt252,
+    /// Number of internal calls the writing call had performed before this write, which places
+    /// the write among the writes of those calls.
+    pub n_preceding_calls: usize,
+}
+
+impl StorageWrite {
+    pub fn new(key: ClassHash, value: Felt252, n_preceding_calls: usize) -> Self {
+        StorageWrite {
+            key,
+            value,
+            n_preceding_calls,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Event {
     pub from_address: Address,
@@ -357,6 +493,7 @@ pub struct TransactionExecutionContext {
     pub(crate) nonce: Felt252,
     pub(crate) n_sent_messages: usize,
     pub(crate) _n_steps: u64,
+    pub(crate) fee_token: FeeToken,
 }
 
 impl TransactionExecutionContext {
@@ -385,6 +522,7 @@ impl TransactionExecutionContext {
             version,
             n_sent_messages: 0,
             _n_steps: n_steps,
+            fee_token: FeeToken::Eth,
         }
     }
 
@@ -405,6 +543,7 @@ impl TransactionExecutionContext {
             nonce,
             n_sent_messages: 0,
             _n_steps: n_steps,
+            fee_token: FeeToken::Eth,
         }
     }
 }
@@ -458,19 +597,15 @@ impl TxInfoStruct {
     ) -> Result<TxInfoStruct, SyscallHandlerError> {
         let version = get_big_int(vm, tx_info_ptr)?;
 
-        let account_contract_address = Address(get_big_int(vm, &tx_info_ptr + 1)?);
-        let max_fee =
-            get_big_int(vm, &tx_info_ptr + 2)?
-                .to_u128()
-                .ok_or(SyscallHandlerError::Conversion(
-                    "Felt252".to_string(),
-                    "u128".to_string(),
-                ))?;
-        let signature_len = get_integer(vm, &tx_info_ptr + 3)?;
-        let signature = get_relocatable(vm, &tx_info_ptr + 4)?;
-        let transaction_hash = get_big_int(vm, &tx_info_ptr + 5)?;
-        let chain_id = get_big_int(vm, &tx_info_ptr + 6)?;
-        let nonce = get_big_int(vm, &tx_info_ptr + 7)?;
+        let account_contract_address = Address(get_big_int(vm, (tx_info_ptr + 1)?)?);
+        let max_fee = get_big_int(vm, (tx_info_ptr + 2)?)?.to_u128().ok_or(
+            SyscallHandlerError::Conversion("Felt252".to_string(), "u128".to_string()),
+        )?;
+        let signature_len = get_integer(vm, (tx_info_ptr + 3)?)?;
+        let signature = get_relocatable(vm, (tx_info_ptr + 4)?)?;
+        let transaction_hash = get_big_int(vm, (tx_info_ptr + 5)?)?;
+        let chain_id = get_big_int(vm, (tx_info_ptr + 6)?)?;
+        let nonce = get_big_int(vm, (tx_info_ptr + 7)?)?;
 
         Ok(TxInfoStruct {
             version,
@@ -494,6 +629,16 @@ pub struct TransactionExecutionInfo {
     pub actual_fee: u128,
     pub actual_resources: HashMap<String, usize>,
     pub tx_type: Option<TransactionType>,
+    /// Nonce of the transaction's sender before and after its execution. Only recorded by
+    /// `StarknetState::execute_tx`, and only for transactions with an L2 sender.
+    pub sender_nonce_before: Option<Felt252>,
+    pub sender_nonce_after: Option<Felt252>,
+    /// Cairo gas consumed by the validate and execute calls, plus the `fee_transfer_gas`. Each
+    /// call's figure already includes its inner calls. Computed when the info is built and when the fee info
+    /// is set.
+    pub total_gas_consumed: u128,
+    /// Number of times each syscall was invoked during the transaction, keyed by syscall name.
+    pub syscall_counter: HashMap<String, usize>,
 }
 
 impl TransactionExecutionInfo {
@@ -506,7 +651,7 @@ impl TransactionExecutionInfo {
         actual_resources: HashMap<String, usize>,
         tx_type: Option<TransactionType>,
     ) -> Self {
-        TransactionExecutionInfo {
+        let mut tx_info = TransactionExecutionInfo {
             validate_info,
             call_info,
             revert_error,
@@ -514,7 +659,13 @@ impl TransactionExecutionInfo {
             actual_fee,
             actual_resources,
             tx_type,
-        }
+            sender_nonce_before: None,
+            sender_nonce_after: None,
+            total_gas_consumed: 0,
+            syscall_counter: HashMap::new(),
+        };
+        tx_info.total_gas_consumed = tx_info.calls_gas_consumed();
+        tx_info
     }
 
     // In deploy account tx, validation will take place after execution of the constructor.
@@ -545,7 +696,7 @@ impl TransactionExecutionInfo {
         validate_info: Option<CallInfo>,
         fee_transfer_info: Option<CallInfo>,
     ) -> Self {
-        TransactionExecutionInfo {
+        let mut tx_info = TransactionExecutionInfo {
             validate_info,
             call_info: execute_call_info,
             revert_error: None,
@@ -553,7 +704,13 @@ impl TransactionExecutionInfo {
             actual_fee: 0,
             actual_resources: HashMap::new(),
             tx_type,
-        }
+            sender_nonce_before: None,
+            sender_nonce_after: None,
+            total_gas_consumed: 0,
+            syscall_counter: HashMap::new(),
+        };
+        tx_info.total_gas_consumed = tx_info.calls_gas_consumed();
+        tx_info
     }
 
     pub fn new_without_fee_info(
@@ -563,7 +720,7 @@ impl TransactionExecutionInfo {
         actual_resources: HashMap<String, usize>,
         tx_type: Option<TransactionType>,
     ) -> Self {
-        TransactionExecutionInfo {
+        let mut tx_info = TransactionExecutionInfo {
             validate_info,
             call_info,
             revert_error,
@@ -571,12 +728,67 @@ impl TransactionExecutionInfo {
             actual_fee: 0,
             actual_resources,
             tx_type,
-        }
+            sender_nonce_before: None,
+            sender_nonce_after: None,
+            total_gas_consumed: 0,
+            syscall_counter: HashMap::new(),
+        };
+        tx_info.total_gas_consumed = tx_info.calls_gas_consumed();
+        tx_info
+    }
+
+    /// Sets the number of times each syscall was invoked, see
+    /// [`ExecutionResourcesManager::syscall_invocations`].
+    ///
+    /// [`ExecutionResourcesManager::syscall_invocations`]: crate::state::ExecutionResourcesManager::syscall_invocations
+    pub fn with_syscall_counter(mut self, syscall_counter: HashMap<String, usize>) -> Self {
+        self.syscall_counter = syscall_counter;
+        self
     }
 
     pub fn set_fee_info(&mut self, actual_fee: u128, fee_transfer_call_info: Option<CallInfo>) {
         self.actual_fee = actual_fee;
         self.fee_transfer_info = fee_transfer_call_info;
+        self.total_gas_consumed = self.calls_gas_consumed();
+    }
+
+    /// Sums the Cairo gas consumed by the top-level calls, which already includes their inner
+    /// calls.
+    fn calls_gas_consumed(&self) -> u128 {
+        let execution_gas: u128 = [&self.validate_info, &self.call_info]
+            .into_iter()
+            .flatten()
+            .map(|call_info| call_info.gas_consumed)
+            .sum();
+        execution_gas + self.fee_transfer_gas()
+    }
+
+    /// Returns the Cairo gas consumed by the fee transfer call, zero if there was none.
+    /// Cairo 0 fee tokens don't meter gas, so their transfer is charged `STEP_GAS_COST` for each
+    /// step it ran. It's part of `total_gas_consumed`.
+    pub fn fee_transfer_gas(&self) -> u128 {
+        self.fee_transfer_info.as_ref().map_or(0, |call_info| {
+            if call_info.gas_consumed == 0 {
+                call_info.execution_resources.n_steps as u128 * STEP_GAS_COST
+            } else {
+                call_info.gas_consumed
+            }
+        })
+    }
+
+    /// Returns the panic data of the top-level call if it failed, as the raw felts returned by
+    /// the Cairo 1 contract. `revert_error` only keeps a description of other execution errors.
+    pub fn revert_reason(&self) -> Option<Vec<Felt252>> {
+        self.call_info
+            .as_ref()
+            .filter(|call_info| call_info.failure_flag)
+            .map(|call_info| call_info.retdata.clone())
+    }
+
+    /// Returns true if the execution failed, either with an error described by `revert_error`
+    /// or with a panic whose data is returned by `revert_reason`.
+    pub fn is_reverted(&self) -> bool {
+        self.revert_error.is_some() || self.revert_reason().is_some()
     }
 
     pub fn get_visited_storage_entries_of_many(
@@ -589,6 +801,44 @@ impl TransactionExecutionInfo {
         })
     }
 
+    /// Returns the storage writes performed across the whole call tree, grouped by contract
+    /// address. Both addresses and keys are sorted; if a key was written more than once, the
+    /// value of the last write in execution order is kept. Writes of failed calls, including
+    /// the ones of their internal calls, are left out as they were discarded.
+    pub fn storage_updates_by_contract(&self) -> BTreeMap<Address, BTreeMap<ClassHash, Felt252>> {
+        let mut storage_updates = BTreeMap::new();
+
+        for call in self.non_optional_calls() {
+            call.apply_storage_writes(&mut storage_updates);
+        }
+
+        storage_updates
+    }
+
+    /// Returns, for every call in the call tree, the contract whose storage was used paired
+    /// with the class whose code was executed, in call topology order and without duplicates.
+    /// Delegate calls (e.g. library calls) run foreign code against the caller's storage, so a
+    /// storage contract listed with more than one class hash executed code other than its own.
+    pub fn code_vs_storage_map(&self) -> Vec<(Address, ClassHash)> {
+        let mut code_vs_storage: Vec<(Address, ClassHash)> = Vec::new();
+
+        for call in self
+            .non_optional_calls()
+            .iter()
+            .flat_map(CallInfo::gen_call_topology)
+        {
+            let Some(class_hash) = call.class_hash else {
+                continue;
+            };
+            let entry = (call.contract_address, class_hash);
+            if !code_vs_storage.contains(&entry) {
+                code_vs_storage.push(entry);
+            }
+        }
+
+        code_vs_storage
+    }
+
     pub fn get_sorted_events(&self) -> Result<Vec<Event>, TransactionError> {
         let calls = self.non_optional_calls();
         let mut sorted_events: Vec<Event> = Vec::new();
@@ -612,6 +862,108 @@ impl TransactionExecutionInfo {
 
         Ok(sorted_messages)
     }
+
+    /// Returns the storage slots written by code of more than one class, which happens when a
+    /// proxy delegates to implementations whose storage layouts overlap. Slots are sorted by
+    /// contract address and key, and their writes listed in call topology order.
+    pub fn storage_layout_conflicts(&self) -> Vec<SlotConflict> {
+        let mut writes_by_slot: BTreeMap<StorageEntry, Vec<(ClassHash, Felt252)>> = BTreeMap::new();
+
+        for call in self
+            .non_optional_calls()
+            .iter()
+            .flat_map(CallInfo::gen_call_topology)
+        {
+            let Some(class_hash) = call.class_hash else {
+                continue;
+            };
+            for StorageWrite { key, value, .. } in call.storage_writes {
+                writes_by_slot
+                    .entry((call.contract_address.clone(), key))
+                    .or_default()
+                    .push((class_hash, value));
+            }
+        }
+
+        writes_by_slot
+            .into_iter()
+            .filter(|(_, writes)| {
+                writes
+                    .iter()
+                    .any(|(class_hash, _)| *class_hash != writes[0].0)
+            })
+            .map(|((contract_address, key), writes)| SlotConflict {
+                contract_address,
+                key,
+                writes,
+            })
+            .collect()
+    }
+
+    /// Returns true if any syscall in the call tree failed for running out of gas, even when
+    /// the calling contract handled the failure.
+    pub fn had_out_of_gas_syscall(&self) -> bool {
+        self.non_optional_calls()
+            .iter()
+            .flat_map(CallInfo::gen_call_topology)
+            .any(|call| call.out_of_gas_syscall.is_some())
+    }
+
+    /// Returns a [`TransactionError::OutOfGas`] naming the first syscall in the call tree that
+    /// failed for running out of gas, if any. The failure was returned to the contract, so it
+    /// doesn't fail the transaction by itself.
+    pub fn out_of_gas_error(&self) -> Option<TransactionError> {
+        self.non_optional_calls()
+            .iter()
+            .flat_map(CallInfo::gen_call_topology)
+            .find_map(|call| call.out_of_gas_syscall)
+            .map(|syscall| TransactionError::OutOfGas { syscall })
+    }
+}
+
+/// A storage slot written by more than one class, see
+/// [`TransactionExecutionInfo::storage_layout_conflicts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotConflict {
+    pub contract_address: Address,
+    pub key: [u8; 32],
+    /// Class whose code performed each write, with the value written.
+    pub writes: Vec<(ClassHash, Felt252)>,
+}
+
+/// Nests a flat list of internal calls under `root`, as produced by syscall handlers used outside
+/// of an `ExecutionEntryPoint`.
+///
+/// `internal_calls` must be in the order in which the calls started. Each call is attached to
+/// the most recent unfinished call whose contract address is its caller address, falling back
+/// to `root` when there is none. Children keep their relative order and are appended after any
+/// internal call the parent already had.
+pub fn build_call_tree(root: CallInfo, internal_calls: Vec<CallInfo>) -> CallInfo {
+    fn attach_last(stack: &mut Vec<CallInfo>) {
+        if let Some(child) = stack.pop() {
+            if let Some(parent) = stack.last_mut() {
+                parent.internal_calls.push(child);
+            }
+        }
+    }
+
+    let mut stack = vec![root];
+    for call in internal_calls {
+        // Calls that are not the caller of the current one have already finished
+        while stack.len() > 1
+            && stack.last().map_or(false, |parent| {
+                parent.contract_address != call.caller_address
+            })
+        {
+            attach_last(&mut stack);
+        }
+        stack.push(call);
+    }
+    while stack.len() > 1 {
+        attach_last(&mut stack);
+    }
+
+    stack.pop().unwrap_or_default()
 }
 
 // --------------------
@@ -691,6 +1043,45 @@ mod tests {
         assert_eq!(sorted_events, vec![event]);
     }
 
+    #[test]
+    fn sorted_builtin_instance_counter_test() {
+        let builtins = [
+            ("range_check_builtin", 4),
+            ("pedersen_builtin", 2),
+            ("bitwise_builtin", 1),
+            ("poseidon_builtin", 3),
+        ];
+        let call_info_with = |builtins: &[(&str, usize)]| CallInfo {
+            execution_resources: ExecutionResources {
+                builtin_instance_counter: builtins
+                    .iter()
+                    .map(|(builtin, count)| (builtin.to_string(), *count))
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let sorted = call_info_with(&builtins).sorted_builtin_instance_counter();
+        let reversed: Vec<_> = builtins.iter().rev().copied().collect();
+        for _ in 0..10 {
+            assert_eq!(
+                call_info_with(&reversed).sorted_builtin_instance_counter(),
+                sorted
+            );
+        }
+
+        assert_eq!(
+            sorted.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("bitwise_builtin".to_string(), 1),
+                ("pedersen_builtin".to_string(), 2),
+                ("poseidon_builtin".to_string(), 3),
+                ("range_check_builtin".to_string(), 4),
+            ]
+        );
+    }
+
     #[test]
     fn non_optional_calls_test() {
         let mut tx_info = TransactionExecutionInfo {
@@ -725,6 +1116,295 @@ mod tests {
         assert_eq!(res, [])
     }
 
+    #[test]
+    fn new_without_fee_info_computes_total_gas_consumed_test() {
+        // Transactions that pay no fee, like L1 handlers, are built this way
+        let tx_info = TransactionExecutionInfo::new_without_fee_info(
+            None,
+            Some(CallInfo {
+                gas_consumed: 200,
+                internal_calls: vec![CallInfo {
+                    gas_consumed: 150,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            None,
+            HashMap::new(),
+            Some(TransactionType::L1Handler),
+        );
+
+        assert_eq!(tx_info.total_gas_consumed, 200);
+    }
+
+    #[test]
+    fn set_fee_info_computes_total_gas_consumed_test() {
+        let mut tx_info = TransactionExecutionInfo {
+            validate_info: Some(CallInfo {
+                gas_consumed: 10,
+                ..Default::default()
+            }),
+            call_info: Some(CallInfo {
+                gas_consumed: 200,
+                // Already accounted for in the gas consumed by its caller
+                internal_calls: vec![CallInfo {
+                    gas_consumed: 150,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(tx_info.total_gas_consumed, 0);
+
+        let fee_transfer_info = CallInfo {
+            gas_consumed: 3,
+            ..Default::default()
+        };
+        tx_info.set_fee_info(1, Some(fee_transfer_info));
+
+        let expected: u128 = tx_info
+            .non_optional_calls()
+            .iter()
+            .map(|call_info| call_info.gas_consumed)
+            .sum();
+        assert_eq!(expected, 213);
+        assert_eq!(tx_info.total_gas_consumed, expected);
+    }
+
+    #[test]
+    fn fee_transfer_gas_test() {
+        let mut tx_info = TransactionExecutionInfo {
+            call_info: Some(CallInfo {
+                gas_consumed: 100,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(tx_info.fee_transfer_gas(), 0);
+
+        tx_info.set_fee_info(
+            1,
+            Some(CallInfo {
+                gas_consumed: 25,
+                ..Default::default()
+            }),
+        );
+
+        assert_eq!(tx_info.fee_transfer_gas(), 25);
+        assert_eq!(tx_info.total_gas_consumed, 125);
+
+        // Cairo 0 fee tokens don't meter gas
+        tx_info.set_fee_info(
+            1,
+            Some(CallInfo {
+                execution_resources: ExecutionResources {
+                    n_steps: 525,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        );
+
+        assert_eq!(tx_info.fee_transfer_gas(), 525 * STEP_GAS_COST);
+        assert_eq!(tx_info.total_gas_consumed, 100 + 525 * STEP_GAS_COST);
+    }
+
+    #[test]
+    fn storage_updates_by_contract_test() {
+        let contract_a = Address(2.into());
+        let contract_b = Address(1.into());
+
+        // Library call running against contract A's storage
+        let delegate_call = CallInfo {
+            contract_address: contract_a.clone(),
+            storage_writes: vec![
+                StorageWrite::new([2; 32], 3.into(), 0),
+                StorageWrite::new([4; 32], 9.into(), 0),
+            ],
+            ..Default::default()
+        };
+        let internal_call = CallInfo {
+            contract_address: contract_b.clone(),
+            storage_writes: vec![
+                StorageWrite::new([3; 32], 30.into(), 0),
+                StorageWrite::new([1; 32], 10.into(), 0),
+            ],
+            ..Default::default()
+        };
+        let failed_call = CallInfo {
+            contract_address: contract_b.clone(),
+            storage_writes: vec![StorageWrite::new([1; 32], 99.into(), 0)],
+            failure_flag: true,
+            ..Default::default()
+        };
+        // Slot 2 is written before and after the library call
+        let call_info = CallInfo {
+            contract_address: contract_a.clone(),
+            storage_writes: vec![
+                StorageWrite::new([2; 32], 1.into(), 0),
+                StorageWrite::new([2; 32], 2.into(), 1),
+            ],
+            internal_calls: vec![delegate_call, internal_call, failed_call],
+            ..Default::default()
+        };
+        let fee_transfer_info = CallInfo {
+            contract_address: contract_a.clone(),
+            storage_writes: vec![StorageWrite::new([1; 32], 5.into(), 0)],
+            ..Default::default()
+        };
+        let tx_info = TransactionExecutionInfo {
+            call_info: Some(call_info),
+            fee_transfer_info: Some(fee_transfer_info),
+            ..Default::default()
+        };
+
+        let storage_updates = tx_info.storage_updates_by_contract();
+
+        assert_eq!(
+            storage_updates.keys().collect::<Vec<_>>(),
+            vec![&contract_b, &contract_a]
+        );
+        assert_eq!(
+            storage_updates[&contract_a],
+            BTreeMap::from([
+                ([1; 32], Felt252::from(5)),
+                ([2; 32], Felt252::from(2)),
+                ([4; 32], Felt252::from(9))
+            ])
+        );
+        assert_eq!(
+            storage_updates[&contract_b],
+            BTreeMap::from([([1; 32], Felt252::from(10)), ([3; 32], Felt252::from(30))])
+        );
+    }
+
+    #[test]
+    fn code_vs_storage_map_test() {
+        let proxy = Address(1.into());
+        let other_contract = Address(2.into());
+        let proxy_class_hash = [1; 32];
+        let implementation_class_hash = [2; 32];
+        let other_class_hash = [3; 32];
+
+        // The proxy delegates to the implementation class, which then calls another contract
+        let external_call = CallInfo {
+            call_type: Some(CallType::Call),
+            contract_address: other_contract.clone(),
+            class_hash: Some(other_class_hash),
+            ..Default::default()
+        };
+        let delegate_call = CallInfo {
+            call_type: Some(CallType::Delegate),
+            contract_address: proxy.clone(),
+            class_hash: Some(implementation_class_hash),
+            internal_calls: vec![external_call],
+            ..Default::default()
+        };
+        let call_info = CallInfo {
+            call_type: Some(CallType::Call),
+            contract_address: proxy.clone(),
+            class_hash: Some(proxy_class_hash),
+            internal_calls: vec![delegate_call],
+            ..Default::default()
+        };
+        let tx_info = TransactionExecutionInfo {
+            call_info: Some(call_info),
+            ..Default::default()
+        };
+
+        let code_vs_storage = tx_info.code_vs_storage_map();
+
+        assert_eq!(
+            code_vs_storage,
+            vec![
+                (proxy.clone(), proxy_class_hash),
+                (proxy.clone(), implementation_class_hash),
+                (other_contract, other_class_hash),
+            ]
+        );
+        // The proxy's storage was used by both its own code and the delegated one
+        assert_eq!(
+            code_vs_storage
+                .iter()
+                .filter(|(storage, _)| *storage == proxy)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn storage_layout_conflicts_test() {
+        let proxy = Address(1.into());
+        let proxy_class_hash = [1; 32];
+        let implementation_a = [2; 32];
+        let implementation_b = [3; 32];
+
+        // Implementation A keeps an owner in slot 5, implementation B a counter in the same slot
+        let delegate_call_a = CallInfo {
+            call_type: Some(CallType::Delegate),
+            contract_address: proxy.clone(),
+            class_hash: Some(implementation_a),
+            storage_writes: vec![
+                StorageWrite::new([5; 32], 0x1234.into(), 0),
+                StorageWrite::new([6; 32], 1.into(), 0),
+            ],
+            ..Default::default()
+        };
+        let delegate_call_b = CallInfo {
+            call_type: Some(CallType::Delegate),
+            contract_address: proxy.clone(),
+            class_hash: Some(implementation_b),
+            storage_writes: vec![StorageWrite::new([5; 32], 7.into(), 0)],
+            ..Default::default()
+        };
+        let call_info = CallInfo {
+            call_type: Some(CallType::Call),
+            contract_address: proxy.clone(),
+            class_hash: Some(proxy_class_hash),
+            storage_writes: vec![StorageWrite::new([6; 32], 2.into(), 0)],
+            internal_calls: vec![delegate_call_a, delegate_call_b],
+            ..Default::default()
+        };
+        let tx_info = TransactionExecutionInfo {
+            call_info: Some(call_info),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            tx_info.storage_layout_conflicts(),
+            vec![
+                SlotConflict {
+                    contract_address: proxy.clone(),
+                    key: [5; 32],
+                    writes: vec![
+                        (implementation_a, 0x1234.into()),
+                        (implementation_b, 7.into())
+                    ],
+                },
+                SlotConflict {
+                    contract_address: proxy,
+                    key: [6; 32],
+                    writes: vec![(proxy_class_hash, 2.into()), (implementation_a, 1.into())],
+                },
+            ]
+        );
+
+        // Writes from a single class never conflict
+        let tx_info = TransactionExecutionInfo {
+            call_info: Some(CallInfo {
+                class_hash: Some(proxy_class_hash),
+                storage_writes: vec![
+                    StorageWrite::new([5; 32], 1.into(), 0),
+                    StorageWrite::new([5; 32], 2.into(), 0),
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(tx_info.storage_layout_conflicts().is_empty());
+    }
+
     #[test]
     fn gen_call_topology_test() {
         // dfs root
@@ -1005,4 +1685,100 @@ mod tests {
             ])
         )
     }
+
+    #[test]
+    fn build_call_tree_test() {
+        let call = |caller: u32, contract: u32, selector: u32| CallInfo {
+            caller_address: Address(caller.into()),
+            contract_address: Address(contract.into()),
+            entry_point_selector: Some(selector.into()),
+            ..Default::default()
+        };
+
+        // 1 calls 2, which calls 3 twice and then 4, and finally 1 calls 5
+        let root = call(0, 1, 0);
+        let internal_calls = vec![
+            call(1, 2, 1),
+            call(2, 3, 2),
+            call(2, 3, 3),
+            call(2, 4, 4),
+            call(1, 5, 5),
+        ];
+
+        let expected = CallInfo {
+            internal_calls: vec![
+                CallInfo {
+                    internal_calls: vec![call(2, 3, 2), call(2, 3, 3), call(2, 4, 4)],
+                    ..call(1, 2, 1)
+                },
+                call(1, 5, 5),
+            ],
+            ..call(0, 1, 0)
+        };
+
+        assert_eq!(build_call_tree(root.clone(), internal_calls), expected);
+        assert_eq!(build_call_tree(root.clone(), Vec::new()), root);
+    }
+
+    #[test]
+    fn had_out_of_gas_syscall_test() {
+        // The inner call failed for running out of gas but the outer call handled it
+        let inner_call = CallInfo {
+            contract_address: Address(2.into()),
+            failure_flag: true,
+            out_of_gas_syscall: Some("emit_event".to_string()),
+            retdata: vec![Felt252::from_bytes_be(b"Syscall out of gas")],
+            ..Default::default()
+        };
+        let mut tx_info = TransactionExecutionInfo {
+            call_info: Some(CallInfo {
+                contract_address: Address(1.into()),
+                internal_calls: vec![CallInfo::default(), inner_call],
+                ..Default::default()
+            }),
+            validate_info: Some(CallInfo::default()),
+            ..Default::default()
+        };
+
+        assert!(!tx_info.is_reverted());
+        assert!(tx_info.had_out_of_gas_syscall());
+        assert_matches!(
+            tx_info.out_of_gas_error(),
+            Some(TransactionError::OutOfGas { syscall }) if syscall == "emit_event"
+        );
+
+        tx_info.call_info.as_mut().unwrap().internal_calls[1].out_of_gas_syscall = None;
+        assert!(!tx_info.had_out_of_gas_syscall());
+        assert!(tx_info.out_of_gas_error().is_none());
+    }
+
+    #[test]
+    fn semantically_eq_ignores_execution_resources() {
+        let call = |n_steps: usize, retdata: Vec<Felt252>| CallInfo {
+            retdata,
+            execution_resources: ExecutionResources {
+                n_steps,
+                n_memory_holes: 0,
+                builtin_instance_counter: HashMap::from([(
+                    "range_check_builtin".to_string(),
+                    n_steps / 10,
+                )]),
+            },
+            ..Default::default()
+        };
+        let with_internal_call = |n_steps: usize, retdata: Vec<Felt252>| CallInfo {
+            internal_calls: vec![call(n_steps, retdata.clone())],
+            ..call(n_steps, retdata)
+        };
+
+        let call_info = with_internal_call(94, vec![144.into()]);
+        assert!(call_info.semantically_eq(&with_internal_call(120, vec![144.into()])));
+        assert_ne!(call_info, with_internal_call(120, vec![144.into()]));
+
+        assert!(!call_info.semantically_eq(&with_internal_call(94, vec![89.into()])));
+        assert!(!call_info.semantically_eq(&CallInfo {
+            internal_calls: vec![call(94, vec![89.into()])],
+            ..call(94, vec![144.into()])
+        }));
+    }
 }
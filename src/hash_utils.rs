@@ -4,7 +4,7 @@ use cairo_vm::felt::Felt252;
 use num_integer::Integer;
 use num_traits::Pow;
 use starknet_crypto::{pedersen_hash, FieldElement};
-use std::vec;
+use std::{cell::RefCell, sync::Arc, vec};
 
 /// Computes the contract address.
 ///
@@ -79,6 +79,9 @@ pub fn calculate_contract_address(
 
 /// Computes Pedersen hash for a slice of `Felt252` elements.
 ///
+/// The hash is computed by the current thread's [`HashBackend`], which is Pedersen unless
+/// replaced through [`with_hash_backend`].
+///
 /// # Arguments
 ///
 /// * `vec` - A slice of `Felt252` elements representing the input vector.
@@ -111,26 +114,105 @@ pub fn calculate_contract_address(
 /// }
 /// ```
 pub fn compute_hash_on_elements(vec: &[Felt252]) -> Result<Felt252, HashError> {
-    let mut felt_vec = vec
-        .iter()
-        .map(|num| {
-            FieldElement::from_dec_str(&num.to_str_radix(10))
-                .map_err(|e| HashError::FailedToComputeHash(e.to_string()))
-        })
-        .collect::<Result<Vec<FieldElement>, HashError>>()?;
+    match current_hash_backend() {
+        Some(backend) => backend.hash_on_elements(vec),
+        None => PedersenHashBackend.hash_on_elements(vec),
+    }
+}
+
+/// Hash function behind [`compute_hash_on_elements`], and therefore behind contract addresses,
+/// deprecated class hashes and transaction hashes.
+pub trait HashBackend {
+    fn hash_on_elements(&self, elements: &[Felt252]) -> Result<Felt252, HashError>;
+}
+
+/// The Pedersen hash chain used by Starknet, this is the default backend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PedersenHashBackend;
+
+impl HashBackend for PedersenHashBackend {
+    fn hash_on_elements(&self, elements: &[Felt252]) -> Result<Felt252, HashError> {
+        let mut felt_vec = elements
+            .iter()
+            .map(|num| {
+                FieldElement::from_dec_str(&num.to_str_radix(10))
+                    .map_err(|e| HashError::FailedToComputeHash(e.to_string()))
+            })
+            .collect::<Result<Vec<FieldElement>, HashError>>()?;
+
+        felt_vec.push(FieldElement::from(felt_vec.len()));
+        felt_vec.insert(0, FieldElement::from(0_u16));
+
+        let felt_result = felt_vec
+            .into_iter()
+            .reduce(|x, y| pedersen_hash(&x, &y))
+            .ok_or(HashError::FailedToComputeHash(
+                "Failed to compute Pedersen hash.".to_string(),
+            ))?;
+
+        let result = Felt252::from_bytes_be(&felt_result.to_bytes_be());
+        Ok(result)
+    }
+}
+
+/// A cheap, deterministic but non-cryptographic hash, meant to speed up tests that don't
+/// depend on the actual Starknet hashes. Must never be used to compute real addresses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MockHashBackend;
+
+impl HashBackend for MockHashBackend {
+    fn hash_on_elements(&self, elements: &[Felt252]) -> Result<Felt252, HashError> {
+        let multiplier = Felt252::from(MOCK_HASH_MULTIPLIER);
+        Ok(elements
+            .iter()
+            .fold(Felt252::from(elements.len()), |acc, element| {
+                acc * &multiplier + element
+            }))
+    }
+}
+
+const MOCK_HASH_MULTIPLIER: u64 = 0x100000001b3;
+
+/// A hash backend that can be installed on several threads at once.
+pub(crate) type SharedHashBackend = Arc<dyn HashBackend + Send + Sync>;
+
+thread_local! {
+    static HASH_BACKEND: RefCell<Option<SharedHashBackend>> = RefCell::new(None);
+}
 
-    felt_vec.push(FieldElement::from(felt_vec.len()));
-    felt_vec.insert(0, FieldElement::from(0_u16));
+/// Runs `f` with `backend` as the hash backend of the current thread, restoring the previous
+/// one afterwards (even if `f` panics). Other threads keep using their own backend, except the
+/// workers spawned by `StarknetState::execute_parallel`, which inherit it.
+pub fn with_hash_backend<B: HashBackend + Send + Sync + 'static, R>(
+    backend: B,
+    f: impl FnOnce() -> R,
+) -> R {
+    let backend: SharedHashBackend = Arc::new(backend);
+    with_shared_hash_backend(Some(backend), f)
+}
+
+/// Returns the hash backend installed on the current thread by [`with_hash_backend`], if any.
+pub(crate) fn current_hash_backend() -> Option<SharedHashBackend> {
+    HASH_BACKEND.with(|backend| backend.borrow().clone())
+}
 
-    let felt_result = felt_vec
-        .into_iter()
-        .reduce(|x, y| pedersen_hash(&x, &y))
-        .ok_or(HashError::FailedToComputeHash(
-            "Failed to compute Pedersen hash.".to_string(),
-        ))?;
+/// Runs `f` with `backend` as the hash backend of the current thread, `None` meaning the
+/// default one. Used to carry a thread's backend over to the threads it spawns.
+pub(crate) fn with_shared_hash_backend<R>(
+    backend: Option<SharedHashBackend>,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct RestoreBackend(Option<SharedHashBackend>);
 
-    let result = Felt252::from_bytes_be(&felt_result.to_bytes_be());
-    Ok(result)
+    impl Drop for RestoreBackend {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            HASH_BACKEND.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = RestoreBackend(HASH_BACKEND.with(|current| current.replace(backend)));
+    f()
 }
 
 #[cfg(test)]
@@ -208,4 +290,67 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn calculate_contract_address_with_mock_hash_backend() {
+        let calculate = |salt: u32| {
+            calculate_contract_address(
+                &salt.into(),
+                &2.into(),
+                &[3.into(), 4.into()],
+                Address(5.into()),
+            )
+            .unwrap()
+        };
+
+        let (first, second, other_salt) = with_hash_backend(MockHashBackend, || {
+            (calculate(1), calculate(1), calculate(2))
+        });
+
+        assert_eq!(first, second);
+        assert_ne!(first, other_salt);
+        assert_eq!(
+            first,
+            MockHashBackend
+                .hash_on_elements(&[
+                    Felt252::from_bytes_be(b"STARKNET_CONTRACT_ADDRESS"),
+                    5.into(),
+                    1.into(),
+                    2.into(),
+                    MockHashBackend
+                        .hash_on_elements(&[3.into(), 4.into()])
+                        .unwrap(),
+                ])
+                .unwrap()
+                .mod_floor(&(Felt252::new(2).pow(251) - Felt252::new(256)))
+        );
+
+        // The Pedersen backend is restored afterwards
+        assert_eq!(
+            calculate(1),
+            felt_str!(
+                "1885555033409779003200115284723341705041371741573881252130189632266543809788"
+            )
+        );
+    }
+
+    #[test]
+    fn shared_hash_backend_is_used_by_other_threads() {
+        let elements = [1.into(), 2.into()];
+        let hash = with_hash_backend(MockHashBackend, || {
+            let backend = current_hash_backend();
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        with_shared_hash_backend(backend, || compute_hash_on_elements(&elements))
+                    })
+                    .join()
+                    .unwrap()
+            })
+        })
+        .unwrap();
+
+        assert_eq!(hash, MockHashBackend.hash_on_elements(&elements).unwrap());
+        assert!(current_hash_backend().is_none());
+    }
 }
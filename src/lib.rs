@@ -1040,4 +1040,69 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn test_transaction_sender_address() {
+        let deploy = Transaction::Deploy(
+            Deploy::new(
+                SALT.clone(),
+                CONTRACT_CLASS.clone(),
+                vec![],
+                StarknetChainId::TestNet.to_felt(),
+                0.into(),
+            )
+            .unwrap(),
+        );
+        let declare = Transaction::Declare(
+            Declare::new(
+                CONTRACT_CLASS.clone(),
+                StarknetChainId::TestNet.to_felt(),
+                CONTRACT_ADDRESS.clone(),
+                0,
+                0.into(),
+                vec![],
+                Felt252::zero(),
+            )
+            .unwrap(),
+        );
+        let declare_v2 = Transaction::DeclareV2(Box::new(declarev2_tx()));
+        let deploy_account = DeployAccount::new(
+            *CLASS_HASH_BYTES,
+            0,
+            0.into(),
+            Felt252::zero(),
+            vec![],
+            SIGNATURE.clone(),
+            SALT.clone(),
+            StarknetChainId::TestNet.to_felt(),
+        )
+        .unwrap();
+        let deployed_account_address = deploy_account.contract_address().clone();
+        let deploy_account = Transaction::DeployAccount(deploy_account);
+        let invoke = Transaction::InvokeFunction(
+            InvokeFunction::new(
+                TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+                EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+                0,
+                TRANSACTION_VERSION.clone(),
+                vec![],
+                SIGNATURE.clone(),
+                StarknetChainId::TestNet.to_felt(),
+                Some(Felt252::zero()),
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(deploy.sender_address(), Address(Felt252::zero()));
+        assert_eq!(declare.sender_address(), CONTRACT_ADDRESS.clone());
+        assert_eq!(
+            declare_v2.sender_address(),
+            TEST_ACCOUNT_CONTRACT_ADDRESS.clone()
+        );
+        assert_eq!(deploy_account.sender_address(), deployed_account_address);
+        assert_eq!(
+            invoke.sender_address(),
+            TEST_ACCOUNT_CONTRACT_ADDRESS.clone()
+        );
+    }
 }
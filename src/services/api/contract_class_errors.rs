@@ -17,4 +17,8 @@ pub enum ContractClassError {
     ParseError,
     #[error("Program error: {0}")]
     ProgramError(String),
+    #[error("The contract class program is empty")]
+    EmptyProgram,
+    #[error("Entry point offset {0} is out of bounds for a program of length {1}")]
+    EntryPointOffsetOutOfBounds(usize, usize),
 }
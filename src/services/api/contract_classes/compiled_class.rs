@@ -26,6 +26,47 @@ pub enum CompiledClass {
     Casm(Arc<CasmContractClass>),
 }
 
+impl CompiledClass {
+    /// Returns the number of felts in the class' program data (Cairo 0) or bytecode (Casm).
+    pub fn bytecode_length(&self) -> usize {
+        match self {
+            CompiledClass::Deprecated(contract_class) => contract_class.program.data_len(),
+            CompiledClass::Casm(contract_class) => contract_class.bytecode.len(),
+        }
+    }
+
+    /// Checks that the class is well-formed: its program or bytecode isn't empty and every
+    /// entry point offset points inside it. See [`ContractClass::validate`].
+    pub fn validate(&self) -> Result<(), ContractClassError> {
+        match self {
+            CompiledClass::Deprecated(contract_class) => contract_class.validate(),
+            CompiledClass::Casm(contract_class) => {
+                let bytecode_length = contract_class.bytecode.len();
+                if bytecode_length == 0 {
+                    return Err(ContractClassError::EmptyProgram);
+                }
+
+                let entry_points = &contract_class.entry_points_by_type;
+                for entry_point in entry_points
+                    .external
+                    .iter()
+                    .chain(entry_points.l1_handler.iter())
+                    .chain(entry_points.constructor.iter())
+                {
+                    if entry_point.offset >= bytecode_length {
+                        return Err(ContractClassError::EntryPointOffsetOutOfBounds(
+                            entry_point.offset,
+                            bytecode_length,
+                        ));
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
 impl TryInto<CasmContractClass> for CompiledClass {
     type Error = ContractClassError;
     fn try_into(self) -> Result<CasmContractClass, ContractClassError> {
@@ -58,15 +99,20 @@ struct MiddleSierraContractClass {
     #[allow(dead_code)]
     abi: serde_json::Value,
 }
-impl From<StarknetRsContractClass> for CompiledClass {
-    fn from(starknet_rs_contract_class: StarknetRsContractClass) -> Self {
+impl TryFrom<StarknetRsContractClass> for CompiledClass {
+    type Error = ContractClassError;
+
+    fn try_from(starknet_rs_contract_class: StarknetRsContractClass) -> Result<Self, Self::Error> {
         match starknet_rs_contract_class {
             Sierra(flattened_sierra_contract_class) => {
                 let abi: Option<Contract> =
-                    serde_json::from_str(&flattened_sierra_contract_class.abi).unwrap();
+                    serde_json::from_str(&flattened_sierra_contract_class.abi)
+                        .map_err(|_| ContractClassError::ParseError)?;
 
-                let v = serde_json::to_value(&flattened_sierra_contract_class).unwrap();
-                let middle_sierra: MiddleSierraContractClass = serde_json::from_value(v).unwrap();
+                let v = serde_json::to_value(&flattened_sierra_contract_class)
+                    .map_err(|_| ContractClassError::ParseError)?;
+                let middle_sierra: MiddleSierraContractClass =
+                    serde_json::from_value(v).map_err(|_| ContractClassError::ParseError)?;
 
                 let sierra_cc = SierraContractClass {
                     sierra_program: middle_sierra.sierra_program,
@@ -76,14 +122,17 @@ impl From<StarknetRsContractClass> for CompiledClass {
                     abi,
                 };
 
-                let casm_cc = CasmContractClass::from_contract_class(sierra_cc, true).unwrap();
+                let casm_cc = CasmContractClass::from_contract_class(sierra_cc, true)
+                    .map_err(|err| ContractClassError::ProgramError(err.to_string()))?;
 
-                CompiledClass::Casm(Arc::new(casm_cc))
+                Ok(CompiledClass::Casm(Arc::new(casm_cc)))
             }
             Legacy(_deprecated_contract_class) => {
-                let as_str = decode_reader(_deprecated_contract_class.program).unwrap();
+                let as_str = decode_reader(_deprecated_contract_class.program)
+                    .map_err(|_| ContractClassError::ParseError)?;
 
-                let program = Program::from_bytes(as_str.as_bytes(), None).unwrap();
+                let program = Program::from_bytes(as_str.as_bytes(), None)
+                    .map_err(|err| ContractClassError::ProgramError(err.to_string()))?;
 
                 // let hinted_class_hash = compute_hinted_class_hash();
                 let mut entry_points_by_type: HashMap<EntryPointType, Vec<ContractEntryPoint>> =
@@ -131,30 +180,36 @@ impl From<StarknetRsContractClass> for CompiledClass {
                     .collect::<Vec<ContractEntryPoint>>();
                 entry_points_by_type.insert(EntryPointType::Constructor, l1_handler_entries);
 
-                let v = serde_json::to_value(&_deprecated_contract_class.abi).unwrap();
-                let abi: Option<AbiType> = serde_json::from_value(v).unwrap();
+                let v = serde_json::to_value(&_deprecated_contract_class.abi)
+                    .map_err(|_| ContractClassError::ParseError)?;
+                let abi: Option<AbiType> =
+                    serde_json::from_value(v).map_err(|_| ContractClassError::ParseError)?;
 
                 let cairo_program_to_hash: CairoProgramToHash =
-                    serde_json::from_str(as_str.as_str()).unwrap();
+                    serde_json::from_str(as_str.as_str())
+                        .map_err(|_| ContractClassError::ParseError)?;
 
                 let serialized_cc = SerializedContractClass {
                     program: cairo_program_to_hash,
                     entry_points_by_type: serde_json::to_value(
                         &_deprecated_contract_class.entry_points_by_type,
                     )
-                    .unwrap(),
-                    abi: serde_json::to_value(&_deprecated_contract_class.abi).unwrap(),
+                    .map_err(|_| ContractClassError::ParseError)?,
+                    abi: serde_json::to_value(&_deprecated_contract_class.abi)
+                        .map_err(|_| ContractClassError::ParseError)?,
                 };
 
-                let v = serde_json::to_value(serialized_cc).unwrap();
-                let hinted_class_hash = compute_hinted_class_hash(&v).unwrap();
+                let v = serde_json::to_value(serialized_cc)
+                    .map_err(|_| ContractClassError::ParseError)?;
+                let hinted_class_hash = compute_hinted_class_hash(&v)
+                    .map_err(|err| ContractClassError::ProgramError(err.to_string()))?;
 
-                CompiledClass::Deprecated(Arc::new(ContractClass {
+                Ok(CompiledClass::Deprecated(Arc::new(ContractClass {
                     program,
                     entry_points_by_type,
                     abi,
                     hinted_class_hash,
-                }))
+                })))
             }
         }
     }
@@ -178,3 +233,53 @@ fn decode_reader(bytes: Vec<u8>) -> io::Result<String> {
     gz.read_to_string(&mut s)?;
     Ok(s)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytecode_length_deprecated_class() {
+        let program_json = std::fs::read_to_string("starknet_programs/fibonacci.json").unwrap();
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+
+        let raw_program: serde_json::Value = serde_json::from_str(&program_json).unwrap();
+        let expected_length = raw_program["program"]["data"].as_array().unwrap().len();
+
+        let compiled_class = CompiledClass::Deprecated(Arc::new(contract_class));
+        assert_ne!(expected_length, 0);
+        assert_eq!(compiled_class.bytecode_length(), expected_length);
+    }
+
+    #[test]
+    fn bytecode_length_casm_class() {
+        #[cfg(not(feature = "cairo_1_tests"))]
+        let program_data = include_bytes!("../../../../starknet_programs/cairo2/fibonacci.casm");
+        #[cfg(feature = "cairo_1_tests")]
+        let program_data = include_bytes!("../../../../starknet_programs/cairo1/fibonacci.casm");
+
+        let casm_contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+        let raw_class: serde_json::Value = serde_json::from_slice(program_data).unwrap();
+        let expected_length = raw_class["bytecode"].as_array().unwrap().len();
+
+        let compiled_class = CompiledClass::Casm(Arc::new(casm_contract_class));
+        assert_ne!(expected_length, 0);
+        assert_eq!(compiled_class.bytecode_length(), expected_length);
+    }
+
+    #[test]
+    fn try_from_legacy_class_with_corrupt_program() {
+        // "not gzip" in base64, the program is expected to be gzip compressed
+        let corrupt_class: StarknetRsContractClass = serde_json::from_value(serde_json::json!({
+            "program": "bm90IGd6aXA=",
+            "entry_points_by_type": { "CONSTRUCTOR": [], "EXTERNAL": [], "L1_HANDLER": [] },
+            "abi": [],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            CompiledClass::try_from(corrupt_class),
+            Err(ContractClassError::ParseError)
+        );
+    }
+}
@@ -10,7 +10,9 @@ use cairo_vm::types::{errors::program_errors::ProgramError, program::Program};
 use core::str::FromStr;
 use getset::{CopyGetters, Getters};
 use serde_json::Value;
-use starknet_api::deprecated_contract_class::{ContractClassAbiEntry, EntryPoint};
+use starknet_api::deprecated_contract_class::{
+    ContractClassAbiEntry, EntryPoint, FunctionAbiEntryType,
+};
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -128,6 +130,33 @@ impl ContractClass {
         })
     }
 
+    /// Checks that the class is well-formed: its program isn't empty, its entry points are
+    /// sorted and unique, and each one points inside the program.
+    pub fn validate(&self) -> Result<(), ContractClassError> {
+        let program_length = self.program.data_len();
+        if program_length == 0 {
+            return Err(ContractClassError::EmptyProgram);
+        }
+
+        for entry_points in self.entry_points_by_type.values() {
+            for i in 1..entry_points.len() {
+                if entry_points[i - 1].selector() >= entry_points[i].selector() {
+                    return Err(ContractClassError::EntrypointError(entry_points.clone()));
+                }
+            }
+            for entry_point in entry_points {
+                if entry_point.offset() >= program_length {
+                    return Err(ContractClassError::EntryPointOffsetOutOfBounds(
+                        entry_point.offset(),
+                        program_length,
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Parses a [`ContractClass`] from a compiled Cairo 0 program's JSON and a class hash.
     ///
     /// This constructor avoids the need to recompute the class hash from the program JSON,
@@ -158,6 +187,26 @@ impl ContractClass {
         })
     }
 
+    /// Returns the number of calldata felts the constructor takes according to the ABI, or
+    /// `None` if it can't be told: the ABI is missing, has no constructor or the constructor
+    /// takes parameters other than felts.
+    pub fn constructor_calldata_len(&self) -> Option<usize> {
+        let constructor = self.abi.as_ref()?.iter().find_map(|entry| match entry {
+            ContractClassAbiEntry::Function(function)
+                if matches!(function.r#type, FunctionAbiEntryType::Constructor) =>
+            {
+                Some(&function.entry)
+            }
+            _ => None,
+        })?;
+
+        constructor
+            .inputs
+            .iter()
+            .all(|input| input.r#type == "felt")
+            .then_some(constructor.inputs.len())
+    }
+
     /// Parses a [`ContractClass`] from a compiled Cairo 0 program's JSON
     /// at the given file path.
     pub fn from_path<F>(path: F) -> Result<Self, ProgramError>
@@ -266,7 +315,7 @@ mod tests {
         serde::deserialize_program::BuiltinName,
     };
     use starknet_api::deprecated_contract_class::{
-        FunctionAbiEntry, FunctionAbiEntryType, FunctionAbiEntryWithType, TypedParameter,
+        FunctionAbiEntry, FunctionAbiEntryWithType, TypedParameter,
     };
 
     #[test]
@@ -440,4 +489,40 @@ mod tests {
             contract_class_from_program_json_and_class_hash
         );
     }
+
+    #[test]
+    fn validate_contract_class() {
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        assert_eq!(contract_class.validate(), Ok(()));
+
+        let empty_class = ContractClass::new_with_hinted_class_hash(
+            0.into(),
+            Program::default(),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        let error = empty_class.validate().unwrap_err();
+        assert_eq!(error, ContractClassError::EmptyProgram);
+        assert_eq!(error.to_string(), "The contract class program is empty");
+
+        let program_length = contract_class.program().data_len();
+        let out_of_bounds_class = ContractClass::new_with_hinted_class_hash(
+            0.into(),
+            contract_class.program().clone(),
+            HashMap::from([(
+                EntryPointType::External,
+                vec![ContractEntryPoint::new(1.into(), program_length)],
+            )]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            out_of_bounds_class.validate(),
+            Err(ContractClassError::EntryPointOffsetOutOfBounds(
+                program_length,
+                program_length
+            ))
+        );
+    }
 }
@@ -3,7 +3,8 @@ use super::{
     state_cache::{StateCache, StorageEntry},
 };
 use crate::{
-    core::errors::state_errors::StateError,
+    core::errors::{hash_errors::HashError, state_errors::StateError},
+    hash_utils::compute_hash_on_elements,
     services::api::contract_classes::{
         compiled_class::CompiledClass, deprecated_contract_class::ContractClass,
     },
@@ -15,7 +16,7 @@ use cairo_vm::felt::Felt252;
 use getset::{Getters, MutGetters};
 use num_traits::Zero;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
 };
 
@@ -35,6 +36,11 @@ pub struct CachedState<T: StateReader> {
     pub(crate) contract_classes: Option<ContractClassCache>,
     #[get = "pub"]
     pub(crate) casm_contract_classes: Option<CasmClassCache>,
+    /// Maximum number of classes kept in the class caches, unbounded if `None`.
+    pub(crate) class_cache_capacity: Option<usize>,
+    /// Usage order of the cached classes fetched from the state reader, the only ones counted
+    /// against `class_cache_capacity` and evicted when it's exceeded.
+    pub(crate) class_cache_usage: ClassCacheUsage,
 }
 
 impl<T: StateReader> CachedState<T> {
@@ -49,6 +55,8 @@ impl<T: StateReader> CachedState<T> {
             contract_classes: contract_class_cache,
             state_reader,
             casm_contract_classes: casm_class_cache,
+            class_cache_capacity: None,
+            class_cache_usage: ClassCacheUsage::default(),
         }
     }
 
@@ -64,10 +72,114 @@ impl<T: StateReader> CachedState<T> {
             contract_classes,
             state_reader,
             casm_contract_classes,
+            class_cache_capacity: None,
+            class_cache_usage: ClassCacheUsage::default(),
         }
     }
 
-    /// Sets the contract classes cache.
+    /// Creates a view on top of this state that can be executed against without affecting it.
+    ///
+    /// The view reads through the shared parent (its cache, class caches and state reader),
+    /// which is never copied, and keeps every write in its own cache. Many views can be
+    /// created from the same parent and used concurrently, each one isolated from the others.
+    pub fn readonly_view(self: &Arc<Self>) -> CachedState<CachedState<T>> {
+        CachedState::new(Arc::clone(self), None, None)
+    }
+
+    /// Returns the addresses of the contracts deployed on top of the state reader: those with a
+    /// class hash written in the cache and none in the reader. Contracts that only had their
+    /// class replaced are not included. The order is unspecified.
+    pub fn deployed_addresses(&self) -> Vec<Address> {
+        self.cache
+            .class_hash_writes
+            .keys()
+            .filter(|address| {
+                !matches!(
+                    self.state_reader.get_class_hash_at(address),
+                    Ok(class_hash) if class_hash != *UNINITIALIZED_CLASS_HASH
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns a digest of the storage of `contract_address` as seen by this state: the
+    /// [`compute_hash_on_elements`] hash of its `[key_0, value_0, key_1, value_1, ...]` pairs,
+    /// sorted by key and skipping zero values, so unset and zeroed slots hash the same.
+    ///
+    /// This isn't the root of the Starknet storage trie, only a deterministic commitment for
+    /// comparing states. It covers the entries listed by [`StateReader::storage_entries_for`].
+    pub fn contract_storage_root(&self, contract_address: &Address) -> Result<Felt252, HashError> {
+        let elements: Vec<Felt252> = self
+            .storage_entries_for(contract_address)
+            .into_iter()
+            .filter(|(_, value)| !value.is_zero())
+            .flat_map(|(key, value)| [Felt252::from_bytes_be(&key), value])
+            .collect();
+
+        compute_hash_on_elements(&elements)
+    }
+
+    /// Bounds the number of classes that [`State::get_contract_class`] fetched from the state
+    /// reader and keeps in the class caches (deprecated and casm classes together) to
+    /// `capacity`, evicting the least recently used one whenever a new one is fetched past it.
+    ///
+    /// Only these classes count against the capacity and are evicted, so a miss is refetched
+    /// from the state reader. Classes passed as class caches to the constructor or
+    /// [`Self::set_contract_classes`], fetched by [`Self::prefetch`] or written through
+    /// [`State::set_contract_class`] and [`State::set_compiled_class`] are always kept.
+    ///
+    /// Only accesses through [`State`] update the usage order, as [`StateReader`] methods
+    /// can't mutate the cache.
+    pub fn with_class_cache_capacity(mut self, capacity: usize) -> Self {
+        self.class_cache_capacity = Some(capacity);
+        self.evict_cached_classes();
+        self
+    }
+
+    /// Tracks the class cached for `class_hash` as fetched from the state reader and the most
+    /// recently used one, evicting the least recently used fetched classes if the cache
+    /// capacity is exceeded.
+    fn track_loaded_class(&mut self, class_hash: ClassHash) {
+        self.class_cache_usage.touch(class_hash);
+        self.evict_cached_classes();
+    }
+
+    /// Stops tracking the class cached for `class_hash`, as it was written to this state and
+    /// can't be reloaded.
+    fn untrack_written_class(&mut self, class_hash: ClassHash) {
+        self.class_cache_usage.remove(&class_hash);
+    }
+
+    /// Marks the cached class for `class_hash` as the most recently used one if it's tracked.
+    fn touch_cached_class(&mut self, class_hash: ClassHash) {
+        if self.class_cache_capacity.is_some() && self.class_cache_usage.contains(&class_hash) {
+            self.class_cache_usage.touch(class_hash);
+        }
+    }
+
+    /// Evicts the least recently used classes fetched from the state reader until they fit in
+    /// the class cache capacity.
+    fn evict_cached_classes(&mut self) {
+        let Some(capacity) = self.class_cache_capacity else {
+            return;
+        };
+
+        while self.class_cache_usage.len() > capacity {
+            let Some(evicted) = self.class_cache_usage.pop_least_recently_used() else {
+                break;
+            };
+            if let Some(classes) = self.contract_classes.as_mut() {
+                classes.remove(&evicted);
+            }
+            if let Some(classes) = self.casm_contract_classes.as_mut() {
+                classes.remove(&evicted);
+            }
+        }
+    }
+
+    /// Sets the contract classes cache. Its classes aren't bounded by the cache capacity, see
+    /// [`Self::with_class_cache_capacity`].
     pub fn set_contract_classes(
         &mut self,
         contract_classes: ContractClassCache,
@@ -79,6 +191,64 @@ impl<T: StateReader> CachedState<T> {
         Ok(())
     }
 
+    /// Fetches the given storage entries, contract classes and contract addresses (their
+    /// class hash and nonce) from the state reader in a single pass and stores them in the
+    /// cache, so that later accesses don't hit the state reader. Cached entries are skipped.
+    pub fn prefetch(
+        &mut self,
+        keys: &[StorageEntry],
+        classes: &[ClassHash],
+        addresses: &[Address],
+    ) -> Result<(), StateError> {
+        for storage_entry in keys {
+            State::get_storage_at(self, storage_entry)?;
+        }
+
+        for contract_address in addresses {
+            State::get_class_hash_at(self, contract_address)?;
+            State::get_nonce_at(self, contract_address)?;
+        }
+
+        for class_hash in classes {
+            if self.is_contract_class_cached(class_hash) {
+                continue;
+            }
+
+            match self.state_reader.get_contract_class(class_hash)? {
+                CompiledClass::Deprecated(contract_class) => {
+                    self.contract_classes
+                        .get_or_insert_with(HashMap::new)
+                        .insert(*class_hash, contract_class.as_ref().clone());
+                }
+                CompiledClass::Casm(casm_class) => {
+                    self.casm_contract_classes
+                        .get_or_insert_with(HashMap::new)
+                        .insert(*class_hash, casm_class.as_ref().clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the contract class for `class_hash` is already in the class caches.
+    /// Like [`StateReader::get_contract_class`], `class_hash` may also be the class hash of a
+    /// casm class cached under its compiled class hash.
+    pub(crate) fn is_contract_class_cached(&self, class_hash: &ClassHash) -> bool {
+        let compiled_class_hash = self
+            .cache
+            .class_hash_to_compiled_class_hash
+            .get(class_hash)
+            .unwrap_or(class_hash);
+
+        self.contract_classes
+            .as_ref()
+            .map_or(false, |x| x.contains_key(class_hash))
+            || self.casm_contract_classes.as_ref().map_or(false, |x| {
+                x.contains_key(class_hash) || x.contains_key(compiled_class_hash)
+            })
+    }
+
     /// Returns the casm classes.
     #[allow(dead_code)]
     pub(crate) fn get_casm_classes(&mut self) -> Result<&CasmClassCache, StateError> {
@@ -88,6 +258,59 @@ impl<T: StateReader> CachedState<T> {
     }
 }
 
+/// Least recently used order of a set of class hashes, updated in amortized constant time.
+///
+/// Every use is appended with a new tick, entries older than the last use of their class are
+/// skipped when popped and compacted away once they outnumber the live ones.
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ClassCacheUsage {
+    last_uses: HashMap<ClassHash, u64>,
+    uses: VecDeque<(ClassHash, u64)>,
+    next_tick: u64,
+}
+
+impl ClassCacheUsage {
+    /// Marks `class_hash` as the most recently used class, tracking it if it wasn't.
+    pub(crate) fn touch(&mut self, class_hash: ClassHash) {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        self.last_uses.insert(class_hash, tick);
+        self.uses.push_back((class_hash, tick));
+
+        if self.uses.len() > 2 * self.last_uses.len() {
+            let last_uses = &self.last_uses;
+            self.uses
+                .retain(|(class_hash, tick)| last_uses.get(class_hash) == Some(tick));
+        }
+    }
+
+    /// Returns the number of tracked classes.
+    pub(crate) fn len(&self) -> usize {
+        self.last_uses.len()
+    }
+
+    /// Returns whether `class_hash` is tracked.
+    pub(crate) fn contains(&self, class_hash: &ClassHash) -> bool {
+        self.last_uses.contains_key(class_hash)
+    }
+
+    /// Stops tracking `class_hash`, its uses are skipped when popped.
+    pub(crate) fn remove(&mut self, class_hash: &ClassHash) {
+        self.last_uses.remove(class_hash);
+    }
+
+    /// Stops tracking the least recently used class and returns it.
+    pub(crate) fn pop_least_recently_used(&mut self) -> Option<ClassHash> {
+        while let Some((class_hash, tick)) = self.uses.pop_front() {
+            if self.last_uses.get(&class_hash) == Some(&tick) {
+                self.last_uses.remove(&class_hash);
+                return Some(class_hash);
+            }
+        }
+        None
+    }
+}
+
 impl<T: StateReader> StateReader for CachedState<T> {
     /// Returns the class hash for a given contract address.
     fn get_class_hash_at(&self, contract_address: &Address) -> Result<ClassHash, StateError> {
@@ -165,6 +388,30 @@ impl<T: StateReader> StateReader for CachedState<T> {
             .cloned()
     }
 
+    /// Returns the storage entries of the state reader under the given contract instance,
+    /// overridden by the cached reads and then by the cached writes.
+    fn storage_entries_for(&self, contract_address: &Address) -> Vec<(ClassHash, Felt252)> {
+        let mut entries: HashMap<ClassHash, Felt252> = self
+            .state_reader
+            .storage_entries_for(contract_address)
+            .into_iter()
+            .collect();
+        for ((address, key), value) in self
+            .cache
+            .storage_initial_values
+            .iter()
+            .chain(self.cache.storage_writes.iter())
+        {
+            if address == contract_address {
+                entries.insert(*key, value.clone());
+            }
+        }
+
+        let mut entries: Vec<(ClassHash, Felt252)> = entries.into_iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        entries
+    }
+
     /// Returns the contract class for a given class hash.
     fn get_contract_class(&self, class_hash: &ClassHash) -> Result<CompiledClass, StateError> {
         // This method can receive both compiled_class_hash & class_hash and return both casm and deprecated contract classes
@@ -217,6 +464,7 @@ impl<T: StateReader> State for CachedState<T> {
         match self.contract_classes.as_mut() {
             Some(x) => {
                 x.insert(*class_hash, contract_class.clone());
+                self.untrack_written_class(*class_hash);
             }
             None => {
                 self.contract_classes = Some(HashMap::new());
@@ -296,6 +544,7 @@ impl<T: StateReader> State for CachedState<T> {
             .as_mut()
             .ok_or(StateError::MissingCasmClassCache)?
             .insert(compiled_class_hash, casm_class);
+        self.untrack_written_class(compiled_class_hash);
         Ok(())
     }
 
@@ -445,7 +694,9 @@ impl<T: StateReader> State for CachedState<T> {
             .as_ref()
             .and_then(|x| x.get(class_hash))
         {
-            return Ok(CompiledClass::Deprecated(Arc::new(compiled_class.clone())));
+            let compiled_class = CompiledClass::Deprecated(Arc::new(compiled_class.clone()));
+            self.touch_cached_class(*class_hash);
+            return Ok(compiled_class);
         }
         // I: CASM CONTRACT CLASS : COMPILED_CLASS_HASH
         if let Some(compiled_class) = self
@@ -453,18 +704,25 @@ impl<T: StateReader> State for CachedState<T> {
             .as_ref()
             .and_then(|x| x.get(class_hash))
         {
-            return Ok(CompiledClass::Casm(Arc::new(compiled_class.clone())));
+            let compiled_class = CompiledClass::Casm(Arc::new(compiled_class.clone()));
+            self.touch_cached_class(*class_hash);
+            return Ok(compiled_class);
         }
         // I: CASM CONTRACT CLASS : CLASS_HASH
-        if let Some(compiled_class_hash) =
-            self.cache.class_hash_to_compiled_class_hash.get(class_hash)
+        if let Some(compiled_class_hash) = self
+            .cache
+            .class_hash_to_compiled_class_hash
+            .get(class_hash)
+            .copied()
         {
-            if let Some(casm_class) = &mut self
+            if let Some(casm_class) = self
                 .casm_contract_classes
                 .as_ref()
-                .and_then(|m| m.get(compiled_class_hash))
+                .and_then(|m| m.get(&compiled_class_hash))
             {
-                return Ok(CompiledClass::Casm(Arc::new(casm_class.clone())));
+                let casm_class = CompiledClass::Casm(Arc::new(casm_class.clone()));
+                self.touch_cached_class(compiled_class_hash);
+                return Ok(casm_class);
             }
         }
         // II: FETCHING FROM STATE_READER
@@ -473,12 +731,16 @@ impl<T: StateReader> State for CachedState<T> {
             CompiledClass::Casm(ref class) => {
                 // We call this method instead of state_reader's in order to update the cache's class_hash_initial_values map
                 let compiled_class_hash = self.get_compiled_class_hash(class_hash)?;
-                self.casm_contract_classes
-                    .as_mut()
-                    .and_then(|m| m.insert(compiled_class_hash, class.as_ref().clone()));
+                if let Some(casm_classes) = self.casm_contract_classes.as_mut() {
+                    casm_classes.insert(compiled_class_hash, class.as_ref().clone());
+                    self.track_loaded_class(compiled_class_hash);
+                }
             }
             CompiledClass::Deprecated(ref contract) => {
-                self.set_contract_class(class_hash, &contract.clone())?
+                self.contract_classes
+                    .get_or_insert_with(HashMap::new)
+                    .insert(*class_hash, contract.as_ref().clone());
+                self.track_loaded_class(*class_hash);
             }
         }
         Ok(contract)
@@ -489,9 +751,10 @@ impl<T: StateReader> State for CachedState<T> {
 mod tests {
     use super::*;
 
-    use crate::state::in_memory_state_reader::InMemoryStateReader;
+    use crate::{state::in_memory_state_reader::InMemoryStateReader, utils::CompiledClassHash};
 
     use num_traits::One;
+    use std::cell::Cell;
 
     /// Test checks if class hashes and nonces are correctly fetched from the state reader.
     /// It also tests the increment_nonce method.
@@ -629,6 +892,39 @@ mod tests {
         assert_eq!(new_result.unwrap(), new_value);
     }
 
+    #[test]
+    fn readonly_views_are_isolated() {
+        let contract_address = Address(31.into());
+        let storage_entry = (contract_address, [18; 32]);
+
+        let mut parent = CachedState::new(Arc::new(InMemoryStateReader::default()), None, None);
+        parent.set_storage_at(&storage_entry, Felt252::new(1));
+        let parent = Arc::new(parent);
+
+        let mut first_view = parent.readonly_view();
+        let mut second_view = parent.readonly_view();
+        assert_eq!(
+            first_view.get_storage_at(&storage_entry).unwrap(),
+            Felt252::new(1)
+        );
+
+        first_view.set_storage_at(&storage_entry, Felt252::new(2));
+        second_view.set_storage_at(&storage_entry, Felt252::new(3));
+
+        assert_eq!(
+            first_view.get_storage_at(&storage_entry).unwrap(),
+            Felt252::new(2)
+        );
+        assert_eq!(
+            second_view.get_storage_at(&storage_entry).unwrap(),
+            Felt252::new(3)
+        );
+        assert_eq!(
+            StateReader::get_storage_at(parent.as_ref(), &storage_entry).unwrap(),
+            Felt252::new(1)
+        );
+    }
+
     /// This test ensures that an error is thrown when trying to set contract classes twice.
     #[test]
     fn set_contract_classes_twice_error_test() {
@@ -810,4 +1106,305 @@ mod tests {
 
         assert_eq!(changes, expected_changes);
     }
+
+    /// State reader that counts how many times it was queried.
+    #[derive(Debug, Default)]
+    struct CountingStateReader {
+        inner: InMemoryStateReader,
+        fetches: Cell<usize>,
+    }
+
+    impl CountingStateReader {
+        fn fetch(&self) {
+            self.fetches.set(self.fetches.get() + 1);
+        }
+    }
+
+    impl StateReader for CountingStateReader {
+        fn get_contract_class(&self, class_hash: &ClassHash) -> Result<CompiledClass, StateError> {
+            self.fetch();
+            self.inner.get_contract_class(class_hash)
+        }
+
+        fn get_class_hash_at(&self, contract_address: &Address) -> Result<ClassHash, StateError> {
+            self.fetch();
+            self.inner.get_class_hash_at(contract_address)
+        }
+
+        fn get_nonce_at(&self, contract_address: &Address) -> Result<Felt252, StateError> {
+            self.fetch();
+            self.inner.get_nonce_at(contract_address)
+        }
+
+        fn get_storage_at(&self, storage_entry: &StorageEntry) -> Result<Felt252, StateError> {
+            self.fetch();
+            self.inner.get_storage_at(storage_entry)
+        }
+
+        fn get_compiled_class_hash(
+            &self,
+            class_hash: &ClassHash,
+        ) -> Result<CompiledClassHash, StateError> {
+            self.fetch();
+            self.inner.get_compiled_class_hash(class_hash)
+        }
+    }
+
+    #[test]
+    fn prefetch_populates_cache() {
+        let contract_address = Address(4242.into());
+        let class_hash = [3; 32];
+        let nonce = Felt252::new(7);
+        let storage_entry = (contract_address.clone(), [101; 32]);
+        let storage_value = Felt252::new(1);
+        let contract_class =
+            ContractClass::from_path("starknet_programs/raw_contract_classes/class_with_abi.json")
+                .unwrap();
+
+        let mut state_reader = CountingStateReader::default();
+        state_reader
+            .inner
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .inner
+            .address_to_nonce_mut()
+            .insert(contract_address.clone(), nonce.clone());
+        state_reader
+            .inner
+            .address_to_storage_mut()
+            .insert(storage_entry.clone(), storage_value.clone());
+        state_reader
+            .inner
+            .class_hash_to_contract_class_mut()
+            .insert(class_hash, contract_class.clone());
+
+        let state_reader = Arc::new(state_reader);
+        let mut cached_state = CachedState::new(state_reader.clone(), None, None);
+
+        // Without prefetching, every first access hits the state reader
+        assert_eq!(
+            State::get_storage_at(&mut cached_state, &storage_entry).unwrap(),
+            storage_value
+        );
+        assert_eq!(state_reader.fetches.get(), 1);
+
+        // Cached entries are skipped, the rest is fetched once
+        cached_state
+            .prefetch(
+                &[storage_entry.clone()],
+                &[class_hash],
+                &[contract_address.clone()],
+            )
+            .unwrap();
+        assert_eq!(state_reader.fetches.get(), 4);
+
+        assert_eq!(
+            State::get_storage_at(&mut cached_state, &storage_entry).unwrap(),
+            storage_value
+        );
+        assert_eq!(
+            State::get_class_hash_at(&mut cached_state, &contract_address).unwrap(),
+            class_hash
+        );
+        assert_eq!(
+            State::get_nonce_at(&mut cached_state, &contract_address).unwrap(),
+            nonce
+        );
+        assert_eq!(
+            State::get_contract_class(&mut cached_state, &class_hash).unwrap(),
+            CompiledClass::Deprecated(Arc::new(contract_class))
+        );
+        assert_eq!(state_reader.fetches.get(), 4);
+
+        // Prefetching again doesn't fetch anything
+        cached_state
+            .prefetch(&[storage_entry], &[class_hash], &[contract_address])
+            .unwrap();
+        assert_eq!(state_reader.fetches.get(), 4);
+    }
+
+    #[test]
+    fn storage_entries_for_merges_cached_writes() {
+        let contract_address = Address(32123.into());
+        let other_address = Address(1.into());
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader.address_to_storage_mut().extend([
+            ((contract_address.clone(), [1; 32]), Felt252::from(10)),
+            ((contract_address.clone(), [2; 32]), Felt252::from(20)),
+            ((other_address.clone(), [1; 32]), Felt252::from(30)),
+        ]);
+        assert_eq!(
+            state_reader.storage_entries_for(&contract_address),
+            vec![([1; 32], 10.into()), ([2; 32], 20.into())]
+        );
+
+        let mut cached_state = CachedState::new(Arc::new(state_reader), None, None);
+        cached_state.set_storage_at(&(contract_address.clone(), [2; 32]), 21.into());
+        cached_state.set_storage_at(&(contract_address.clone(), [3; 32]), 40.into());
+        cached_state.set_storage_at(&(other_address, [3; 32]), 50.into());
+
+        assert_eq!(
+            cached_state.storage_entries_for(&contract_address),
+            vec![
+                ([1; 32], 10.into()),
+                ([2; 32], 21.into()),
+                ([3; 32], 40.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn contract_storage_root_depends_on_the_contract_storage_only() {
+        let address = Address(1.into());
+        let storage_root = |writes: &[(Address, u8, u64)]| {
+            let mut cached_state =
+                CachedState::new(Arc::new(InMemoryStateReader::default()), None, None);
+            for (address, key, value) in writes {
+                cached_state.set_storage_at(&(address.clone(), [*key; 32]), (*value).into());
+            }
+            cached_state.contract_storage_root(&address).unwrap()
+        };
+
+        let root = storage_root(&[(address.clone(), 1, 10), (address.clone(), 2, 20)]);
+        // The order of the writes doesn't matter, nor do other contracts or zeroed slots
+        assert_eq!(
+            storage_root(&[
+                (address.clone(), 2, 20),
+                (Address(2.into()), 3, 30),
+                (address.clone(), 4, 0),
+                (address.clone(), 1, 10),
+            ]),
+            root
+        );
+
+        assert_ne!(
+            storage_root(&[(address.clone(), 1, 10), (address.clone(), 2, 21)]),
+            root
+        );
+        assert_ne!(storage_root(&[(address.clone(), 1, 10)]), root);
+    }
+
+    #[test]
+    fn class_cache_capacity_evicts_least_recently_used_class() {
+        let contract_class =
+            ContractClass::from_path("starknet_programs/raw_contract_classes/class_with_abi.json")
+                .unwrap();
+        let mut state_reader = InMemoryStateReader::default();
+        for class_hash in [[1; 32], [2; 32], [3; 32]] {
+            state_reader
+                .class_hash_to_contract_class_mut()
+                .insert(class_hash, contract_class.clone());
+        }
+        let mut cached_state =
+            CachedState::new(Arc::new(state_reader), None, None).with_class_cache_capacity(2);
+
+        State::get_contract_class(&mut cached_state, &[1; 32]).unwrap();
+        State::get_contract_class(&mut cached_state, &[2; 32]).unwrap();
+        // Using the first class makes the second one the least recently used
+        State::get_contract_class(&mut cached_state, &[1; 32]).unwrap();
+        State::get_contract_class(&mut cached_state, &[3; 32]).unwrap();
+
+        assert!(cached_state.is_contract_class_cached(&[1; 32]));
+        assert!(!cached_state.is_contract_class_cached(&[2; 32]));
+        assert!(cached_state.is_contract_class_cached(&[3; 32]));
+        assert_eq!(cached_state.contract_classes().as_ref().unwrap().len(), 2);
+
+        // The evicted class is reloaded from the state reader, evicting the first one
+        assert_eq!(
+            State::get_contract_class(&mut cached_state, &[2; 32]).unwrap(),
+            CompiledClass::Deprecated(Arc::new(contract_class))
+        );
+        assert!(cached_state.is_contract_class_cached(&[2; 32]));
+        assert!(!cached_state.is_contract_class_cached(&[1; 32]));
+    }
+
+    #[test]
+    fn class_cache_capacity_keeps_written_classes() {
+        let contract_class =
+            ContractClass::from_path("starknet_programs/raw_contract_classes/class_with_abi.json")
+                .unwrap();
+        let mut state_reader = InMemoryStateReader::default();
+        for class_hash in [[1; 32], [2; 32], [3; 32]] {
+            state_reader
+                .class_hash_to_contract_class_mut()
+                .insert(class_hash, contract_class.clone());
+        }
+        let mut cached_state =
+            CachedState::new(Arc::new(state_reader), None, None).with_class_cache_capacity(1);
+
+        // The first class is fetched from the state reader and then written, so it's never
+        // evicted even though it's the least recently used one
+        State::get_contract_class(&mut cached_state, &[1; 32]).unwrap();
+        cached_state
+            .set_contract_class(&[1; 32], &contract_class)
+            .unwrap();
+        State::get_contract_class(&mut cached_state, &[2; 32]).unwrap();
+        State::get_contract_class(&mut cached_state, &[3; 32]).unwrap();
+
+        assert!(cached_state.is_contract_class_cached(&[1; 32]));
+        assert!(!cached_state.is_contract_class_cached(&[2; 32]));
+        assert!(cached_state.is_contract_class_cached(&[3; 32]));
+
+        // Written classes don't count against the capacity
+        cached_state
+            .set_contract_class(&[4; 32], &contract_class)
+            .unwrap();
+        assert!(cached_state.is_contract_class_cached(&[3; 32]));
+        assert!(cached_state.is_contract_class_cached(&[4; 32]));
+        assert_eq!(cached_state.contract_classes().as_ref().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn class_cache_capacity_keeps_seeded_classes() {
+        let contract_class =
+            ContractClass::from_path("starknet_programs/raw_contract_classes/class_with_abi.json")
+                .unwrap();
+        let mut state_reader = InMemoryStateReader::default();
+        for class_hash in [[1; 32], [2; 32], [3; 32]] {
+            state_reader
+                .class_hash_to_contract_class_mut()
+                .insert(class_hash, contract_class.clone());
+        }
+        // The seeded class is unknown to the state reader, so it couldn't be fetched again
+        let seeded_classes = HashMap::from([([9; 32], contract_class.clone())]);
+        let mut cached_state = CachedState::new(Arc::new(state_reader), Some(seeded_classes), None)
+            .with_class_cache_capacity(1);
+
+        for class_hash in [[1; 32], [2; 32], [3; 32]] {
+            State::get_contract_class(&mut cached_state, &class_hash).unwrap();
+        }
+
+        assert!(!cached_state.is_contract_class_cached(&[1; 32]));
+        assert!(!cached_state.is_contract_class_cached(&[2; 32]));
+        assert!(cached_state.is_contract_class_cached(&[3; 32]));
+        assert_eq!(
+            State::get_contract_class(&mut cached_state, &[9; 32]).unwrap(),
+            CompiledClass::Deprecated(Arc::new(contract_class))
+        );
+    }
+
+    #[test]
+    fn class_cache_capacity_ignores_set_contract_classes() {
+        let contract_class =
+            ContractClass::from_path("starknet_programs/raw_contract_classes/class_with_abi.json")
+                .unwrap();
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .class_hash_to_contract_class_mut()
+            .insert([4; 32], contract_class.clone());
+        let mut cached_state =
+            CachedState::new(Arc::new(state_reader), None, None).with_class_cache_capacity(2);
+
+        let contract_classes: ContractClassCache = [[1; 32], [2; 32], [3; 32]]
+            .into_iter()
+            .map(|class_hash| (class_hash, contract_class.clone()))
+            .collect();
+        cached_state.set_contract_classes(contract_classes).unwrap();
+        assert_eq!(cached_state.contract_classes().as_ref().unwrap().len(), 3);
+
+        // Fetching a class from the state reader doesn't evict them either
+        State::get_contract_class(&mut cached_state, &[4; 32]).unwrap();
+        assert_eq!(cached_state.contract_classes().as_ref().unwrap().len(), 4);
+    }
 }
@@ -4,9 +4,11 @@ use super::{
 };
 use crate::{
     core::errors::state_errors::StateError,
+    execution::StorageWrite,
     utils::{Address, ClassHash},
 };
 use cairo_vm::felt::Felt252;
+use num_traits::Zero;
 use std::collections::HashSet;
 
 #[derive(Debug)]
@@ -16,6 +18,8 @@ pub(crate) struct ContractStorageState<'a, S: StateReader> {
     /// Maintain all read request values in chronological order
     pub(crate) read_values: Vec<Felt252>,
     pub(crate) accessed_keys: HashSet<ClassHash>,
+    /// Maintain all written keys and values in chronological order
+    pub(crate) written_values: Vec<StorageWrite>,
 }
 
 impl<'a, S: StateReader> ContractStorageState<'a, S> {
@@ -25,6 +29,7 @@ impl<'a, S: StateReader> ContractStorageState<'a, S> {
             contract_address,
             read_values: Vec::new(),
             accessed_keys: HashSet::new(),
+            written_values: Vec::new(),
         }
     }
 
@@ -38,9 +43,76 @@ impl<'a, S: StateReader> ContractStorageState<'a, S> {
         Ok(value)
     }
 
-    pub(crate) fn write(&mut self, address: &ClassHash, value: Felt252) {
+    /// Same as [`Self::read`], but returns `None` for slots that were never written instead
+    /// of zero, so that they can be told apart from slots explicitly set to zero.
+    pub(crate) fn try_storage_read(
+        &mut self,
+        address: &ClassHash,
+    ) -> Result<Option<Felt252>, StateError> {
+        let value = self.read(address)?;
+        let storage_entry = (self.contract_address.clone(), *address);
+        if !value.is_zero() || self.state.cache.storage_writes.contains_key(&storage_entry) {
+            return Ok(Some(value));
+        }
+
+        // Unset slots are cached as zero, so only the state reader can tell them apart
+        match self.state.state_reader.get_storage_at(&storage_entry) {
+            Ok(_) => Ok(Some(value)),
+            Err(
+                StateError::EmptyKeyInStorage
+                | StateError::NoneStoragLeaf(_)
+                | StateError::NoneStorage(_)
+                | StateError::NoneContractState(_),
+            ) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes `value` at `address`. `n_preceding_calls` is the number of internal calls the
+    /// contract performed before this write.
+    pub(crate) fn write(&mut self, address: &ClassHash, value: Felt252, n_preceding_calls: usize) {
         self.accessed_keys.insert(*address);
+        self.written_values.push(StorageWrite::new(
+            *address,
+            value.clone(),
+            n_preceding_calls,
+        ));
         self.state
             .set_storage_at(&(self.contract_address.clone(), *address), value);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::in_memory_state_reader::InMemoryStateReader;
+    use std::sync::Arc;
+
+    #[test]
+    fn try_storage_read_distinguishes_unset_slots_from_zero() {
+        let contract_address = Address(1111.into());
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_storage_mut()
+            .insert((contract_address.clone(), [1; 32]), Felt252::zero());
+        let mut state = CachedState::new(Arc::new(state_reader), None, None);
+        let mut storage_state = ContractStorageState::new(&mut state, contract_address);
+        storage_state.write(&[2; 32], Felt252::zero(), 0);
+
+        // Explicitly set to zero, in the state reader and in the cache
+        assert_eq!(
+            storage_state.try_storage_read(&[1; 32]).unwrap(),
+            Some(Felt252::zero())
+        );
+        assert_eq!(
+            storage_state.try_storage_read(&[2; 32]).unwrap(),
+            Some(Felt252::zero())
+        );
+        // Never written
+        assert_eq!(storage_state.try_storage_read(&[3; 32]).unwrap(), None);
+
+        // Plain reads keep zero-filling unset slots
+        assert_eq!(storage_state.read(&[3; 32]).unwrap(), Felt252::zero());
+        assert_eq!(storage_state.read_values, vec![Felt252::zero(); 4]);
+    }
+}
@@ -125,6 +125,17 @@ impl StateReader for InMemoryStateReader {
             .copied()
     }
 
+    fn storage_entries_for(&self, contract_address: &Address) -> Vec<(ClassHash, Felt252)> {
+        let mut entries: Vec<(ClassHash, Felt252)> = self
+            .address_to_storage
+            .iter()
+            .filter(|((address, _), _)| address == contract_address)
+            .map(|((_, key), value)| (*key, value.clone()))
+            .collect();
+        entries.sort_by_key(|(key, _)| *key);
+        entries
+    }
+
     fn get_contract_class(&self, class_hash: &ClassHash) -> Result<CompiledClass, StateError> {
         // Deprecated contract classes dont have a compiled_class_hash, we dont need to fetch it
         if let Some(compiled_class) = self.class_hash_to_contract_class.get(class_hash) {
@@ -6,6 +6,7 @@ pub mod state_cache;
 
 use crate::{
     core::errors::state_errors::StateError,
+    definitions::constants::RECENT_BLOCK_HASHES_WINDOW,
     services::api::contract_classes::compiled_class::CompiledClass,
     utils::{
         get_keys, subtract_mappings, to_cache_state_storage_mapping, to_state_diff_storage_mapping,
@@ -32,6 +33,9 @@ pub struct BlockInfo {
     pub gas_price: u64,
     /// The sequencer address of this block.
     pub sequencer_address: Address,
+    /// Hashes of recent blocks by block number, consulted by the `get_block_hash` syscall
+    /// before the block hash contract storage.
+    pub recent_block_hashes: HashMap<u64, Felt252>,
 }
 
 impl BlockInfo {
@@ -41,9 +45,34 @@ impl BlockInfo {
             block_timestamp: 0,
             gas_price: 0,
             sequencer_address,
+            recent_block_hashes: HashMap::new(),
         }
     }
 
+    /// Moves to `next_block_info`, recording `block_hash` as the hash of the current block.
+    /// The recent block hashes are carried over, dropping the ones older than
+    /// `RECENT_BLOCK_HASHES_WINDOW` blocks.
+    pub fn advance(
+        &mut self,
+        mut next_block_info: BlockInfo,
+        block_hash: Felt252,
+    ) -> Result<(), TransactionError> {
+        self.validate_legal_progress(&next_block_info)?;
+
+        let oldest_block_number = next_block_info
+            .block_number
+            .saturating_sub(RECENT_BLOCK_HASHES_WINDOW);
+        let mut recent_block_hashes = std::mem::take(&mut self.recent_block_hashes);
+        recent_block_hashes.insert(self.block_number, block_hash);
+        recent_block_hashes.retain(|block_number, _| *block_number >= oldest_block_number);
+        next_block_info
+            .recent_block_hashes
+            .extend(recent_block_hashes);
+
+        *self = next_block_info;
+        Ok(())
+    }
+
     pub fn validate_legal_progress(
         &self,
         next_block_info: &BlockInfo,
@@ -67,6 +96,7 @@ impl Default for BlockInfo {
             block_timestamp: 0,
             gas_price: 0,
             sequencer_address: Address(0.into()),
+            recent_block_hashes: HashMap::new(),
         }
     }
 }
@@ -75,6 +105,11 @@ impl Default for BlockInfo {
 pub struct ExecutionResourcesManager {
     pub(crate) syscall_counter: HashMap<String, u64>,
     pub(crate) cairo_usage: ExecutionResources,
+    /// Total gas spent by each syscall, keyed by syscall name.
+    pub(crate) syscall_gas: HashMap<String, u128>,
+    /// Number of times each syscall was invoked, keyed by syscall name. Unlike
+    /// `syscall_counter`, syscalls are recorded even if they weren't registered in advance.
+    pub(crate) syscall_invocations: HashMap<String, usize>,
 }
 
 impl ExecutionResourcesManager {
@@ -86,10 +121,16 @@ impl ExecutionResourcesManager {
         ExecutionResourcesManager {
             syscall_counter,
             cairo_usage,
+            syscall_gas: HashMap::new(),
+            syscall_invocations: HashMap::new(),
         }
     }
 
     pub fn increment_syscall_counter(&mut self, syscall_name: &str, amount: u64) -> Option<()> {
+        *self
+            .syscall_invocations
+            .entry(syscall_name.to_string())
+            .or_default() += amount as usize;
         self.syscall_counter
             .get_mut(syscall_name)
             .map(|val| *val += amount)
@@ -100,6 +141,36 @@ impl ExecutionResourcesManager {
             .get(syscall_name)
             .map(ToOwned::to_owned)
     }
+
+    pub fn add_syscall_gas(&mut self, syscall_name: &str, gas: u128) {
+        let total = self
+            .syscall_gas
+            .entry(syscall_name.to_string())
+            .or_default();
+        *total = total.saturating_add(gas);
+    }
+
+    /// Returns the total gas spent by each syscall performed so far, including the gas of the
+    /// calls made through `call_contract`, `library_call` and `deploy`.
+    pub fn gas_by_syscall(&self) -> HashMap<String, u128> {
+        self.syscall_gas.clone()
+    }
+
+    /// Returns the number of times each syscall was invoked so far, including the syscalls of
+    /// the calls made through `call_contract`, `library_call` and `deploy`.
+    pub fn syscall_invocations(&self) -> HashMap<String, usize> {
+        self.syscall_invocations.clone()
+    }
+
+    /// Zeroes the syscall counters and clears the gas spent per syscall, so the manager can be
+    /// reused for another measurement. The tracked syscall names and the cairo usage are kept.
+    pub fn reset_counters(&mut self) {
+        self.syscall_counter
+            .values_mut()
+            .for_each(|counter| *counter = 0);
+        self.syscall_gas.clear();
+        self.syscall_invocations.clear();
+    }
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Getters)]
@@ -232,6 +303,32 @@ fn test_validate_legal_progress() {
     assert!(first_block.validate_legal_progress(&next_block).is_ok())
 }
 
+#[test]
+fn test_advance_records_recent_block_hashes() {
+    let mut block_info = BlockInfo {
+        block_number: RECENT_BLOCK_HASHES_WINDOW,
+        block_timestamp: 1,
+        recent_block_hashes: HashMap::from([(0, 10.into()), (1, 11.into())]),
+        ..Default::default()
+    };
+    let next_block = BlockInfo {
+        block_number: RECENT_BLOCK_HASHES_WINDOW + 1,
+        block_timestamp: 2,
+        ..Default::default()
+    };
+
+    block_info.advance(next_block, 12.into()).unwrap();
+
+    assert_eq!(block_info.block_number, RECENT_BLOCK_HASHES_WINDOW + 1);
+    assert_eq!(
+        block_info.recent_block_hashes,
+        HashMap::from([
+            (1, Felt252::from(11)),
+            (RECENT_BLOCK_HASHES_WINDOW, Felt252::from(12))
+        ])
+    );
+}
+
 #[cfg(test)]
 mod test {
     use std::{collections::HashMap, sync::Arc};
@@ -308,6 +405,33 @@ mod test {
         );
     }
 
+    #[test]
+    fn execution_resources_manager_reset_counters() {
+        let mut execution_resources_manager =
+            super::ExecutionResourcesManager::new(vec!["syscall1".to_string()], Default::default());
+        execution_resources_manager
+            .increment_syscall_counter("syscall1", 3)
+            .unwrap();
+        execution_resources_manager.add_syscall_gas("syscall1", 100);
+
+        execution_resources_manager.reset_counters();
+
+        assert_eq!(
+            execution_resources_manager.get_syscall_counter("syscall1"),
+            Some(0)
+        );
+        assert!(execution_resources_manager.gas_by_syscall().is_empty());
+
+        // The syscall is still tracked after the reset
+        execution_resources_manager
+            .increment_syscall_counter("syscall1", 1)
+            .unwrap();
+        assert_eq!(
+            execution_resources_manager.get_syscall_counter("syscall1"),
+            Some(1)
+        );
+    }
+
     #[test]
     fn state_diff_to_cached_state_should_return_correct_cached_state() {
         let mut state_reader = InMemoryStateReader::default();
@@ -24,6 +24,11 @@ pub trait StateReader {
         &self,
         class_hash: &ClassHash,
     ) -> Result<CompiledClassHash, StateError>;
+    /// Returns every storage key and value known under the given contract instance, sorted by
+    /// key. Readers that can't enumerate their storage return an empty list.
+    fn storage_entries_for(&self, _contract_address: &Address) -> Vec<(ClassHash, Felt252)> {
+        Vec::new()
+    }
 }
 
 pub trait State {
@@ -1,7 +1,7 @@
 #![allow(clippy::absurd_extreme_comparisons)]
 
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::ops::Add;
 
 use super::syscall_handler_errors::SyscallHandlerError;
 use super::syscall_request::{
@@ -12,6 +12,8 @@ use super::syscall_response::{
     DeployResponse, GetBlockHashResponse, GetBlockTimestampResponse, KeccakResponse,
     SyscallResponse,
 };
+use super::syscall_side_effects::SyscallSideEffects;
+use super::syscall_trace::{SyscallTrace, SyscallTraceEvent, SyscallTracer};
 use super::{
     syscall_info::get_syscall_size_from_name,
     syscall_request::{
@@ -20,13 +22,13 @@ use super::{
     },
     syscall_response::{CallContractResponse, FailureReason, ResponseBody},
 };
-use crate::definitions::block_context::BlockContext;
+use crate::definitions::block_context::{BlockContext, KeccakGasCheck};
 use crate::definitions::constants::BLOCK_HASH_CONTRACT_ADDRESS;
-use crate::execution::execution_entry_point::ExecutionResult;
+use crate::execution::execution_entry_point::{ExecutionResult, StepLimit};
 use crate::services::api::contract_classes::compiled_class::CompiledClass;
 use crate::state::cached_state::CachedState;
 use crate::state::BlockInfo;
-use crate::transaction::error::TransactionError;
+use crate::transaction::{constructor_calldata_mismatch, error::TransactionError};
 use crate::utils::calculate_sn_keccak;
 use crate::{
     core::errors::state_errors::StateError,
@@ -42,7 +44,7 @@ use crate::{
         contract_storage_state::ContractStorageState,
         state_api::{State, StateReader},
     },
-    utils::{felt_to_hash, get_big_int, get_felt_range, Address, ClassHash},
+    utils::{felt_to_hash, get_big_int, get_felt_range, storage_key, Address, ClassHash},
 };
 use cairo_vm::felt::Felt252;
 use cairo_vm::{
@@ -59,10 +61,12 @@ use num_traits::{One, ToPrimitive, Zero};
 
 const STEP: u128 = 100;
 const SYSCALL_BASE: u128 = 100 * STEP;
-const KECCAK_ROUND_COST: u128 = 180000;
+/// Flat cost of `get_execution_info`, as charged by the Starknet OS. The execution info is
+/// written to memory the OS already holds, so the signature length doesn't affect its cost.
+const GET_EXECUTION_INFO_GAS_COST: u128 = SYSCALL_BASE + 10 * STEP;
 lazy_static! {
     /// Felt->syscall map that was extracted from new_syscalls.json (Cairo 1.0 syscalls)
-    static ref SELECTOR_TO_SYSCALL: HashMap<Felt252, &'static str> = {
+    pub static ref SELECTOR_TO_SYSCALL: HashMap<Felt252, &'static str> = {
             let mut map: HashMap<Felt252, &'static str> = HashMap::with_capacity(9);
 
             map.insert(92376026794327011772951660_u128.into(), "library_call");
@@ -103,7 +107,7 @@ lazy_static! {
 
         map.insert("call_contract", SYSCALL_BASE + 10 * STEP + map["entry_point"]);
         map.insert("deploy", SYSCALL_BASE + 200 * STEP + map["entry_point"]);
-        map.insert("get_execution_info", SYSCALL_BASE + 10 * STEP);
+        map.insert("get_execution_info", GET_EXECUTION_INFO_GAS_COST);
         map.insert("library_call", map["call_contract"]);
         map.insert("replace_class", SYSCALL_BASE + 50 * STEP);
         map.insert("storage_read", SYSCALL_BASE + 50 * STEP);
@@ -115,6 +119,9 @@ lazy_static! {
 
         map
     };
+
+    /// Storage keys must be below 2^251 to fit in the storage trie.
+    static ref STORAGE_KEY_UPPER_BOUND: Felt252 = Felt252::one() << 251u32;
 }
 
 #[derive(Debug)]
@@ -132,7 +139,25 @@ pub struct BusinessLogicSyscallHandler<'a, S: StateReader> {
     pub(crate) starknet_storage_state: ContractStorageState<'a, S>,
     pub(crate) support_reverted: bool,
     pub(crate) entry_point_selector: Felt252,
-    pub(crate) selector_to_syscall: &'a HashMap<Felt252, &'static str>,
+    /// Syscalls supported by the handler, defaults to [`SELECTOR_TO_SYSCALL`].
+    pub(crate) selector_to_syscall: Cow<'a, HashMap<Felt252, &'static str>>,
+    /// Syscalls performed by the call, only collected when set to `Some`.
+    pub(crate) syscall_trace: Option<SyscallTrace>,
+    /// Name of the first syscall that failed for running out of gas.
+    pub(crate) out_of_gas_syscall: Option<String>,
+    /// Callback notified before and after every syscall.
+    pub(crate) syscall_tracer: Option<SyscallTracer<'a>>,
+    /// Aborts the execution after the syscall that reaches it.
+    pub(crate) step_limit: Option<StepLimit>,
+    /// Number of syscalls performed, only counted when `step_limit` is set.
+    pub(crate) n_executed_syscalls: usize,
+    /// Gas left when the execution was aborted by `step_limit`.
+    pub(crate) halted_remaining_gas: Option<u128>,
+    /// Gas left for the syscalls performed through this handler, shared across them.
+    pub(crate) gas_budget: Option<u128>,
+    /// Makes `post_run` skip the read-only segment validation, for runs inspected before
+    /// they finish.
+    pub(crate) skip_segment_validation: bool,
 }
 
 // TODO: execution entry point may no be a parameter field, but there is no way to generate a default for now
@@ -170,9 +195,55 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             expected_syscall_ptr: syscall_ptr,
             support_reverted,
             entry_point_selector,
-            selector_to_syscall: &SELECTOR_TO_SYSCALL,
+            selector_to_syscall: Cow::Borrowed(&SELECTOR_TO_SYSCALL),
+            syscall_trace: None,
+            out_of_gas_syscall: None,
+            syscall_tracer: None,
+            step_limit: None,
+            n_executed_syscalls: 0,
+            halted_remaining_gas: None,
+            gas_budget: None,
+            skip_segment_validation: false,
         }
     }
+    /// Replaces the selector->syscall map, e.g. to simulate a protocol version that supports
+    /// a different set of syscalls.
+    pub fn with_selector_to_syscall(
+        mut self,
+        selector_to_syscall: Cow<'a, HashMap<Felt252, &'static str>>,
+    ) -> Self {
+        self.selector_to_syscall = selector_to_syscall;
+        self
+    }
+
+    /// Sets a callback notified before every syscall with its name, selector and available
+    /// gas, and after it with the remaining gas and whether it failed.
+    pub fn with_syscall_tracer(mut self, tracer: impl FnMut(&SyscallTraceEvent<'_>) + 'a) -> Self {
+        self.syscall_tracer = Some(SyscallTracer(Box::new(tracer)));
+        self
+    }
+
+    /// Caps the gas spent by all the syscalls performed through this handler to `gas_budget`,
+    /// on top of the gas available to each of them. A syscall whose cost exceeds what is left
+    /// of the budget fails with out of gas.
+    pub fn with_gas_budget(mut self, gas_budget: u128) -> Self {
+        self.gas_budget = Some(gas_budget);
+        self
+    }
+
+    /// Makes [`Self::post_run`] skip the validation of the read-only segments, which fails on
+    /// runs that didn't finish, e.g. when stepping through a contract in a debugger. Executions
+    /// should keep it enabled.
+    pub fn with_skip_segment_validation(mut self, skip_segment_validation: bool) -> Self {
+        self.skip_segment_validation = skip_segment_validation;
+        self
+    }
+
+    /// Returns what is left of the budget set with [`Self::with_gas_budget`].
+    pub fn gas_budget(&self) -> Option<u128> {
+        self.gas_budget
+    }
+
     pub fn default_with_state(state: &'a mut CachedState<S>) -> Self {
         BusinessLogicSyscallHandler::new_for_testing(
             BlockInfo::default(),
@@ -226,7 +297,15 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             expected_syscall_ptr,
             support_reverted: false,
             entry_point_selector,
-            selector_to_syscall: &SELECTOR_TO_SYSCALL,
+            selector_to_syscall: Cow::Borrowed(&SELECTOR_TO_SYSCALL),
+            syscall_trace: None,
+            out_of_gas_syscall: None,
+            syscall_tracer: None,
+            step_limit: None,
+            n_executed_syscalls: 0,
+            halted_remaining_gas: None,
+            gas_budget: None,
+            skip_segment_validation: false,
         }
     }
 
@@ -236,56 +315,111 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             .increment_syscall_counter(syscall_name, 1);
     }
 
+    /// Charges `class_load_gas_cost` if the class for `class_hash` isn't cached yet, and
+    /// caches it so that later calls to it are not charged again.
+    /// Returns the remaining gas, or an out of gas failure response if it isn't enough.
+    fn charge_class_load(
+        &mut self,
+        vm: &mut VirtualMachine,
+        syscall_name: &str,
+        class_hash: &ClassHash,
+        remaining_gas: u128,
+    ) -> Result<Result<u128, SyscallResponse>, SyscallHandlerError> {
+        let class_load_gas = self.block_context.class_load_gas_cost;
+        if class_load_gas == 0
+            || self
+                .starknet_storage_state
+                .state
+                .is_contract_class_cached(class_hash)
+        {
+            return Ok(Ok(remaining_gas));
+        }
+
+        if remaining_gas < class_load_gas {
+            let response = self.out_of_gas_failure(vm, syscall_name, b"Syscall out of gas")?;
+            return Ok(Err(SyscallResponse {
+                gas: remaining_gas,
+                body: Some(response),
+            }));
+        }
+
+        self.starknet_storage_state
+            .state
+            .prefetch(&[], &[*class_hash], &[])?;
+
+        Ok(Ok(remaining_gas - class_load_gas))
+    }
+
+    /// Returns a failure response if the calldata between `calldata_start` and `calldata_end`
+    /// is longer than `max_calldata_length`, so that it is rejected before being read.
+    fn check_calldata_length(
+        &mut self,
+        vm: &mut VirtualMachine,
+        calldata_start: Relocatable,
+        calldata_end: Relocatable,
+        remaining_gas: u128,
+    ) -> Result<Option<SyscallResponse>, SyscallHandlerError> {
+        let calldata_length = calldata_end.offset.saturating_sub(calldata_start.offset);
+        if calldata_length <= self.block_context.max_calldata_length {
+            return Ok(None);
+        }
+
+        let response = self.failure_from_error_msg(vm, b"Calldata too long")?;
+        Ok(Some(SyscallResponse {
+            gas: remaining_gas,
+            body: Some(response),
+        }))
+    }
+
     fn call_contract_helper(
         &mut self,
         vm: &mut VirtualMachine,
         remaining_gas: u128,
         execution_entry_point: ExecutionEntryPoint,
     ) -> Result<SyscallResponse, SyscallHandlerError> {
+        let result = execution_entry_point.execute(
+            self.starknet_storage_state.state,
+            &self.block_context,
+            &mut self.resources_manager,
+            &mut self.tx_execution_context,
+            false,
+            self.block_context.invoke_tx_max_n_steps,
+            false,
+        );
         let ExecutionResult {
             call_info,
             revert_error,
             ..
-        } = execution_entry_point
-            .execute(
-                self.starknet_storage_state.state,
-                &self.block_context,
-                &mut self.resources_manager,
-                &mut self.tx_execution_context,
-                false,
-                self.block_context.invoke_tx_max_n_steps,
-                false,
-            )
-            .map_err(|err| SyscallHandlerError::ExecutionError(err.to_string()))?;
+        } = match result {
+            // A missing selector fails the call the way it would on-chain instead of
+            // aborting the whole execution.
+            Err(TransactionError::EntryPointNotFound) => {
+                let response = self.failure_from_error_msg(vm, b"ENTRYPOINT_NOT_FOUND")?;
+                return Ok(SyscallResponse {
+                    gas: remaining_gas,
+                    body: Some(response),
+                });
+            }
+            result => result.map_err(|err| SyscallHandlerError::ExecutionError(err.to_string()))?,
+        };
 
         let call_info = call_info.ok_or(SyscallHandlerError::ExecutionError(
             revert_error.unwrap_or("Execution error".to_string()),
         ))?;
 
-        let retdata_maybe_reloc = call_info
-            .retdata
-            .clone()
-            .into_iter()
-            .map(|item| MaybeRelocatable::from(Felt252::new(item)))
-            .collect::<Vec<MaybeRelocatable>>();
-
-        let retdata_start = self.allocate_segment(vm, retdata_maybe_reloc)?;
-        let retdata_end = (retdata_start + call_info.retdata.len())?;
-
         let remaining_gas = remaining_gas.saturating_sub(call_info.gas_consumed);
 
         let gas = remaining_gas;
-        let body = if call_info.failure_flag {
-            Some(ResponseBody::Failure(FailureReason {
-                retdata_start,
-                retdata_end,
-            }))
-        } else {
-            Some(ResponseBody::CallContract(CallContractResponse {
-                retdata_start,
-                retdata_end,
-            }))
-        };
+        let body = Some(call_info.result().into_response_body(
+            self,
+            vm,
+            |retdata_start, retdata_end| {
+                ResponseBody::CallContract(CallContractResponse {
+                    retdata_start,
+                    retdata_end,
+                })
+            },
+        )?);
 
         // update syscall handler information
         self.starknet_storage_state
@@ -320,31 +454,49 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         class_hash_bytes: ClassHash,
         constructor_calldata: Vec<Felt252>,
         remainig_gas: u128,
+        deploy_from_zero: bool,
     ) -> Result<CallResult, StateError> {
-        let compiled_class = if let Ok(compiled_class) = self
+        let compiled_class = match self
             .starknet_storage_state
             .state
             .get_contract_class(&class_hash_bytes)
         {
-            compiled_class
-        } else {
-            return Ok(CallResult {
-                gas_consumed: 0,
-                is_success: false,
-                retdata: vec![Felt252::from_bytes_be(b"CLASS_HASH_NOT_FOUND").into()],
-            });
+            Ok(compiled_class) => compiled_class,
+            Err(err) => {
+                let error_msg: &[u8] = match err {
+                    StateError::MalformedContractClass(..) => b"CLASS_DESERIALIZATION_FAILED",
+                    _ => b"CLASS_HASH_NOT_FOUND",
+                };
+                return Ok(CallResult {
+                    gas_consumed: 0,
+                    is_success: false,
+                    retdata: vec![Felt252::from_bytes_be(error_msg).into()],
+                });
+            }
         };
 
+        if let Some(expected) = constructor_calldata_mismatch(
+            &self.block_context,
+            &compiled_class,
+            &constructor_calldata,
+        ) {
+            return Err(StateError::ConstructorArgumentMismatch(
+                expected,
+                constructor_calldata.len(),
+            ));
+        }
+
         if self.constructor_entry_points_empty(compiled_class)? {
             if !constructor_calldata.is_empty() {
                 return Err(StateError::ConstructorCalldataEmpty());
             }
 
-            let call_info = CallInfo::empty_constructor_call(
+            let mut call_info = CallInfo::empty_constructor_call(
                 contract_address.clone(),
                 self.contract_address.clone(),
                 Some(class_hash_bytes),
             );
+            call_info.deploy_from_zero = Some(deploy_from_zero);
             self.internal_calls.push(call_info.clone());
 
             return Ok(call_info.result());
@@ -377,9 +529,10 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             )
             .map_err(|_| StateError::ExecutionEntryPoint())?;
 
-        let call_info = call_info.ok_or(StateError::CustomError(
+        let mut call_info = call_info.ok_or(StateError::CustomError(
             revert_error.unwrap_or("Execution error".to_string()),
         ))?;
+        call_info.deploy_from_zero = Some(deploy_from_zero);
 
         self.internal_calls.push(call_info.clone());
 
@@ -387,7 +540,8 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
     }
 
     fn syscall_storage_write(&mut self, key: Felt252, value: Felt252) {
-        self.starknet_storage_state.write(&key.to_be_bytes(), value)
+        self.starknet_storage_state
+            .write(&storage_key(&key), value, self.internal_calls.len())
     }
 
     pub fn syscall(
@@ -396,7 +550,7 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         syscall_ptr: Relocatable,
     ) -> Result<(), SyscallHandlerError> {
         let selector = get_big_int(vm, syscall_ptr)?;
-        let syscall_name = self.selector_to_syscall.get(&selector).ok_or(
+        let syscall_name: &'static str = self.selector_to_syscall.get(&selector).copied().ok_or(
             SyscallHandlerError::SelectorNotInHandlerMap(selector.to_string()),
         )?;
 
@@ -406,11 +560,14 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             .ok_or(MathError::Felt252ToU64Conversion(Box::new(initial_gas)))?;
 
         // Advance SyscallPointer as the first two cells contain the selector & gas
-        let mut syscall_ptr: Relocatable =
-            (syscall_ptr + 2_usize).map_err(SyscallHandlerError::from)?;
+        let mut syscall_ptr = advance_syscall_ptr(syscall_ptr, 2)?;
 
         let request = self.read_and_validate_syscall_request(vm, &mut syscall_ptr, syscall_name)?;
 
+        if let Some(syscall_trace) = self.syscall_trace.as_mut() {
+            syscall_trace.push(selector.clone(), request.trace_args(vm)?);
+        }
+
         // Check and reduce gas (after validating the syscall selector for consistency wth the OS).
         let required_gas = SYSCALL_GAS_COST
             .get(syscall_name)
@@ -419,14 +576,19 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
                 selector.to_string(),
             ))?;
 
-        let response = if initial_gas < required_gas {
-            let out_of_gas_felt = Felt252::from_bytes_be("Out of gas".as_bytes());
-            let retdata_start =
-                self.allocate_segment(vm, vec![MaybeRelocatable::from(out_of_gas_felt)])?;
-            let response_body = ResponseBody::Failure(FailureReason {
-                retdata_start,
-                retdata_end: (retdata_start + 1)?,
+        if let Some(SyscallTracer(tracer)) = self.syscall_tracer.as_mut() {
+            tracer(&SyscallTraceEvent::Before {
+                name: syscall_name,
+                selector: &selector,
+                gas: initial_gas,
             });
+        }
+
+        let exceeds_budget = self
+            .gas_budget
+            .map_or(false, |gas_budget| gas_budget < required_gas);
+        let response = if initial_gas < required_gas || exceeds_budget {
+            let response_body = self.out_of_gas_failure(vm, syscall_name, b"Out of gas")?;
 
             SyscallResponse {
                 gas: initial_gas,
@@ -437,6 +599,20 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             let remaining_gas = initial_gas - required_gas;
             self.execute_syscall(request, remaining_gas, vm)?
         };
+        let gas_spent = initial_gas.saturating_sub(response.gas);
+        self.resources_manager
+            .add_syscall_gas(syscall_name, gas_spent);
+        if let Some(gas_budget) = self.gas_budget.as_mut() {
+            *gas_budget = gas_budget.saturating_sub(gas_spent);
+        }
+
+        if let Some(SyscallTracer(tracer)) = self.syscall_tracer.as_mut() {
+            tracer(&SyscallTraceEvent::After {
+                name: syscall_name,
+                gas: response.gas,
+                failed: matches!(response.body, Some(ResponseBody::Failure(_))),
+            });
+        }
 
         // Write response to the syscall segment.
         self.expected_syscall_ptr = vm
@@ -444,6 +620,14 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             .get_relocatable()
             .ok_or(MemoryError::WriteArg)?;
 
+        if let Some(step_limit) = self.step_limit {
+            self.n_executed_syscalls += 1;
+            if step_limit.is_reached(self.n_executed_syscalls, response.gas) {
+                self.halted_remaining_gas = Some(response.gas);
+                return Err(SyscallHandlerError::ExecutionHalted);
+            }
+        }
+
         Ok(())
     }
 
@@ -482,28 +666,29 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         let current_block_number = self.block_context.block_info.block_number;
 
         if block_number > current_block_number - 10 {
-            let out_of_range_felt = Felt252::from_bytes_be("Block number out of range".as_bytes());
-            let retdata_start =
-                self.allocate_segment(vm, vec![MaybeRelocatable::from(out_of_range_felt)])?;
-            let failure = FailureReason {
-                retdata_start,
-                retdata_end: (retdata_start + 1)?,
-            };
+            let response = self.failure_from_error_msg(vm, b"Block number out of range")?;
 
             return Ok(SyscallResponse {
                 gas: remaining_gas,
-                body: Some(ResponseBody::Failure(failure)),
+                body: Some(response),
             });
         }
 
         // FIXME: Update this after release.
         const V_0_12_0_FIRST_BLOCK: u64 = 0;
-        let block_hash = if block_number < V_0_12_0_FIRST_BLOCK {
+        let recent_block_hash = self
+            .block_context
+            .block_info
+            .recent_block_hashes
+            .get(&block_number);
+        let block_hash = if let Some(block_hash) = recent_block_hash {
+            block_hash.clone()
+        } else if block_number < V_0_12_0_FIRST_BLOCK {
             Felt252::zero()
         } else {
             self.starknet_storage_state.state.get_storage_at(&(
                 BLOCK_HASH_CONTRACT_ADDRESS.clone(),
-                Felt252::new(block_number).to_be_bytes(),
+                storage_key(&Felt252::new(block_number)),
             ))?
         };
 
@@ -515,7 +700,44 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         })
     }
 
-    pub(crate) fn post_run(
+    /// Returns the side effects collected so far: emitted events and messages, internal calls
+    /// and the accesses to the contract's storage.
+    pub fn side_effects(&self) -> SyscallSideEffects {
+        SyscallSideEffects {
+            events: self.events.clone(),
+            l2_to_l1_messages: self.l2_to_l1_messages.clone(),
+            internal_calls: self.internal_calls.clone(),
+            storage_read_values: self.starknet_storage_state.read_values.clone(),
+            accessed_storage_keys: self.starknet_storage_state.accessed_keys.clone(),
+            storage_writes: self.starknet_storage_state.written_values.clone(),
+        }
+    }
+
+    /// Returns the calls performed by the contract so far, in execution order.
+    pub fn internal_calls(&self) -> &[CallInfo] {
+        &self.internal_calls
+    }
+
+    /// Returns the events emitted by the contract so far.
+    pub fn events(&self) -> &[OrderedEvent] {
+        &self.events
+    }
+
+    /// Returns the messages to L1 sent by the contract so far.
+    pub fn l2_to_l1_messages(&self) -> &[OrderedL2ToL1Message] {
+        &self.l2_to_l1_messages
+    }
+
+    /// Reads the contract's storage at `key` like the `storage_read` syscall, but returns `None`
+    /// for a slot that was never written instead of zero.
+    pub fn try_storage_read(&mut self, key: [u8; 32]) -> Result<Option<Felt252>, StateError> {
+        self.starknet_storage_state.try_storage_read(&key)
+    }
+
+    /// Finalizes a run through this handler: checks that the syscall pointer stopped where the
+    /// performed syscalls left it and validates the read-only segments, unless disabled with
+    /// [`Self::with_skip_segment_validation`].
+    pub fn post_run(
         &self,
         runner: &mut VirtualMachine,
         syscall_stop_ptr: Relocatable,
@@ -527,6 +749,9 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
                 syscall_stop_ptr,
             ));
         }
+        if self.skip_segment_validation {
+            return Ok(());
+        }
         self.validate_read_only_segments(runner)
     }
 
@@ -547,7 +772,11 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             };
 
             if seg_size != used_size.into() {
-                return Err(TransactionError::OutOfBound);
+                return Err(TransactionError::OutOfBound(
+                    segment_ptr.segment_index,
+                    seg_size,
+                    used_size,
+                ));
             }
             runner.mark_address_range_as_accessed(segment_ptr, used_size)?;
         }
@@ -558,19 +787,38 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
 impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
     fn emit_event(
         &mut self,
-        vm: &VirtualMachine,
+        vm: &mut VirtualMachine,
         request: EmitEventRequest,
         remaining_gas: u128,
     ) -> Result<SyscallResponse, SyscallHandlerError> {
         let order = self.tx_execution_context.n_emitted_events;
         let keys: Vec<Felt252> = get_felt_range(vm, request.keys_start, request.keys_end)?;
         let data: Vec<Felt252> = get_felt_range(vm, request.data_start, request.data_end)?;
+
+        // Charge for the event payload on top of the syscall base cost.
+        let payload_gas = self
+            .block_context
+            .emit_event_key_gas_cost
+            .saturating_mul(keys.len() as u128)
+            .saturating_add(
+                self.block_context
+                    .emit_event_data_gas_cost
+                    .saturating_mul(data.len() as u128),
+            );
+        if remaining_gas < payload_gas {
+            let response = self.out_of_gas_failure(vm, "emit_event", b"Syscall out of gas")?;
+            return Ok(SyscallResponse {
+                gas: remaining_gas,
+                body: Some(response),
+            });
+        }
+
         self.events.push(OrderedEvent::new(order, keys, data));
 
         // Update events count.
         self.tx_execution_context.n_emitted_events += 1;
         Ok(SyscallResponse {
-            gas: remaining_gas,
+            gas: remaining_gas - payload_gas,
             body: None,
         })
     }
@@ -603,18 +851,19 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         remaining_gas: u128,
     ) -> Result<SyscallResponse, SyscallHandlerError> {
         if request.reserved != 0.into() {
-            let retdata_start = self.allocate_segment(
-                vm,
-                vec![Felt252::from_bytes_be(b"Unsupported address domain").into()],
-            )?;
-            let retdata_end = retdata_start.add(1)?;
+            let response = self.failure_from_error_msg(vm, b"Unsupported address domain")?;
 
             return Ok(SyscallResponse {
                 gas: remaining_gas,
-                body: Some(ResponseBody::Failure(FailureReason {
-                    retdata_start,
-                    retdata_end,
-                })),
+                body: Some(response),
+            });
+        }
+        if request.key >= *STORAGE_KEY_UPPER_BOUND {
+            let response = self.failure_from_error_msg(vm, b"Invalid storage key")?;
+
+            return Ok(SyscallResponse {
+                gas: remaining_gas,
+                body: Some(response),
             });
         }
 
@@ -664,6 +913,31 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         vm.insert_value::<Felt252>(res_segment, tx_info.nonce.clone())?;
         res_segment = (res_segment + 1)?;
 
+        // Tail of the v2 TxInfo layout, zeroed for v1 transactions: resource_bounds span, tip,
+        // paymaster_data span, nonce and fee data availability modes and
+        // account_deployment_data span. The spans are empty.
+        let v2_tail_start = res_segment;
+        let empty_span = MaybeRelocatable::from(signature_end);
+        let zero = MaybeRelocatable::from(Felt252::zero());
+        let v2_tail = [
+            empty_span.clone(),
+            empty_span.clone(),
+            zero.clone(),
+            empty_span.clone(),
+            empty_span.clone(),
+            zero.clone(),
+            zero,
+            empty_span.clone(),
+            empty_span,
+        ];
+        let v2_tail_len = v2_tail.len();
+        for value in v2_tail {
+            vm.insert_value(res_segment, value)?;
+            res_segment = (res_segment + 1)?;
+        }
+        // v1 contracts never read the tail, it mustn't count as memory holes for them
+        vm.mark_address_range_as_accessed(v2_tail_start, v2_tail_len)?;
+
         let block_info_ptr = res_segment;
         vm.insert_value::<Felt252>(res_segment, block_info.block_number.into())?;
         res_segment = (res_segment + 1)?;
@@ -695,7 +969,24 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         request: CallContractRequest,
         remaining_gas: u128,
     ) -> Result<SyscallResponse, SyscallHandlerError> {
+        if let Some(response) = self.check_calldata_length(
+            vm,
+            request.calldata_start,
+            request.calldata_end,
+            remaining_gas,
+        )? {
+            return Ok(response);
+        }
         let calldata = get_felt_range(vm, request.calldata_start, request.calldata_end)?;
+
+        let class_hash =
+            State::get_class_hash_at(self.starknet_storage_state.state, &request.contract_address)?;
+        let remaining_gas =
+            match self.charge_class_load(vm, "call_contract", &class_hash, remaining_gas)? {
+                Ok(remaining_gas) => remaining_gas,
+                Err(response) => return Ok(response),
+            };
+
         let execution_entry_point = ExecutionEntryPoint::new(
             request.contract_address,
             calldata,
@@ -717,18 +1008,19 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         remaining_gas: u128,
     ) -> Result<SyscallResponse, SyscallHandlerError> {
         if request.reserved != Felt252::zero() {
-            let retdata_start = self.allocate_segment(
-                vm,
-                vec![Felt252::from_bytes_be(b"Unsupported address domain").into()],
-            )?;
-            let retdata_end = retdata_start.add(1)?;
+            let response = self.failure_from_error_msg(vm, b"Unsupported address domain")?;
 
             return Ok(SyscallResponse {
                 gas: remaining_gas,
-                body: Some(ResponseBody::Failure(FailureReason {
-                    retdata_start,
-                    retdata_end,
-                })),
+                body: Some(response),
+            });
+        }
+        if request.key >= *STORAGE_KEY_UPPER_BOUND {
+            let response = self.failure_from_error_msg(vm, b"Invalid storage key")?;
+
+            return Ok(SyscallResponse {
+                gas: remaining_gas,
+                body: Some(response),
             });
         }
 
@@ -793,6 +1085,7 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             class_hash_bytes,
             constructor_calldata,
             remaining_gas,
+            request.deploy_from_zero.is_one(),
         )?;
 
         Ok((contract_address, result))
@@ -808,26 +1101,13 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
 
         remaining_gas -= result.gas_consumed;
 
-        let retdata_len = result.retdata.len();
-
-        let retdata_start = self.allocate_segment(vm, result.retdata)?;
-        let retdata_end = retdata_start.add(retdata_len)?;
-
-        let ok = result.is_success;
-
-        let body: ResponseBody = if ok {
-            let contract_address = contract_address.0;
+        let body = result.into_response_body(self, vm, |retdata_start, retdata_end| {
             ResponseBody::Deploy(DeployResponse {
-                contract_address,
+                contract_address: contract_address.0,
                 retdata_start,
                 retdata_end,
             })
-        } else {
-            ResponseBody::Failure(FailureReason {
-                retdata_start,
-                retdata_end,
-            })
-        };
+        })?;
         let response = SyscallResponse {
             gas: remaining_gas,
             body: Some(body),
@@ -905,7 +1185,7 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         self.increment_syscall_count(syscall_name);
         let syscall_request = self.read_syscall_request(vm, *syscall_ptr, syscall_name)?;
 
-        *syscall_ptr += get_syscall_size_from_name(syscall_name);
+        *syscall_ptr = advance_syscall_ptr(*syscall_ptr, get_syscall_size_from_name(syscall_name))?;
         Ok(syscall_request)
     }
 
@@ -915,7 +1195,23 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         request: LibraryCallRequest,
         remaining_gas: u128,
     ) -> Result<SyscallResponse, SyscallHandlerError> {
+        if let Some(response) = self.check_calldata_length(
+            vm,
+            request.calldata_start,
+            request.calldata_end,
+            remaining_gas,
+        )? {
+            return Ok(response);
+        }
         let calldata = get_felt_range(vm, request.calldata_start, request.calldata_end)?;
+
+        let class_hash = request.class_hash.to_be_bytes();
+        let remaining_gas =
+            match self.charge_class_load(vm, "library_call", &class_hash, remaining_gas)? {
+                Ok(remaining_gas) => remaining_gas,
+                Err(response) => return Ok(response),
+            };
+
         let execution_entry_point = ExecutionEntryPoint::new(
             self.contract_address.clone(),
             calldata,
@@ -923,7 +1219,7 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
             self.caller_address.clone(),
             EntryPointType::External,
             Some(CallType::Delegate),
-            Some(request.class_hash.to_be_bytes()),
+            Some(class_hash),
             remaining_gas,
         );
 
@@ -968,28 +1264,50 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
     ) -> Result<SyscallResponse, SyscallHandlerError> {
         let length = (request.input_end - request.input_start)?;
         let mut gas = remaining_gas;
+        let chunk_size = self.block_context.keccak_chunk_size;
+        let round_cost = self.block_context.keccak_round_cost;
+        if chunk_size == 0 || chunk_size > 25 {
+            return Err(SyscallHandlerError::InvalidKeccakChunkSize(chunk_size));
+        }
 
-        if length % 17 != 0 {
-            let response = self.failure_from_error_msg(vm, b"Invalid keccak input size")?;
+        if length % chunk_size != 0 {
+            // Include the received length and the expected chunk size so the failure can be
+            // told apart from a bad pointer.
+            let response = self.failure_from_retdata(
+                vm,
+                &[
+                    Felt252::from_bytes_be(b"Invalid keccak input size"),
+                    length.into(),
+                    chunk_size.into(),
+                ],
+            )?;
+            return Ok(SyscallResponse {
+                gas,
+                body: Some(response),
+            });
+        }
+        let n_chunks = length / chunk_size;
+        if self.block_context.keccak_gas_check == KeccakGasCheck::Upfront
+            && gas < round_cost.saturating_mul(n_chunks as u128)
+        {
+            let response = self.out_of_gas_failure(vm, "keccak", b"Syscall out of gas")?;
             return Ok(SyscallResponse {
                 gas,
                 body: Some(response),
             });
         }
-        let n_chunks = length / 17;
         let mut state = [0u64; 25];
         for i in 0..n_chunks {
-            // TODO: check this before the loop, taking care to preserve functionality.
-            if gas < KECCAK_ROUND_COST {
-                let response = self.failure_from_error_msg(vm, b"Syscall out of gas")?;
+            if gas < round_cost {
+                let response = self.out_of_gas_failure(vm, "keccak", b"Syscall out of gas")?;
                 return Ok(SyscallResponse {
                     gas,
                     body: Some(response),
                 });
             }
-            gas -= KECCAK_ROUND_COST;
-            let chunk_start = (request.input_start + i * 17)?;
-            let chunk = get_felt_range(vm, chunk_start, (chunk_start + 17)?)?;
+            gas -= round_cost;
+            let chunk_start = (request.input_start + i * chunk_size)?;
+            let chunk = get_felt_range(vm, chunk_start, (chunk_start + chunk_size)?)?;
             for (i, val) in chunk.iter().enumerate() {
                 state[i] ^= val.to_u64().ok_or_else(|| {
                     SyscallHandlerError::Conversion("Felt252".to_string(), "u64".to_string())
@@ -1008,18 +1326,1383 @@ impl<'a, S: StateReader> BusinessLogicSyscallHandler<'a, S> {
         })
     }
 
-    // TODO: refactor code to use this function
+    /// Same as [`Self::failure_from_error_msg`], recording `syscall_name` as a syscall that ran
+    /// out of gas.
+    fn out_of_gas_failure(
+        &mut self,
+        vm: &mut VirtualMachine,
+        syscall_name: &str,
+        error_msg: &[u8],
+    ) -> Result<ResponseBody, SyscallHandlerError> {
+        self.out_of_gas_syscall
+            .get_or_insert_with(|| syscall_name.to_string());
+        self.failure_from_error_msg(vm, error_msg)
+    }
+
+    /// Allocates a read-only segment holding `error_msg` encoded as a single felt and returns
+    /// a `Failure` response body pointing to it.
     fn failure_from_error_msg(
         &mut self,
         vm: &mut VirtualMachine,
         error_msg: &[u8],
     ) -> Result<ResponseBody, SyscallHandlerError> {
-        let felt_encoded_msg = Felt252::from_bytes_be(error_msg);
+        self.failure_from_retdata(vm, &[Felt252::from_bytes_be(error_msg)])
+    }
+
+    /// Allocates a read-only segment holding every felt in `retdata` and returns a `Failure`
+    /// response body pointing to it.
+    fn failure_from_retdata(
+        &mut self,
+        vm: &mut VirtualMachine,
+        retdata: &[Felt252],
+    ) -> Result<ResponseBody, SyscallHandlerError> {
         let retdata_start =
-            self.allocate_segment(vm, vec![MaybeRelocatable::from(felt_encoded_msg)])?;
+            self.allocate_segment(vm, retdata.iter().map(MaybeRelocatable::from).collect())?;
         Ok(ResponseBody::Failure(FailureReason {
             retdata_start,
-            retdata_end: (retdata_start + 1)?,
+            retdata_end: (retdata_start + retdata.len())?,
         }))
     }
 }
+
+/// Moves `syscall_ptr` forward by `size` cells, failing instead of overflowing its offset.
+fn advance_syscall_ptr(
+    syscall_ptr: Relocatable,
+    size: usize,
+) -> Result<Relocatable, SyscallHandlerError> {
+    (syscall_ptr + size).map_err(SyscallHandlerError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::errors::state_errors::StateError,
+        definitions::{
+            block_context::{BlockContext, KeccakGasCheck},
+            constants::{DEFAULT_KECCAK_ROUND_COST, TRANSACTION_VERSION},
+        },
+        execution::{
+            execution_entry_point::{ExecutionEntryPoint, ExecutionResult},
+            CallInfo, CallResult, CallType, OrderedEvent, TransactionExecutionContext,
+            TransactionExecutionInfo,
+        },
+        hash_utils::calculate_contract_address,
+        services::api::contract_classes::{
+            compiled_class::CompiledClass, deprecated_contract_class::ContractClass,
+        },
+        state::{
+            cached_state::CachedState,
+            in_memory_state_reader::InMemoryStateReader,
+            state_api::{State, StateReader},
+            state_cache::StorageEntry,
+            ExecutionResourcesManager,
+        },
+        syscalls::{
+            syscall_handler_errors::SyscallHandlerError,
+            syscall_info::get_syscall_size_from_name,
+            syscall_request::{
+                CallContractRequest, DeployRequest, EmitEventRequest, FromPtr, GetBlockHashRequest,
+                KeccakRequest, LibraryCallRequest, StorageReadRequest, StorageWriteRequest,
+            },
+            syscall_response::{CallContractResponse, ResponseBody, SyscallResponse},
+            syscall_trace::SyscallTraceEvent,
+        },
+        transaction::error::TransactionError,
+        utils::{
+            calculate_sn_keccak, get_felt_range, test_utils::*, Address, ClassHash,
+            CompiledClassHash,
+        },
+        EntryPointType,
+    };
+    use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+    use cairo_vm::{
+        felt::{felt_str, Felt252},
+        types::relocatable::{MaybeRelocatable, Relocatable},
+        vm::vm_core::VirtualMachine,
+    };
+    use num_traits::{One, Zero};
+    use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+    type BusinessLogicSyscallHandler<'a> =
+        super::BusinessLogicSyscallHandler<'a, InMemoryStateReader>;
+
+    #[test]
+    fn get_execution_info_zeroes_v2_tx_info_tail_for_v1_transactions() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.tx_execution_context.version = 1.into();
+        syscall_handler.tx_execution_context.signature = vec![10.into(), 11.into()];
+        syscall_handler.tx_execution_context.nonce = 7.into();
+        let mut vm = vm!();
+
+        let response = syscall_handler.get_execution_info(&mut vm, 1000).unwrap();
+        let Some(ResponseBody::GetExecutionInfo { exec_info_ptr }) = response.body else {
+            panic!("expected a get_execution_info response")
+        };
+        let tx_info_ptr = vm.get_relocatable((exec_info_ptr + 1).unwrap()).unwrap();
+        let tx_info_field = |offset: usize| vm.get_maybe(&(tx_info_ptr + offset).unwrap()).unwrap();
+
+        // v1 fields keep their offsets
+        assert_eq!(tx_info_field(0), MaybeRelocatable::from(Felt252::one()));
+        assert_eq!(tx_info_field(7), MaybeRelocatable::from(Felt252::new(7)));
+
+        // resource_bounds, paymaster_data and account_deployment_data are empty spans
+        for (start, end) in [(8, 9), (11, 12), (15, 16)] {
+            assert_eq!(tx_info_field(start), tx_info_field(end));
+            assert!(tx_info_field(start).get_relocatable().is_some());
+        }
+        // tip and data availability modes are zero
+        for offset in [10, 13, 14] {
+            assert_eq!(
+                tx_info_field(offset),
+                MaybeRelocatable::from(Felt252::zero())
+            );
+        }
+
+        let block_info_ptr = vm.get_relocatable(exec_info_ptr).unwrap();
+        assert_eq!(block_info_ptr, (tx_info_ptr + 17).unwrap());
+    }
+
+    #[test]
+    fn get_execution_info_gas_does_not_depend_on_the_signature_length() {
+        for signature_len in [0, 1, 50] {
+            let mut state = CachedState::<InMemoryStateReader>::default();
+            let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+            syscall_handler.tx_execution_context.signature = vec![1.into(); signature_len];
+            let mut vm = vm!();
+
+            let syscall = vec![
+                Felt252::from_bytes_be(b"GetExecutionInfo").into(),
+                Felt252::from(1_000_000).into(),
+            ];
+            let syscall_ptr = syscall_handler.allocate_segment(&mut vm, syscall).unwrap();
+            syscall_handler.syscall(&mut vm, syscall_ptr).unwrap();
+
+            assert_eq!(
+                vm.get_integer((syscall_ptr + 2).unwrap())
+                    .unwrap()
+                    .into_owned(),
+                Felt252::from(
+                    1_000_000 - (super::GET_EXECUTION_INFO_GAS_COST - super::SYSCALL_BASE)
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn storage_read_unsupported_address_domain_failure() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let request = StorageReadRequest {
+            key: [0; 32],
+            reserved: 1.into(),
+        };
+        let response = syscall_handler
+            .storage_read(&mut vm, request, 1000)
+            .unwrap();
+
+        assert_eq!(response.gas, 1000);
+        let Some(ResponseBody::Failure(failure)) = response.body else {
+            panic!("expected a failure response")
+        };
+        assert_eq!(failure.retdata_end, (failure.retdata_start + 1).unwrap());
+        assert_eq!(
+            vm.get_integer(failure.retdata_start).unwrap().into_owned(),
+            Felt252::from_bytes_be(b"Unsupported address domain")
+        );
+    }
+
+    #[test]
+    fn storage_domains_do_not_alias() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let key = Felt252::from(7);
+        let write = |reserved: Felt252| StorageWriteRequest {
+            reserved,
+            key: key.clone(),
+            value: 5.into(),
+        };
+        let read = |reserved: Felt252| StorageReadRequest {
+            key: key.to_be_bytes(),
+            reserved,
+        };
+        let expect_domain_failure = |vm: &VirtualMachine, response: SyscallResponse| {
+            let Some(ResponseBody::Failure(failure)) = response.body else {
+                panic!("expected a failure response")
+            };
+            assert_eq!(
+                vm.get_integer(failure.retdata_start).unwrap().into_owned(),
+                Felt252::from_bytes_be(b"Unsupported address domain")
+            );
+        };
+
+        syscall_handler
+            .storage_write(&mut vm, write(Felt252::zero()), 1_000_000)
+            .unwrap();
+
+        // Only domain 0 is supported: reading the same key in domain 1 fails instead of
+        // returning the domain 0 value, and writing it leaves the domain 0 value untouched.
+        let response = syscall_handler
+            .storage_read(&mut vm, read(Felt252::one()), 1_000_000)
+            .unwrap();
+        expect_domain_failure(&vm, response);
+        let response = syscall_handler
+            .storage_write(
+                &mut vm,
+                StorageWriteRequest {
+                    value: 6.into(),
+                    ..write(Felt252::one())
+                },
+                1_000_000,
+            )
+            .unwrap();
+        expect_domain_failure(&vm, response);
+
+        let response = syscall_handler
+            .storage_read(&mut vm, read(Felt252::zero()), 1_000_000)
+            .unwrap();
+        let Some(ResponseBody::StorageReadResponse { value: Some(value) }) = response.body else {
+            panic!("expected a storage read response")
+        };
+        assert_eq!(value, 5.into());
+    }
+
+    fn run_keccak(gas_check: KeccakGasCheck, n_chunks: usize, gas: u128) -> SyscallResponse {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.block_context.keccak_gas_check = gas_check;
+        let mut vm = vm!();
+
+        let input = vec![MaybeRelocatable::from(Felt252::from(1)); 17 * n_chunks];
+        let input_start = syscall_handler.allocate_segment(&mut vm, input).unwrap();
+        let request = KeccakRequest {
+            input_start,
+            input_end: (input_start + 17 * n_chunks).unwrap(),
+        };
+
+        syscall_handler.keccak(&mut vm, request, gas).unwrap()
+    }
+
+    #[test]
+    fn keccak_gas_check_modes_consume_the_same_gas_on_success() {
+        let gas = 3 * DEFAULT_KECCAK_ROUND_COST;
+
+        let per_round = run_keccak(KeccakGasCheck::PerRound, 2, gas);
+        let upfront = run_keccak(KeccakGasCheck::Upfront, 2, gas);
+
+        assert!(matches!(per_round.body, Some(ResponseBody::Keccak(_))));
+        assert!(matches!(upfront.body, Some(ResponseBody::Keccak(_))));
+        assert_eq!(per_round.gas, DEFAULT_KECCAK_ROUND_COST);
+        assert_eq!(upfront.gas, per_round.gas);
+    }
+
+    #[test]
+    fn keccak_gas_check_modes_fail_at_different_points() {
+        let gas = DEFAULT_KECCAK_ROUND_COST + DEFAULT_KECCAK_ROUND_COST / 2;
+
+        // The per-round check hashes the first chunk before running out of gas.
+        let per_round = run_keccak(KeccakGasCheck::PerRound, 2, gas);
+        assert!(matches!(per_round.body, Some(ResponseBody::Failure(_))));
+        assert_eq!(per_round.gas, DEFAULT_KECCAK_ROUND_COST / 2);
+
+        // The upfront check fails before hashing anything.
+        let upfront = run_keccak(KeccakGasCheck::Upfront, 2, gas);
+        assert!(matches!(upfront.body, Some(ResponseBody::Failure(_))));
+        assert_eq!(upfront.gas, gas);
+    }
+
+    #[test]
+    fn keccak_with_lower_round_cost_succeeds_with_less_gas() {
+        let gas = DEFAULT_KECCAK_ROUND_COST;
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let input = vec![MaybeRelocatable::from(Felt252::from(1)); 17 * 2];
+        let input_start = syscall_handler.allocate_segment(&mut vm, input).unwrap();
+        let request = KeccakRequest {
+            input_start,
+            input_end: (input_start + 17 * 2).unwrap(),
+        };
+
+        // Two rounds don't fit in the gas at the default cost
+        let response = syscall_handler
+            .keccak(&mut vm, request.clone(), gas)
+            .unwrap();
+        assert!(matches!(response.body, Some(ResponseBody::Failure(_))));
+
+        syscall_handler.block_context.keccak_round_cost = gas / 4;
+        let response = syscall_handler.keccak(&mut vm, request, gas).unwrap();
+        assert!(matches!(response.body, Some(ResponseBody::Keccak(_))));
+        assert_eq!(response.gas, gas / 2);
+    }
+
+    /// Hashes `input_len` words with the given chunk size and returns the failure retdata, if any.
+    fn keccak_failure_retdata(chunk_size: usize, input_len: usize) -> Option<Vec<Felt252>> {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.block_context.keccak_chunk_size = chunk_size;
+        let mut vm = vm!();
+
+        let input = vec![MaybeRelocatable::from(Felt252::from(1)); input_len];
+        let input_start = syscall_handler.allocate_segment(&mut vm, input).unwrap();
+        let request = KeccakRequest {
+            input_start,
+            input_end: (input_start + input_len).unwrap(),
+        };
+
+        let response = syscall_handler
+            .keccak(&mut vm, request, 10 * DEFAULT_KECCAK_ROUND_COST)
+            .unwrap();
+        match response.body {
+            Some(ResponseBody::Failure(failure)) => {
+                Some(get_felt_range(&vm, failure.retdata_start, failure.retdata_end).unwrap())
+            }
+            Some(ResponseBody::Keccak(_)) => None,
+            _ => panic!("unexpected keccak response"),
+        }
+    }
+
+    #[test]
+    fn keccak_input_size_must_be_a_multiple_of_the_chunk_size() {
+        let invalid_size = |length: usize, chunk_size: usize| {
+            Some(vec![
+                Felt252::from_bytes_be(b"Invalid keccak input size"),
+                Felt252::from(length),
+                Felt252::from(chunk_size),
+            ])
+        };
+
+        // 34 words are two default-sized chunks but not a multiple of 8.
+        assert_eq!(keccak_failure_retdata(17, 34), None);
+        assert_eq!(keccak_failure_retdata(8, 34), invalid_size(34, 8));
+
+        // 24 words are three 8-word chunks but not a multiple of 17.
+        assert_eq!(keccak_failure_retdata(17, 24), invalid_size(24, 17));
+        assert_eq!(keccak_failure_retdata(8, 24), None);
+    }
+
+    #[test]
+    fn keccak_chunk_size_out_of_range_should_fail() {
+        for chunk_size in [0, 26] {
+            let mut state = CachedState::<InMemoryStateReader>::default();
+            let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+            syscall_handler.block_context.keccak_chunk_size = chunk_size;
+            let mut vm = vm!();
+
+            let input_start = syscall_handler.allocate_segment(&mut vm, vec![]).unwrap();
+            let request = KeccakRequest {
+                input_start,
+                input_end: input_start,
+            };
+
+            assert_matches!(
+                syscall_handler.keccak(&mut vm, request, DEFAULT_KECCAK_ROUND_COST),
+                Err(SyscallHandlerError::InvalidKeccakChunkSize(size)) if size == chunk_size
+            );
+        }
+    }
+
+    #[test]
+    fn failure_from_retdata_with_multiple_felts() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let retdata = [
+            Felt252::from_bytes_be(b"ENTRYPOINT_FAILED"),
+            Felt252::from(2),
+            Felt252::from(3),
+        ];
+        let response = syscall_handler
+            .failure_from_retdata(&mut vm, &retdata)
+            .unwrap();
+
+        let ResponseBody::Failure(failure) = response else {
+            panic!("expected a failure response")
+        };
+        assert_eq!(failure.retdata_end, (failure.retdata_start + 3).unwrap());
+        assert_eq!(
+            get_felt_range(&vm, failure.retdata_start, failure.retdata_end).unwrap(),
+            retdata.to_vec()
+        );
+    }
+
+    #[test]
+    fn call_result_into_response_body() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let call_result = |is_success| CallResult {
+            gas_consumed: 0,
+            is_success,
+            retdata: vec![Felt252::from(1).into(), Felt252::from(2).into()],
+        };
+        let call_contract_body = |retdata_start, retdata_end| {
+            ResponseBody::CallContract(CallContractResponse {
+                retdata_start,
+                retdata_end,
+            })
+        };
+
+        let success = call_result(true)
+            .into_response_body(&mut syscall_handler, &mut vm, call_contract_body)
+            .unwrap();
+        let ResponseBody::CallContract(response) = success else {
+            panic!("expected a call contract response")
+        };
+        assert_eq!(
+            get_felt_range(&vm, response.retdata_start, response.retdata_end).unwrap(),
+            vec![1.into(), 2.into()]
+        );
+
+        let failure = call_result(false)
+            .into_response_body(&mut syscall_handler, &mut vm, call_contract_body)
+            .unwrap();
+        let ResponseBody::Failure(failure) = failure else {
+            panic!("expected a failure response")
+        };
+        assert_eq!(
+            get_felt_range(&vm, failure.retdata_start, failure.retdata_end).unwrap(),
+            vec![1.into(), 2.into()]
+        );
+
+        // Both retdata segments are read-only
+        assert_eq!(syscall_handler.read_only_segments.len(), 2);
+    }
+
+    fn run_emit_event(n_keys: usize, n_data: usize, gas: u128) -> (SyscallResponse, usize, bool) {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.block_context.emit_event_key_gas_cost = 100;
+        syscall_handler.block_context.emit_event_data_gas_cost = 10;
+        let mut vm = vm!();
+
+        let keys = vec![MaybeRelocatable::from(Felt252::from(1)); n_keys];
+        let data = vec![MaybeRelocatable::from(Felt252::from(2)); n_data];
+        let keys_start = syscall_handler.allocate_segment(&mut vm, keys).unwrap();
+        let data_start = syscall_handler.allocate_segment(&mut vm, data).unwrap();
+        let request = EmitEventRequest {
+            keys_start,
+            keys_end: (keys_start + n_keys).unwrap(),
+            data_start,
+            data_end: (data_start + n_data).unwrap(),
+        };
+
+        let response = syscall_handler.emit_event(&mut vm, request, gas).unwrap();
+        (
+            response,
+            syscall_handler.events.len(),
+            syscall_handler.out_of_gas_syscall.is_some(),
+        )
+    }
+
+    #[test]
+    fn emit_event_charges_per_payload_felt() {
+        let (response, n_events, out_of_gas) = run_emit_event(2, 3, 1000);
+
+        assert!(response.body.is_none());
+        assert_eq!(response.gas, 1000 - 2 * 100 - 3 * 10);
+        assert_eq!(n_events, 1);
+        assert!(!out_of_gas);
+    }
+
+    #[test]
+    fn emit_event_large_payload_out_of_gas() {
+        let (response, n_events, out_of_gas) = run_emit_event(5, 100, 1000);
+
+        assert!(matches!(response.body, Some(ResponseBody::Failure(_))));
+        assert_eq!(response.gas, 1000);
+        assert_eq!(n_events, 0);
+        assert!(out_of_gas);
+    }
+
+    #[test]
+    fn get_block_hash_from_recent_block_hashes() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.block_context.block_info.block_number = 20;
+        syscall_handler
+            .block_context
+            .block_info
+            .recent_block_hashes
+            .insert(5, 1234.into());
+        let mut vm = vm!();
+
+        let response = syscall_handler
+            .get_block_hash(&mut vm, GetBlockHashRequest { block_number: 5 }, 1000)
+            .unwrap();
+
+        assert_eq!(response.gas, 1000);
+        let Some(ResponseBody::GetBlockHash(response)) = response.body else {
+            panic!("expected a get_block_hash response")
+        };
+        assert_eq!(response.block_hash, Felt252::from(1234));
+    }
+
+    #[test]
+    fn emitted_event_is_exposed_by_the_handler() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let keys_start = syscall_handler
+            .allocate_segment(&mut vm, vec![Felt252::from(1).into()])
+            .unwrap();
+        let data_start = syscall_handler
+            .allocate_segment(
+                &mut vm,
+                vec![Felt252::from(2).into(), Felt252::from(3).into()],
+            )
+            .unwrap();
+        let syscall_ptr = syscall_handler
+            .allocate_segment(
+                &mut vm,
+                vec![
+                    Felt252::from_bytes_be(b"EmitEvent").into(),
+                    Felt252::from(100_000).into(),
+                    keys_start.into(),
+                    (keys_start + 1).unwrap().into(),
+                    data_start.into(),
+                    (data_start + 2).unwrap().into(),
+                ],
+            )
+            .unwrap();
+
+        syscall_handler.syscall(&mut vm, syscall_ptr).unwrap();
+
+        assert_eq!(
+            syscall_handler.events(),
+            &[OrderedEvent::new(
+                0,
+                vec![1.into()],
+                vec![2.into(), 3.into()]
+            )]
+        );
+        assert!(syscall_handler.internal_calls().is_empty());
+        assert!(syscall_handler.l2_to_l1_messages().is_empty());
+    }
+
+    #[test]
+    fn syscall_tracer_records_every_syscall() {
+        let mut trace = Vec::new();
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state)
+            .with_syscall_tracer(|event| {
+                trace.push(match event {
+                    SyscallTraceEvent::Before { name, .. } => format!("before {name}"),
+                    SyscallTraceEvent::After { name, failed, .. } => {
+                        format!("after {name} failed={failed}")
+                    }
+                })
+            });
+        let mut vm = vm!();
+
+        let storage_write_ptr = syscall_handler
+            .allocate_segment(
+                &mut vm,
+                vec![
+                    Felt252::from_bytes_be(b"StorageWrite").into(),
+                    Felt252::from(100_000).into(),
+                    Felt252::zero().into(),
+                    Felt252::from(1).into(),
+                    Felt252::from(2).into(),
+                ],
+            )
+            .unwrap();
+        syscall_handler.syscall(&mut vm, storage_write_ptr).unwrap();
+
+        let keys_start = syscall_handler
+            .allocate_segment(&mut vm, vec![Felt252::from(1).into()])
+            .unwrap();
+        let emit_event_ptr = syscall_handler
+            .allocate_segment(
+                &mut vm,
+                vec![
+                    Felt252::from_bytes_be(b"EmitEvent").into(),
+                    Felt252::from(100_000).into(),
+                    keys_start.into(),
+                    (keys_start + 1).unwrap().into(),
+                    keys_start.into(),
+                    keys_start.into(),
+                ],
+            )
+            .unwrap();
+        syscall_handler.syscall(&mut vm, emit_event_ptr).unwrap();
+        drop(syscall_handler);
+
+        assert_eq!(
+            trace,
+            vec![
+                "before storage_write",
+                "after storage_write failed=false",
+                "before emit_event",
+                "after emit_event failed=false",
+            ]
+        );
+    }
+
+    #[test]
+    fn advancing_syscall_ptr_past_max_offset_fails() {
+        let syscall_ptr = Relocatable::from((1, usize::MAX - 2));
+
+        assert_eq!(
+            super::advance_syscall_ptr(syscall_ptr, 2).unwrap(),
+            Relocatable::from((1, usize::MAX))
+        );
+        assert_matches!(
+            super::advance_syscall_ptr(syscall_ptr, get_syscall_size_from_name("call_contract")),
+            Err(SyscallHandlerError::MathError(_))
+        );
+
+        // Reading a request at the end of the address space errors instead of panicking
+        let vm = vm!();
+        assert!(EmitEventRequest::from_ptr(&vm, Relocatable::from((1, usize::MAX))).is_err());
+    }
+
+    #[test]
+    fn syscall_gas_is_accumulated_per_syscall() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+        let gas = 10 * DEFAULT_KECCAK_ROUND_COST;
+
+        let run_syscall = |syscall_handler: &mut BusinessLogicSyscallHandler,
+                           vm: &mut VirtualMachine,
+                           selector: &[u8],
+                           args: Vec<MaybeRelocatable>| {
+            let mut syscall = vec![
+                Felt252::from_bytes_be(selector).into(),
+                Felt252::from(gas).into(),
+            ];
+            syscall.extend(args);
+            let syscall_ptr = syscall_handler.allocate_segment(vm, syscall).unwrap();
+            syscall_handler.syscall(vm, syscall_ptr).unwrap();
+        };
+
+        for key in 0..3 {
+            run_syscall(
+                &mut syscall_handler,
+                &mut vm,
+                b"StorageRead",
+                vec![Felt252::zero().into(), Felt252::from(key).into()],
+            );
+        }
+        let input = vec![MaybeRelocatable::from(Felt252::from(1)); 17];
+        let input_start = syscall_handler.allocate_segment(&mut vm, input).unwrap();
+        run_syscall(
+            &mut syscall_handler,
+            &mut vm,
+            b"Keccak",
+            vec![input_start.into(), (input_start + 17).unwrap().into()],
+        );
+
+        let storage_read_gas = super::SYSCALL_GAS_COST["storage_read"] - super::SYSCALL_BASE;
+        assert_eq!(
+            syscall_handler.resources_manager.gas_by_syscall(),
+            HashMap::from([
+                ("storage_read".to_string(), 3 * storage_read_gas),
+                ("keccak".to_string(), DEFAULT_KECCAK_ROUND_COST),
+            ])
+        );
+    }
+
+    #[test]
+    fn storage_read_out_of_gas_records_the_syscall() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let syscall = vec![
+            Felt252::from_bytes_be(b"StorageRead").into(),
+            Felt252::from(10).into(),
+            Felt252::zero().into(),
+            Felt252::from(1).into(),
+        ];
+        let syscall_ptr = syscall_handler.allocate_segment(&mut vm, syscall).unwrap();
+        syscall_handler.syscall(&mut vm, syscall_ptr).unwrap();
+
+        // The response follows the selector, gas and the two request arguments
+        let response_ptr = (syscall_ptr + 4).unwrap();
+        assert_eq!(
+            vm.get_integer(response_ptr).unwrap().into_owned(),
+            Felt252::from(10)
+        );
+        assert_eq!(
+            vm.get_integer((response_ptr + 1).unwrap())
+                .unwrap()
+                .into_owned(),
+            Felt252::one()
+        );
+        let retdata_start = vm.get_relocatable((response_ptr + 2).unwrap()).unwrap();
+        assert_eq!(
+            vm.get_integer(retdata_start).unwrap().into_owned(),
+            Felt252::from_bytes_be(b"Out of gas")
+        );
+        assert_eq!(
+            syscall_handler.out_of_gas_syscall.as_deref(),
+            Some("storage_read")
+        );
+    }
+
+    #[test]
+    fn storage_write_to_out_of_range_key_fails() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let request = StorageWriteRequest {
+            reserved: Felt252::zero(),
+            key: Felt252::one() << 251u32,
+            value: 1.into(),
+        };
+        let response = syscall_handler
+            .storage_write(&mut vm, request, 1000)
+            .unwrap();
+
+        assert_eq!(response.gas, 1000);
+        let Some(ResponseBody::Failure(failure)) = response.body else {
+            panic!("expected a failure response")
+        };
+        assert_eq!(
+            vm.get_integer(failure.retdata_start).unwrap().into_owned(),
+            Felt252::from_bytes_be(b"Invalid storage key")
+        );
+        assert!(syscall_handler
+            .starknet_storage_state
+            .written_values
+            .is_empty());
+    }
+
+    #[test]
+    fn syscalls_share_the_gas_budget() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let storage_read_gas = super::SYSCALL_GAS_COST["storage_read"] - super::SYSCALL_BASE;
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state)
+            .with_gas_budget(storage_read_gas * 3 / 2);
+        let mut vm = vm!();
+
+        // Each syscall has enough gas on its own, but not both within the budget
+        let mut read_failed = || {
+            let syscall = vec![
+                Felt252::from_bytes_be(b"StorageRead").into(),
+                Felt252::from(1_000_000).into(),
+                Felt252::zero().into(),
+                Felt252::from(1).into(),
+            ];
+            let syscall_ptr = syscall_handler.allocate_segment(&mut vm, syscall).unwrap();
+            syscall_handler.syscall(&mut vm, syscall_ptr).unwrap();
+            vm.get_integer((syscall_ptr + 5).unwrap())
+                .unwrap()
+                .into_owned()
+        };
+        assert_eq!(read_failed(), Felt252::zero());
+        assert_eq!(read_failed(), Felt252::one());
+
+        assert_eq!(syscall_handler.gas_budget(), Some(storage_read_gas / 2));
+        assert_eq!(
+            syscall_handler.out_of_gas_syscall.as_deref(),
+            Some("storage_read")
+        );
+    }
+
+    #[test]
+    fn syscall_missing_from_custom_selector_map_fails() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let keccak_selector = Felt252::from_bytes_be(b"Keccak");
+        let mut selector_to_syscall = super::SELECTOR_TO_SYSCALL.clone();
+        selector_to_syscall.remove(&keccak_selector);
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state)
+            .with_selector_to_syscall(Cow::Owned(selector_to_syscall));
+        let mut vm = vm!();
+
+        let syscall_ptr = syscall_handler
+            .allocate_segment(
+                &mut vm,
+                vec![keccak_selector.clone().into(), Felt252::from(1000).into()],
+            )
+            .unwrap();
+
+        assert_matches!(
+            syscall_handler.syscall(&mut vm, syscall_ptr),
+            Err(SyscallHandlerError::SelectorNotInHandlerMap(selector))
+                if selector == keccak_selector.to_string()
+        );
+    }
+
+    #[test]
+    fn deploy_records_deploy_from_zero() {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/fibonacci.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+        let class_hash = [1; 32];
+        let mut state = CachedState::new(
+            Arc::new(InMemoryStateReader::default()),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler.allocate_segment(&mut vm, vec![]).unwrap();
+        for deploy_from_zero in [0, 1] {
+            let request = DeployRequest {
+                class_hash: Felt252::from_bytes_be(&class_hash),
+                salt: deploy_from_zero.into(),
+                calldata_start,
+                calldata_end: calldata_start,
+                deploy_from_zero,
+            };
+            let (_, result) = syscall_handler
+                .syscall_deploy(&vm, request, 100000)
+                .unwrap();
+            assert!(result.is_success);
+        }
+
+        let recorded: Vec<_> = syscall_handler
+            .internal_calls
+            .iter()
+            .map(|call_info| call_info.deploy_from_zero)
+            .collect();
+        assert_eq!(recorded, vec![Some(false), Some(true)]);
+    }
+
+    #[test]
+    fn write_past_read_only_segment_fails() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let segment = syscall_handler
+            .allocate_segment(&mut vm, vec![1.into(), 2.into()])
+            .unwrap();
+        vm.insert_value((segment + 2).unwrap(), Felt252::from(3))
+            .unwrap();
+        vm.segments.compute_effective_sizes();
+
+        assert_matches!(
+            syscall_handler.validate_read_only_segments(&mut vm),
+            Err(TransactionError::OutOfBound(index, expected, 3))
+                if index == segment.segment_index && expected == Felt252::from(2)
+        );
+    }
+
+    #[test]
+    fn post_run_with_skipped_segment_validation() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        // get_execution_info allocates read-only segments
+        let syscall_ptr = vm.add_memory_segment();
+        vm.write_arg(
+            syscall_ptr,
+            &vec![
+                MaybeRelocatable::from(Felt252::from_bytes_be(b"GetExecutionInfo")),
+                MaybeRelocatable::from(Felt252::from(100_000)),
+            ],
+        )
+        .unwrap();
+        syscall_handler.syscall(&mut vm, syscall_ptr).unwrap();
+        let syscall_stop_ptr = syscall_handler.expected_syscall_ptr;
+
+        // The run didn't finish, so the segment sizes weren't computed yet
+        assert_matches!(
+            syscall_handler.post_run(&mut vm, syscall_stop_ptr),
+            Err(TransactionError::InvalidSegmentSize)
+        );
+
+        let syscall_handler = syscall_handler.with_skip_segment_validation(true);
+        assert_matches!(syscall_handler.post_run(&mut vm, syscall_stop_ptr), Ok(()));
+    }
+
+    #[test]
+    fn deploy_deducts_the_constructor_gas() {
+        // The constructor of the events contract writes its counter to storage
+        let program_data = include_bytes!("../../starknet_programs/cairo2/events.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+        let class_hash = [1; 32];
+        let mut state = CachedState::new(
+            Arc::new(InMemoryStateReader::default()),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler.allocate_segment(&mut vm, vec![]).unwrap();
+        let request = DeployRequest {
+            class_hash: Felt252::from_bytes_be(&class_hash),
+            salt: 1.into(),
+            calldata_start,
+            calldata_end: calldata_start,
+            deploy_from_zero: 0,
+        };
+        let response = syscall_handler.deploy(&mut vm, request, 1_000_000).unwrap();
+        assert!(matches!(response.body, Some(ResponseBody::Deploy(_))));
+
+        let [constructor_call] = syscall_handler.internal_calls() else {
+            panic!("expected a single constructor call")
+        };
+        assert_eq!(constructor_call.storage_writes.len(), 1);
+        let storage_write_gas = super::SYSCALL_GAS_COST["storage_write"] - super::SYSCALL_BASE;
+        assert!(constructor_call.gas_consumed >= storage_write_gas);
+        assert_eq!(response.gas, 1_000_000 - constructor_call.gas_consumed);
+    }
+
+    #[test]
+    fn constructor_out_of_gas_surfaces_in_the_execution_info() {
+        // The constructor of the events contract writes its counter to storage
+        let program_data = include_bytes!("../../starknet_programs/cairo2/events.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+        let class_hash = [1; 32];
+        let mut state = CachedState::new(
+            Arc::new(InMemoryStateReader::default()),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler.allocate_segment(&mut vm, vec![]).unwrap();
+        let mut deploy =
+            |syscall_handler: &mut BusinessLogicSyscallHandler, salt: u64, gas: u128| {
+                let request = DeployRequest {
+                    class_hash: Felt252::from_bytes_be(&class_hash),
+                    salt: Felt252::from(salt),
+                    calldata_start,
+                    calldata_end: calldata_start,
+                    deploy_from_zero: 0,
+                };
+                syscall_handler.deploy(&mut vm, request, gas).unwrap();
+            };
+
+        deploy(&mut syscall_handler, 1, 1_000_000);
+        let gas_consumed = syscall_handler.internal_calls()[0].gas_consumed;
+        assert!(syscall_handler.internal_calls()[0]
+            .out_of_gas_syscall
+            .is_none());
+
+        // Enough gas to start the constructor, but not to pay for its storage write
+        let storage_write_gas = super::SYSCALL_GAS_COST["storage_write"] - super::SYSCALL_BASE;
+        deploy(
+            &mut syscall_handler,
+            2,
+            gas_consumed - storage_write_gas / 2,
+        );
+        let constructor_call = &syscall_handler.internal_calls()[1];
+        assert!(constructor_call.failure_flag);
+        assert_eq!(
+            constructor_call.out_of_gas_syscall.as_deref(),
+            Some("storage_write")
+        );
+
+        let tx_info = TransactionExecutionInfo {
+            call_info: Some(CallInfo {
+                internal_calls: syscall_handler.internal_calls().to_vec(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(tx_info.had_out_of_gas_syscall());
+        assert_matches!(
+            tx_info.out_of_gas_error(),
+            Some(TransactionError::OutOfGas { syscall }) if syscall == "storage_write"
+        );
+    }
+
+    #[test]
+    fn deploy_checks_the_constructor_calldata_length() {
+        let contract_class =
+            ContractClass::from_path("starknet_programs/constructor.json").unwrap();
+        let class_hash = [1; 32];
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        state
+            .set_contract_class(&class_hash, &contract_class)
+            .unwrap();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.block_context =
+            BlockContext::default().with_validate_constructor_calldata(true);
+        let mut vm = vm!();
+
+        // The constructor takes a single felt
+        let calldata_start = syscall_handler
+            .allocate_segment(&mut vm, vec![10.into(), 11.into()])
+            .unwrap();
+        let request = DeployRequest {
+            class_hash: Felt252::from_bytes_be(&class_hash),
+            salt: 1.into(),
+            calldata_start,
+            calldata_end: (calldata_start + 2).unwrap(),
+            deploy_from_zero: 0,
+        };
+        assert_matches!(
+            syscall_handler.deploy(&mut vm, request, 1_000_000),
+            Err(SyscallHandlerError::State(
+                StateError::ConstructorArgumentMismatch(1, 2)
+            ))
+        );
+    }
+
+    #[test]
+    fn deploy_from_zero_selects_the_deployer_address() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.contract_address = Address(5.into());
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler
+            .allocate_segment(&mut vm, vec![3.into(), 4.into()])
+            .unwrap();
+        let mut deploy = |deploy_from_zero: usize| {
+            let request = DeployRequest {
+                class_hash: 2.into(),
+                salt: 1.into(),
+                calldata_start,
+                calldata_end: (calldata_start + 2).unwrap(),
+                deploy_from_zero,
+            };
+            // The class isn't declared, so the constructor call fails after the deployment.
+            let (contract_address, _) = syscall_handler
+                .syscall_deploy(&vm, request, 100000)
+                .unwrap();
+            contract_address
+        };
+
+        // deploy_from_zero = 0 deploys from the caller (address 5), see
+        // `test_calculate_contract_address_from_hash`
+        assert_eq!(
+            deploy(0),
+            Address(felt_str!(
+                "1885555033409779003200115284723341705041371741573881252130189632266543809788"
+            ))
+        );
+        // deploy_from_zero = 1 deploys from the zero address, independently of the caller
+        assert_eq!(
+            deploy(1),
+            Address(
+                calculate_contract_address(
+                    &1.into(),
+                    &2.into(),
+                    &[3.into(), 4.into()],
+                    Address::default()
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn deployed_addresses_lists_contracts_deployed_by_syscall() {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/fibonacci.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+        let class_hash = [1; 32];
+        let existing_address = Address(5.into());
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(existing_address.clone(), class_hash);
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler.allocate_segment(&mut vm, vec![]).unwrap();
+        let mut expected: Vec<Address> = [1, 2]
+            .into_iter()
+            .map(|salt| {
+                let request = DeployRequest {
+                    class_hash: Felt252::from_bytes_be(&class_hash),
+                    salt: salt.into(),
+                    calldata_start,
+                    calldata_end: calldata_start,
+                    deploy_from_zero: 0,
+                };
+                let (contract_address, result) = syscall_handler
+                    .syscall_deploy(&vm, request, 100000)
+                    .unwrap();
+                assert!(result.is_success);
+                contract_address
+            })
+            .collect();
+
+        // Replacing the class of an already deployed contract isn't a deployment
+        state.set_class_hash_at(existing_address, [2; 32]).unwrap();
+
+        let mut deployed = state.deployed_addresses();
+        deployed.sort();
+        expected.sort();
+        assert_eq!(deployed, expected);
+    }
+
+    #[test]
+    fn library_call_charges_class_load_only_once() {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/fibonacci.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+        let selector = Felt252::from(
+            contract_class.entry_points_by_type.external[0]
+                .selector
+                .clone(),
+        );
+        let class_hash = [1; 32];
+        let compiled_class_hash = [2; 32];
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .casm_contract_classes_mut()
+            .insert(compiled_class_hash, contract_class);
+        state_reader
+            .class_hash_to_compiled_class_hash_mut()
+            .insert(class_hash, compiled_class_hash);
+        let mut state = CachedState::new(Arc::new(state_reader), None, None);
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.block_context.class_load_gas_cost = 5000;
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler
+            .allocate_segment(&mut vm, vec![1.into(), 1.into(), 10.into()])
+            .unwrap();
+        let gas_used: Vec<u128> = (0..2)
+            .map(|_| {
+                let request = LibraryCallRequest {
+                    class_hash: Felt252::from_bytes_be(&class_hash),
+                    selector: selector.clone(),
+                    calldata_start,
+                    calldata_end: (calldata_start + 3).unwrap(),
+                };
+                let response = syscall_handler
+                    .library_call(&mut vm, request, 1_000_000)
+                    .unwrap();
+                assert!(matches!(response.body, Some(ResponseBody::CallContract(_))));
+                1_000_000 - response.gas
+            })
+            .collect();
+
+        // Only the first call loads the class, the second one finds it cached.
+        assert_eq!(gas_used[0], gas_used[1] + 5000);
+    }
+
+    #[test]
+    fn library_call_with_too_long_calldata_fails() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        syscall_handler.block_context.max_calldata_length = 2;
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler
+            .allocate_segment(&mut vm, vec![1.into(), 1.into(), 10.into()])
+            .unwrap();
+        let request = LibraryCallRequest {
+            class_hash: 1.into(),
+            selector: 2.into(),
+            calldata_start,
+            calldata_end: (calldata_start + 3).unwrap(),
+        };
+        let response = syscall_handler
+            .library_call(&mut vm, request, 1000)
+            .unwrap();
+
+        assert_eq!(response.gas, 1000);
+        let Some(ResponseBody::Failure(failure)) = response.body else {
+            panic!("expected a failure response")
+        };
+        assert_eq!(
+            vm.get_integer(failure.retdata_start).unwrap().into_owned(),
+            Felt252::from_bytes_be(b"Calldata too long")
+        );
+        assert!(syscall_handler.internal_calls().is_empty());
+    }
+
+    #[test]
+    fn call_contract_with_unknown_selector_fails_with_entrypoint_not_found() {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/fibonacci.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        let address = Address(1111.into());
+        let class_hash = [1; 32];
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(address.clone(), class_hash);
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+        let mut syscall_handler = BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let mut vm = vm!();
+
+        let calldata_start = syscall_handler.allocate_segment(&mut vm, vec![]).unwrap();
+        let request = CallContractRequest {
+            selector: Felt252::from_bytes_be(&calculate_sn_keccak(b"not_a_function")),
+            contract_address: address,
+            calldata_start,
+            calldata_end: calldata_start,
+        };
+        let response = syscall_handler
+            .call_contract(&mut vm, request, 1_000_000)
+            .unwrap();
+
+        assert_eq!(response.gas, 1_000_000);
+        let Some(ResponseBody::Failure(failure)) = response.body else {
+            panic!("expected a failure response")
+        };
+        assert_eq!(
+            vm.get_integer(failure.retdata_start).unwrap().into_owned(),
+            Felt252::from_bytes_be(b"ENTRYPOINT_NOT_FOUND")
+        );
+        assert!(syscall_handler.internal_calls().is_empty());
+    }
+
+    #[test]
+    fn library_call_observes_the_delegating_contract_address() {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/delegate_addresses.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        let delegator_address = Address(1111.into());
+        let caller_address = Address(2222.into());
+        let class_hash = [1; 32];
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(delegator_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce_mut()
+            .insert(delegator_address.clone(), Felt252::zero());
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+
+        let exec_entry_point = ExecutionEntryPoint::new(
+            delegator_address.clone(),
+            vec![
+                Felt252::from_bytes_be(&class_hash),
+                Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_addresses")),
+            ],
+            Felt252::from_bytes_be(&calculate_sn_keccak(b"delegate_emit_addresses")),
+            caller_address.clone(),
+            EntryPointType::External,
+            Some(CallType::Call),
+            None,
+            1_000_000,
+        );
+        let block_context = BlockContext::default();
+        let mut tx_execution_context = TransactionExecutionContext::new(
+            Address(0.into()),
+            Felt252::zero(),
+            Vec::new(),
+            0,
+            10.into(),
+            block_context.invoke_tx_max_n_steps(),
+            TRANSACTION_VERSION.clone(),
+        );
+
+        let ExecutionResult { call_info, .. } = exec_entry_point
+            .execute(
+                &mut state,
+                &block_context,
+                &mut ExecutionResourcesManager::default(),
+                &mut tx_execution_context,
+                false,
+                block_context.invoke_tx_max_n_steps(),
+                false,
+            )
+            .unwrap();
+        let call_info = call_info.unwrap();
+
+        // The library call runs in the context of the delegating contract and keeps its caller.
+        let [library_call] = call_info.internal_calls.as_slice() else {
+            panic!("expected a single library call")
+        };
+        assert_eq!(library_call.call_type, Some(CallType::Delegate));
+        assert_eq!(library_call.contract_address, delegator_address);
+        assert_eq!(library_call.caller_address, caller_address);
+        assert_eq!(
+            library_call.events,
+            vec![OrderedEvent::new(
+                0,
+                vec![Felt252::from_bytes_be(&calculate_sn_keccak(
+                    b"ObservedAddresses"
+                ))],
+                vec![delegator_address.0, caller_address.0],
+            )]
+        );
+    }
+
+    /// Reader holding a single class that is stored but can't be deserialized.
+    struct CorruptClassStateReader {
+        corrupt_class_hash: ClassHash,
+    }
+
+    impl StateReader for CorruptClassStateReader {
+        fn get_contract_class(&self, class_hash: &ClassHash) -> Result<CompiledClass, StateError> {
+            if *class_hash == self.corrupt_class_hash {
+                Err(StateError::MalformedContractClass(
+                    *class_hash,
+                    "invalid gzip header".to_string(),
+                ))
+            } else {
+                Err(StateError::MissingCasmClass(*class_hash))
+            }
+        }
+
+        fn get_class_hash_at(&self, contract_address: &Address) -> Result<ClassHash, StateError> {
+            Err(StateError::NoneContractState(contract_address.clone()))
+        }
+
+        fn get_nonce_at(&self, _contract_address: &Address) -> Result<Felt252, StateError> {
+            Ok(Felt252::zero())
+        }
+
+        fn get_storage_at(&self, storage_entry: &StorageEntry) -> Result<Felt252, StateError> {
+            Err(StateError::NoneStorage(storage_entry.clone()))
+        }
+
+        fn get_compiled_class_hash(
+            &self,
+            class_hash: &ClassHash,
+        ) -> Result<CompiledClassHash, StateError> {
+            Err(StateError::NoneCompiledHash(*class_hash))
+        }
+    }
+
+    #[test]
+    fn execute_constructor_distinguishes_malformed_from_missing_classes() {
+        let mut state = CachedState::new(
+            Arc::new(CorruptClassStateReader {
+                corrupt_class_hash: [1; 32],
+            }),
+            None,
+            None,
+        );
+        let mut syscall_handler =
+            super::BusinessLogicSyscallHandler::default_with_state(&mut state);
+        let contract_address = Address(5.into());
+
+        let result = syscall_handler
+            .execute_constructor_entry_point(&contract_address, [1; 32], vec![], 1000, false)
+            .unwrap();
+        assert!(!result.is_success);
+        assert_eq!(
+            result.retdata,
+            vec![Felt252::from_bytes_be(b"CLASS_DESERIALIZATION_FAILED").into()]
+        );
+
+        let result = syscall_handler
+            .execute_constructor_entry_point(&contract_address, [2; 32], vec![], 1000, false)
+            .unwrap();
+        assert!(!result.is_success);
+        assert_eq!(
+            result.retdata,
+            vec![Felt252::from_bytes_be(b"CLASS_HASH_NOT_FOUND").into()]
+        );
+    }
+}
@@ -30,7 +30,7 @@ use crate::{
         state_api::{State, StateReader},
         BlockInfo,
     },
-    transaction::error::TransactionError,
+    transaction::{constructor_calldata_mismatch, error::TransactionError},
     utils::*,
 };
 use crate::{
@@ -174,7 +174,11 @@ impl<'a, S: StateReader> DeprecatedBLSyscallHandler<'a, S> {
             };
 
             if seg_size != used_size.into() {
-                return Err(TransactionError::OutOfBound);
+                return Err(TransactionError::OutOfBound(
+                    segment_ptr.segment_index,
+                    seg_size,
+                    used_size,
+                ));
             }
             runner.mark_address_range_as_accessed(segment_ptr, used_size)?;
         }
@@ -206,6 +210,17 @@ impl<'a, S: StateReader> DeprecatedBLSyscallHandler<'a, S> {
             .state
             .get_contract_class(&class_hash_bytes)?;
 
+        if let Some(expected) = constructor_calldata_mismatch(
+            &self.block_context,
+            &contract_class,
+            &constructor_calldata,
+        ) {
+            return Err(StateError::ConstructorArgumentMismatch(
+                expected,
+                constructor_calldata.len(),
+            ));
+        }
+
         if self.constructor_entry_points_empty(contract_class)? {
             if !constructor_calldata.is_empty() {
                 return Err(StateError::ConstructorCalldataEmpty());
@@ -811,9 +826,7 @@ impl<'a, S: StateReader> DeprecatedBLSyscallHandler<'a, S> {
         &mut self,
         address: Address,
     ) -> Result<Felt252, SyscallHandlerError> {
-        Ok(self
-            .starknet_storage_state
-            .read(&felt_to_hash(&address.0))?)
+        Ok(self.starknet_storage_state.read(&storage_key(&address.0))?)
     }
 
     pub(crate) fn syscall_storage_write(
@@ -821,8 +834,11 @@ impl<'a, S: StateReader> DeprecatedBLSyscallHandler<'a, S> {
         address: Address,
         value: Felt252,
     ) -> Result<(), SyscallHandlerError> {
-        self.starknet_storage_state
-            .write(&felt_to_hash(&address.0), value);
+        self.starknet_storage_state.write(
+            &storage_key(&address.0),
+            value,
+            self.internal_calls.len(),
+        );
 
         Ok(())
     }
@@ -919,8 +935,12 @@ impl<'a, S: StateReader> DeprecatedBLSyscallHandler<'a, S> {
 #[cfg(test)]
 mod tests {
     use crate::{
+        core::errors::state_errors::StateError,
+        definitions::block_context::BlockContext,
+        services::api::contract_classes::deprecated_contract_class::ContractClass,
         state::cached_state::CachedState,
         state::in_memory_state_reader::InMemoryStateReader,
+        state::state_api::State,
         syscalls::syscall_handler_errors::SyscallHandlerError,
         utils::{test_utils::*, Address},
     };
@@ -937,7 +957,7 @@ mod tests {
         },
         vm::{errors::memory_errors::MemoryError, vm_core::VirtualMachine},
     };
-    use num_traits::Zero;
+    use num_traits::{One, Zero};
     use std::{any::Any, borrow::Cow, collections::HashMap};
 
     type DeprecatedBLSyscallHandler<'a> =
@@ -986,6 +1006,43 @@ mod tests {
         )
     }
 
+    #[test]
+    fn deploy_checks_the_constructor_calldata_length() {
+        let contract_class =
+            ContractClass::from_path("starknet_programs/constructor.json").unwrap();
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        state
+            .set_contract_class(&Felt252::one().to_be_bytes(), &contract_class)
+            .unwrap();
+        let mut syscall = DeprecatedBLSyscallHandler::default_with(&mut state);
+        syscall.block_context = BlockContext::default().with_validate_constructor_calldata(true);
+        let mut vm = vm!();
+
+        add_segments!(vm, 2);
+
+        // The constructor takes a single felt
+        memory_insert!(
+            vm,
+            [
+                ((1, 0), 0),
+                ((1, 1), 1),
+                ((1, 2), 2),
+                ((1, 3), 2),
+                ((1, 4), (1, 20)),
+                ((1, 5), 0),
+                ((1, 20), 10),
+                ((1, 21), 11)
+            ]
+        );
+
+        assert_matches!(
+            syscall.syscall_deploy(&vm, relocatable!(1, 0)),
+            Err(SyscallHandlerError::State(
+                StateError::ConstructorArgumentMismatch(1, 2)
+            ))
+        )
+    }
+
     #[test]
     fn can_allocate_segment() {
         let mut state = CachedState::<InMemoryStateReader>::default();
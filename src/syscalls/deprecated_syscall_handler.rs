@@ -1,6 +1,8 @@
 use super::{
-    deprecated_business_logic_syscall_handler::DeprecatedBLSyscallHandler, hint_code::*,
-    other_syscalls, syscall_handler::HintProcessorPostRun,
+    deprecated_business_logic_syscall_handler::DeprecatedBLSyscallHandler,
+    hint_code::*,
+    other_syscalls,
+    syscall_handler::{ExecutionDeadline, HintProcessorPostRun},
 };
 use crate::{state::state_api::StateReader, syscalls::syscall_handler_errors::SyscallHandlerError};
 use cairo_vm::{
@@ -27,6 +29,7 @@ pub(crate) struct DeprecatedSyscallHintProcessor<'a, S: StateReader> {
     pub(crate) builtin_hint_processor: BuiltinHintProcessor,
     pub(crate) syscall_handler: DeprecatedBLSyscallHandler<'a, S>,
     run_resources: RunResources,
+    deadline: ExecutionDeadline,
 }
 
 /// Implementations and methods for DeprecatedSyscallHintProcessor
@@ -36,10 +39,12 @@ impl<'a, S: StateReader> DeprecatedSyscallHintProcessor<'a, S> {
         syscall_handler: DeprecatedBLSyscallHandler<'a, S>,
         run_resources: RunResources,
     ) -> Self {
+        let deadline = ExecutionDeadline::new(syscall_handler.block_context.execution_deadline);
         DeprecatedSyscallHintProcessor {
             builtin_hint_processor: BuiltinHintProcessor::new_empty(),
             syscall_handler,
             run_resources,
+            deadline,
         }
     }
 
@@ -182,11 +187,12 @@ impl<'a, S: StateReader> HintProcessorLogic for DeprecatedSyscallHintProcessor<'
 /// Implement the ResourceTracker trait for DeprecatedSyscallHintProcessor
 impl<'a, S: StateReader> ResourceTracker for DeprecatedSyscallHintProcessor<'a, S> {
     fn consumed(&self) -> bool {
-        self.run_resources.consumed()
+        self.run_resources.consumed() || self.deadline.exceeded()
     }
 
     fn consume_step(&mut self) {
-        self.run_resources.consume_step()
+        self.run_resources.consume_step();
+        self.deadline.consume_step();
     }
 
     fn get_n_steps(&self) -> Option<usize> {
@@ -231,7 +237,8 @@ mod tests {
     use crate::{
         add_segments, allocate_selector, any_box,
         definitions::{
-            block_context::BlockContext, constants::TRANSACTION_VERSION,
+            block_context::{BlockContext, FeeToken},
+            constants::TRANSACTION_VERSION,
             transaction_type::TransactionType,
         },
         execution::{OrderedEvent, OrderedL2ToL1Message, TransactionExecutionContext},
@@ -500,6 +507,7 @@ mod tests {
             nonce: 263.into(),
             n_sent_messages: 52,
             _n_steps: 100000,
+            fee_token: FeeToken::Eth,
         };
         syscall_handler_hint_processor
             .syscall_handler
@@ -824,6 +832,7 @@ mod tests {
             nonce: 263.into(),
             n_sent_messages: 52,
             _n_steps: 10000,
+            fee_token: FeeToken::Eth,
         };
         syscall_handler_hint_processor
             .syscall_handler
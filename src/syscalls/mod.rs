@@ -10,3 +10,5 @@ pub mod syscall_handler_errors;
 pub mod syscall_info;
 pub mod syscall_request;
 pub mod syscall_response;
+pub mod syscall_side_effects;
+pub mod syscall_trace;
@@ -20,7 +20,7 @@ use cairo_vm::{
         vm_core::VirtualMachine,
     },
 };
-use std::{any::Any, boxed::Box, collections::HashMap};
+use std::{any::Any, boxed::Box, collections::HashMap, time::Instant};
 
 pub(crate) trait HintProcessorPostRun {
     /// Performs post run syscall related tasks (if any).
@@ -31,11 +31,46 @@ pub(crate) trait HintProcessorPostRun {
     ) -> Result<(), TransactionError>;
 }
 
+/// Number of VM steps run between two checks of the execution deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+/// Tracks the wall-clock deadline of an execution from the VM run loop, reading the clock
+/// once every [`DEADLINE_CHECK_INTERVAL`] steps.
+pub(crate) struct ExecutionDeadline {
+    deadline: Option<Instant>,
+    n_steps: usize,
+    exceeded: bool,
+}
+
+impl ExecutionDeadline {
+    pub(crate) fn new(deadline: Option<Instant>) -> Self {
+        ExecutionDeadline {
+            deadline,
+            n_steps: 0,
+            exceeded: false,
+        }
+    }
+
+    pub(crate) fn consume_step(&mut self) {
+        if let Some(deadline) = self.deadline {
+            if self.n_steps % DEADLINE_CHECK_INTERVAL == 0 {
+                self.exceeded = Instant::now() >= deadline;
+            }
+            self.n_steps += 1;
+        }
+    }
+
+    pub(crate) fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
 #[allow(unused)]
 pub(crate) struct SyscallHintProcessor<'a, S: StateReader> {
     pub(crate) cairo1_hint_processor: Cairo1HintProcessor,
     pub(crate) syscall_handler: BusinessLogicSyscallHandler<'a, S>,
     pub(crate) run_resources: RunResources,
+    deadline: ExecutionDeadline,
 }
 
 impl<'a, S: StateReader> SyscallHintProcessor<'a, S> {
@@ -44,10 +79,12 @@ impl<'a, S: StateReader> SyscallHintProcessor<'a, S> {
         hints: &[(usize, Vec<Hint>)],
         run_resources: RunResources,
     ) -> Self {
+        let deadline = ExecutionDeadline::new(syscall_handler.block_context.execution_deadline);
         SyscallHintProcessor {
             cairo1_hint_processor: Cairo1HintProcessor::new(hints, run_resources.clone()),
             syscall_handler,
             run_resources,
+            deadline,
         }
     }
 }
@@ -113,11 +150,12 @@ impl<'a, S: StateReader> HintProcessorLogic for SyscallHintProcessor<'a, S> {
 
 impl<'a, S: StateReader> ResourceTracker for SyscallHintProcessor<'a, S> {
     fn consumed(&self) -> bool {
-        self.run_resources.consumed()
+        self.run_resources.consumed() || self.deadline.exceeded()
     }
 
     fn consume_step(&mut self) {
-        self.run_resources.consume_step()
+        self.run_resources.consume_step();
+        self.deadline.consume_step();
     }
 
     fn get_n_steps(&self) -> Option<usize> {
@@ -73,4 +73,10 @@ pub enum SyscallHandlerError {
     UnsupportedAddressDomain(String),
     #[error("{0:?}")]
     CustomError(String),
+    #[error("Invalid syscall trace encoding: {0}")]
+    InvalidSyscallTrace(String),
+    #[error("Execution halted by its step limit")]
+    ExecutionHalted,
+    #[error("Keccak chunk size must be between 1 and 25 words, got {0}")]
+    InvalidKeccakChunkSize(usize),
 }
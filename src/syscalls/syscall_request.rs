@@ -4,7 +4,7 @@ use num_traits::ToPrimitive;
 
 use crate::{
     syscalls::syscall_handler_errors::SyscallHandlerError,
-    utils::{get_big_int, get_integer, get_relocatable, Address},
+    utils::{get_big_int, get_felt_range, get_integer, get_relocatable, storage_key, Address},
 };
 // TODO: maybe we could make FromPtr trait more general, making
 //   it "move" the pointer received like they do in cairo-lang
@@ -50,6 +50,62 @@ pub(crate) enum SyscallRequest {
     Keccak(KeccakRequest),
 }
 
+impl SyscallRequest {
+    /// Returns the request arguments as felts, in the order they appear in the request.
+    /// Segments are inlined as their length followed by their contents.
+    pub(crate) fn trace_args(
+        &self,
+        vm: &VirtualMachine,
+    ) -> Result<Vec<Felt252>, SyscallHandlerError> {
+        let segment =
+            |start: Relocatable, end: Relocatable| -> Result<Vec<Felt252>, SyscallHandlerError> {
+                let values = get_felt_range(vm, start, end)?;
+                Ok([vec![values.len().into()], values].concat())
+            };
+
+        Ok(match self {
+            SyscallRequest::EmitEvent(req) => [
+                segment(req.keys_start, req.keys_end)?,
+                segment(req.data_start, req.data_end)?,
+            ]
+            .concat(),
+            SyscallRequest::LibraryCall(req) => [
+                vec![req.class_hash.clone(), req.selector.clone()],
+                segment(req.calldata_start, req.calldata_end)?,
+            ]
+            .concat(),
+            SyscallRequest::CallContract(req) => [
+                vec![req.contract_address.0.clone(), req.selector.clone()],
+                segment(req.calldata_start, req.calldata_end)?,
+            ]
+            .concat(),
+            SyscallRequest::Deploy(req) => [
+                vec![req.class_hash.clone(), req.salt.clone()],
+                segment(req.calldata_start, req.calldata_end)?,
+                vec![req.deploy_from_zero.into()],
+            ]
+            .concat(),
+            SyscallRequest::GetBlockNumber
+            | SyscallRequest::GetExecutionInfo
+            | SyscallRequest::GetBlockTimestamp(_) => Vec::new(),
+            SyscallRequest::StorageRead(req) => {
+                vec![req.reserved.clone(), Felt252::from_bytes_be(&req.key)]
+            }
+            SyscallRequest::StorageWrite(req) => {
+                vec![req.reserved.clone(), req.key.clone(), req.value.clone()]
+            }
+            SyscallRequest::SendMessageToL1(req) => [
+                vec![req.to_address.0.clone()],
+                segment(req.payload_start, req.payload_end)?,
+            ]
+            .concat(),
+            SyscallRequest::GetBlockHash(req) => vec![req.block_number.into()],
+            SyscallRequest::ReplaceClass(req) => vec![req.class_hash.clone()],
+            SyscallRequest::Keccak(req) => segment(req.input_start, req.input_end)?,
+        })
+    }
+}
+
 // ~~~~~~~~~~~~~~~~~~~~~~~~~
 //  SyscallRequest variants
 // ~~~~~~~~~~~~~~~~~~~~~~~~~
@@ -308,9 +364,9 @@ impl FromPtr for EmitEventRequest {
         syscall_ptr: Relocatable,
     ) -> Result<SyscallRequest, SyscallHandlerError> {
         let keys_start = get_relocatable(vm, syscall_ptr)?;
-        let keys_end = get_relocatable(vm, &syscall_ptr + 1)?;
-        let data_start = get_relocatable(vm, &syscall_ptr + 2)?;
-        let data_end = get_relocatable(vm, &syscall_ptr + 3)?;
+        let keys_end = get_relocatable(vm, (syscall_ptr + 1)?)?;
+        let data_start = get_relocatable(vm, (syscall_ptr + 2)?)?;
+        let data_end = get_relocatable(vm, (syscall_ptr + 3)?)?;
 
         Ok(EmitEventRequest {
             keys_start,
@@ -328,7 +384,7 @@ impl FromPtr for StorageReadRequest {
         syscall_ptr: Relocatable,
     ) -> Result<SyscallRequest, SyscallHandlerError> {
         let reserved = get_big_int(vm, syscall_ptr)?;
-        let key = get_big_int(vm, &syscall_ptr + 1)?.to_be_bytes();
+        let key = storage_key(&get_big_int(vm, (syscall_ptr + 1)?)?);
         Ok(StorageReadRequest { key, reserved }.into())
     }
 }
@@ -360,9 +416,9 @@ impl FromPtr for CallContractRequest {
         syscall_ptr: Relocatable,
     ) -> Result<SyscallRequest, SyscallHandlerError> {
         let contract_address = Address(get_big_int(vm, syscall_ptr)?);
-        let selector = get_big_int(vm, &syscall_ptr + 1)?;
-        let calldata_start = get_relocatable(vm, &syscall_ptr + 2)?;
-        let calldata_end = get_relocatable(vm, &syscall_ptr + 3)?;
+        let selector = get_big_int(vm, (syscall_ptr + 1)?)?;
+        let calldata_start = get_relocatable(vm, (syscall_ptr + 2)?)?;
+        let calldata_end = get_relocatable(vm, (syscall_ptr + 3)?)?;
         Ok(CallContractRequest {
             selector,
             contract_address,
@@ -379,9 +435,9 @@ impl FromPtr for LibraryCallRequest {
         syscall_ptr: Relocatable,
     ) -> Result<SyscallRequest, SyscallHandlerError> {
         let class_hash = get_big_int(vm, syscall_ptr)?;
-        let selector = get_big_int(vm, &syscall_ptr + 1)?;
-        let calldata_start = get_relocatable(vm, &syscall_ptr + 2)?;
-        let calldata_end = get_relocatable(vm, &syscall_ptr + 3)?;
+        let selector = get_big_int(vm, (syscall_ptr + 1)?)?;
+        let calldata_start = get_relocatable(vm, (syscall_ptr + 2)?)?;
+        let calldata_end = get_relocatable(vm, (syscall_ptr + 3)?)?;
 
         Ok(LibraryCallRequest {
             class_hash,
@@ -399,8 +455,8 @@ impl FromPtr for SendMessageToL1Request {
         syscall_ptr: Relocatable,
     ) -> Result<SyscallRequest, SyscallHandlerError> {
         let to_address = Address(get_big_int(vm, syscall_ptr)?);
-        let payload_start = get_relocatable(vm, &syscall_ptr + 1)?;
-        let payload_end = get_relocatable(vm, &syscall_ptr + 2)?;
+        let payload_start = get_relocatable(vm, (syscall_ptr + 1)?)?;
+        let payload_end = get_relocatable(vm, (syscall_ptr + 2)?)?;
 
         Ok(SendMessageToL1Request {
             to_address,
@@ -417,8 +473,8 @@ impl FromPtr for StorageWriteRequest {
         syscall_ptr: Relocatable,
     ) -> Result<SyscallRequest, SyscallHandlerError> {
         let reserved = get_big_int(vm, syscall_ptr)?;
-        let key = get_big_int(vm, &syscall_ptr + 1)?;
-        let value = get_big_int(vm, &syscall_ptr + 2)?;
+        let key = get_big_int(vm, (syscall_ptr + 1)?)?;
+        let value = get_big_int(vm, (syscall_ptr + 2)?)?;
 
         Ok(StorageWriteRequest {
             reserved,
@@ -435,7 +491,7 @@ impl FromPtr for KeccakRequest {
         syscall_ptr: Relocatable,
     ) -> Result<SyscallRequest, SyscallHandlerError> {
         let input_start = get_relocatable(vm, syscall_ptr)?;
-        let input_end = get_relocatable(vm, &syscall_ptr + 1)?;
+        let input_end = get_relocatable(vm, (syscall_ptr + 1)?)?;
 
         Ok(KeccakRequest {
             input_start,
@@ -0,0 +1,222 @@
+use crate::{
+    execution::{CallInfo, OrderedEvent, OrderedL2ToL1Message, StorageWrite},
+    utils::ClassHash,
+};
+use cairo_vm::felt::Felt252;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// Side effects collected by a syscall handler during a single call: the events and messages
+/// it emitted, the calls it performed and its storage accesses. Internal calls keep their own
+/// side effects, which are included recursively when serialized.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyscallSideEffects {
+    pub events: Vec<OrderedEvent>,
+    pub l2_to_l1_messages: Vec<OrderedL2ToL1Message>,
+    pub internal_calls: Vec<CallInfo>,
+    pub storage_read_values: Vec<Felt252>,
+    pub accessed_storage_keys: HashSet<ClassHash>,
+    pub storage_writes: Vec<StorageWrite>,
+}
+
+impl SyscallSideEffects {
+    /// Serializes the side effects as a JSON document meant for golden-file comparison.
+    /// Felts, addresses and storage keys are written as `0x` prefixed hex strings, object keys
+    /// are sorted and accessed storage keys listed in ascending order.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json_value()).unwrap_or_default()
+    }
+
+    fn to_json_value(&self) -> Value {
+        let mut accessed_storage_keys: Vec<&ClassHash> =
+            self.accessed_storage_keys.iter().collect();
+        accessed_storage_keys.sort();
+
+        json!({
+            "events": self.events.iter().map(event_to_json).collect::<Vec<_>>(),
+            "l2_to_l1_messages": self
+                .l2_to_l1_messages
+                .iter()
+                .map(message_to_json)
+                .collect::<Vec<_>>(),
+            "internal_calls": self.internal_calls.iter().map(call_to_json).collect::<Vec<_>>(),
+            "storage_read_values": felts_to_json(&self.storage_read_values),
+            "accessed_storage_keys": accessed_storage_keys
+                .into_iter()
+                .map(hash_to_json)
+                .collect::<Vec<_>>(),
+            "storage_writes": self
+                .storage_writes
+                .iter()
+                .map(|write| json!({ "key": hash_to_json(&write.key), "value": felt_to_json(&write.value) }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl From<&CallInfo> for SyscallSideEffects {
+    fn from(call_info: &CallInfo) -> Self {
+        SyscallSideEffects {
+            events: call_info.events.clone(),
+            l2_to_l1_messages: call_info.l2_to_l1_messages.clone(),
+            internal_calls: call_info.internal_calls.clone(),
+            storage_read_values: call_info.storage_read_values.clone(),
+            accessed_storage_keys: call_info.accessed_storage_keys.clone(),
+            storage_writes: call_info.storage_writes.clone(),
+        }
+    }
+}
+
+fn felt_to_json(felt: &Felt252) -> Value {
+    Value::String(format!("0x{}", felt.to_str_radix(16)))
+}
+
+fn felts_to_json(felts: &[Felt252]) -> Value {
+    Value::Array(felts.iter().map(felt_to_json).collect())
+}
+
+fn hash_to_json(hash: &ClassHash) -> Value {
+    felt_to_json(&Felt252::from_bytes_be(hash))
+}
+
+fn event_to_json(event: &OrderedEvent) -> Value {
+    json!({
+        "order": event.order,
+        "keys": felts_to_json(&event.keys),
+        "data": felts_to_json(&event.data),
+    })
+}
+
+fn message_to_json(message: &OrderedL2ToL1Message) -> Value {
+    json!({
+        "order": message.order,
+        "to_address": felt_to_json(&message.to_address.0),
+        "payload": felts_to_json(&message.payload),
+    })
+}
+
+fn call_to_json(call_info: &CallInfo) -> Value {
+    json!({
+        "contract_address": felt_to_json(&call_info.contract_address.0),
+        "class_hash": call_info.class_hash.as_ref().map(hash_to_json),
+        "entry_point_selector": call_info.entry_point_selector.as_ref().map(felt_to_json),
+        "calldata": felts_to_json(&call_info.calldata),
+        "retdata": felts_to_json(&call_info.retdata),
+        "failure_flag": call_info.failure_flag,
+        "side_effects": SyscallSideEffects::from(call_info).to_json_value(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::{block_context::BlockContext, constants::TRANSACTION_VERSION},
+        execution::{
+            execution_entry_point::{ExecutionEntryPoint, ExecutionResult},
+            CallType, TransactionExecutionContext,
+        },
+        state::{
+            cached_state::CachedState, in_memory_state_reader::InMemoryStateReader,
+            ExecutionResourcesManager,
+        },
+        utils::{calculate_sn_keccak, Address},
+        EntryPointType,
+    };
+    use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+    use num_traits::Zero;
+    use std::{collections::HashMap, sync::Arc};
+
+    fn execute_contract(program_data: &[u8], selector: &[u8], calldata: Vec<Felt252>) -> CallInfo {
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        let address = Address(1111.into());
+        let class_hash: ClassHash = [1; 32];
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(address.clone(), class_hash);
+        state_reader
+            .address_to_nonce_mut()
+            .insert(address.clone(), Felt252::zero());
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+
+        let exec_entry_point = ExecutionEntryPoint::new(
+            address,
+            calldata,
+            Felt252::from_bytes_be(&calculate_sn_keccak(selector)),
+            Address(0.into()),
+            EntryPointType::External,
+            Some(CallType::Delegate),
+            Some(class_hash),
+            100000,
+        );
+        let block_context = BlockContext::default();
+        let mut tx_execution_context = TransactionExecutionContext::new(
+            Address(0.into()),
+            Felt252::zero(),
+            Vec::new(),
+            0,
+            10.into(),
+            block_context.invoke_tx_max_n_steps(),
+            TRANSACTION_VERSION.clone(),
+        );
+
+        let ExecutionResult { call_info, .. } = exec_entry_point
+            .execute(
+                &mut state,
+                &block_context,
+                &mut ExecutionResourcesManager::default(),
+                &mut tx_execution_context,
+                false,
+                block_context.invoke_tx_max_n_steps(),
+                false,
+            )
+            .unwrap();
+        call_info.unwrap()
+    }
+
+    #[test]
+    fn side_effects_to_json() {
+        let events_call = execute_contract(
+            include_bytes!("../../starknet_programs/cairo2/events.casm"),
+            b"emit_event",
+            vec![1.into()],
+        );
+        let json: Value =
+            serde_json::from_str(&SyscallSideEffects::from(&events_call).to_json()).unwrap();
+
+        // emit_event(incremental: true) emits the counter and then increments it
+        let event_key = Felt252::from_bytes_be(&calculate_sn_keccak(b"IncrementalEvent"));
+        assert_eq!(
+            json["events"],
+            json!([{
+                "order": 0,
+                "keys": [format!("0x{}", event_key.to_str_radix(16))],
+                "data": ["0x0"],
+            }])
+        );
+        assert_eq!(json["storage_writes"].as_array().unwrap().len(), 1);
+        assert_eq!(json["storage_writes"][0]["value"], json!("0x1"));
+        assert_eq!(json["l2_to_l1_messages"], json!([]));
+
+        let message_call = execute_contract(
+            include_bytes!("../../starknet_programs/cairo2/send_simple_message_to_l1.casm"),
+            b"send_simple_message_to_l1",
+            vec![0x1a.into(), 0xff.into()],
+        );
+        let json: Value =
+            serde_json::from_str(&SyscallSideEffects::from(&message_call).to_json()).unwrap();
+
+        assert_eq!(
+            json["l2_to_l1_messages"],
+            json!([{ "order": 0, "to_address": "0x1a", "payload": ["0xff"] }])
+        );
+        assert_eq!(json["events"], json!([]));
+        assert_eq!(json["internal_calls"], json!([]));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{syscalls::syscall_handler_errors::SyscallHandlerError, utils::felt_to_hash};
+use cairo_vm::felt::Felt252;
+use std::fmt;
+
+const FELT_SIZE: usize = 32;
+const LENGTH_SIZE: usize = 4;
+
+/// A single syscall invocation: its selector and the decoded request arguments.
+/// Segments passed by pointer (calldata, event keys and data, etc.) are inlined as
+/// their length followed by their contents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyscallTraceEntry {
+    pub selector: Felt252,
+    pub args: Vec<Felt252>,
+}
+
+/// Ordered trace of the syscalls performed by a Cairo 1 contract call.
+///
+/// The binary encoding is a little-endian `u32` entry count followed by every entry,
+/// each one being its selector as a 32 byte big-endian felt, a little-endian `u32`
+/// argument count and the arguments as 32 byte big-endian felts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyscallTrace {
+    pub entries: Vec<SyscallTraceEntry>,
+}
+
+impl SyscallTrace {
+    pub(crate) fn push(&mut self, selector: Felt252, args: Vec<Felt252>) {
+        self.entries.push(SyscallTraceEntry { selector, args });
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let n_felts: usize = self.entries.iter().map(|entry| entry.args.len() + 1).sum();
+        let mut bytes =
+            Vec::with_capacity(LENGTH_SIZE * (self.entries.len() + 1) + FELT_SIZE * n_felts);
+
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in self.entries.iter() {
+            bytes.extend_from_slice(&felt_to_hash(&entry.selector));
+            bytes.extend_from_slice(&(entry.args.len() as u32).to_le_bytes());
+            for arg in entry.args.iter() {
+                bytes.extend_from_slice(&felt_to_hash(arg));
+            }
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SyscallHandlerError> {
+        let mut reader = TraceReader { bytes, offset: 0 };
+
+        let n_entries = reader.read_length()?;
+        let mut entries = Vec::new();
+        for _ in 0..n_entries {
+            let selector = reader.read_felt()?;
+            let n_args = reader.read_length()?;
+            let args = (0..n_args)
+                .map(|_| reader.read_felt())
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.push(SyscallTraceEntry { selector, args });
+        }
+
+        if reader.offset != bytes.len() {
+            return Err(SyscallHandlerError::InvalidSyscallTrace(format!(
+                "{} trailing bytes",
+                bytes.len() - reader.offset
+            )));
+        }
+
+        Ok(SyscallTrace { entries })
+    }
+}
+
+/// Syscall step reported to the tracer set with
+/// [`BusinessLogicSyscallHandler::with_syscall_tracer`].
+///
+/// [`BusinessLogicSyscallHandler::with_syscall_tracer`]: crate::syscalls::business_logic_syscall_handler::BusinessLogicSyscallHandler::with_syscall_tracer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallTraceEvent<'a> {
+    /// The syscall is about to run with `gas` available.
+    Before {
+        name: &'a str,
+        selector: &'a Felt252,
+        gas: u128,
+    },
+    /// The syscall ran, leaving `gas`. `failed` is set when it returned a failure response.
+    After {
+        name: &'a str,
+        gas: u128,
+        failed: bool,
+    },
+}
+
+/// Callback invoked by the syscall handler before and after every syscall.
+pub(crate) struct SyscallTracer<'a>(pub(crate) Box<dyn FnMut(&SyscallTraceEvent<'_>) + 'a>);
+
+impl fmt::Debug for SyscallTracer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SyscallTracer")
+    }
+}
+
+struct TraceReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> TraceReader<'a> {
+    fn read(&mut self, size: usize) -> Result<&'a [u8], SyscallHandlerError> {
+        let chunk = self
+            .bytes
+            .get(self.offset..self.offset + size)
+            .ok_or_else(|| {
+                SyscallHandlerError::InvalidSyscallTrace(format!(
+                    "unexpected end of input at byte {}",
+                    self.offset
+                ))
+            })?;
+        self.offset += size;
+        Ok(chunk)
+    }
+
+    fn read_length(&mut self) -> Result<usize, SyscallHandlerError> {
+        let mut length = [0; LENGTH_SIZE];
+        length.copy_from_slice(self.read(LENGTH_SIZE)?);
+        Ok(u32::from_le_bytes(length) as usize)
+    }
+
+    fn read_felt(&mut self) -> Result<Felt252, SyscallHandlerError> {
+        Ok(Felt252::from_bytes_be(self.read(FELT_SIZE)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::{block_context::BlockContext, constants::TRANSACTION_VERSION},
+        execution::{
+            execution_entry_point::{ExecutionEntryPoint, ExecutionResult},
+            CallType, TransactionExecutionContext,
+        },
+        state::{
+            cached_state::CachedState, in_memory_state_reader::InMemoryStateReader,
+            ExecutionResourcesManager,
+        },
+        utils::{calculate_sn_keccak, Address, ClassHash},
+        EntryPointType,
+    };
+    use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+    use num_traits::Zero;
+    use std::{collections::HashMap, sync::Arc};
+
+    #[test]
+    fn syscall_trace_encoding_round_trip() {
+        let mut trace = SyscallTrace::default();
+        trace.push(Felt252::from_bytes_be(b"GetBlockNumber"), vec![]);
+        trace.push(
+            Felt252::from_bytes_be(b"StorageWrite"),
+            vec![0.into(), 5.into(), Felt252::zero() - Felt252::from(1)],
+        );
+
+        let bytes = trace.to_bytes();
+        assert_eq!(bytes.len(), 4 + (32 + 4) * 2 + 32 * 3);
+        assert_eq!(SyscallTrace::from_bytes(&bytes).unwrap(), trace);
+
+        assert_matches!(
+            SyscallTrace::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(SyscallHandlerError::InvalidSyscallTrace(_))
+        );
+        assert_matches!(
+            SyscallTrace::from_bytes(&[bytes.as_slice(), &[0]].concat()),
+            Err(SyscallHandlerError::InvalidSyscallTrace(_))
+        );
+    }
+
+    #[test]
+    fn syscall_trace_of_contract_execution() {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/events.casm");
+
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        let address = Address(1111.into());
+        let class_hash: ClassHash = [1; 32];
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(address.clone(), class_hash);
+        state_reader
+            .address_to_nonce_mut()
+            .insert(address.clone(), Felt252::zero());
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+
+        let exec_entry_point = ExecutionEntryPoint::new(
+            address.clone(),
+            vec![1.into()],
+            Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+            Address(0.into()),
+            EntryPointType::External,
+            Some(CallType::Delegate),
+            Some(class_hash),
+            100000,
+        );
+        let block_context = BlockContext::default();
+        let mut tx_execution_context = TransactionExecutionContext::new(
+            Address(0.into()),
+            Felt252::zero(),
+            Vec::new(),
+            0,
+            10.into(),
+            block_context.invoke_tx_max_n_steps(),
+            TRANSACTION_VERSION.clone(),
+        );
+
+        let ExecutionResult { call_info, .. } = exec_entry_point
+            .execute(
+                &mut state,
+                &block_context,
+                &mut ExecutionResourcesManager::default(),
+                &mut tx_execution_context,
+                false,
+                block_context.invoke_tx_max_n_steps(),
+                true,
+            )
+            .unwrap();
+        let trace = call_info.unwrap().syscall_trace.unwrap();
+
+        // emit_event(incremental: true) reads the counter, emits it and writes it back
+        let selectors: Vec<Felt252> = trace
+            .entries
+            .iter()
+            .map(|entry| entry.selector.clone())
+            .collect();
+        assert_eq!(
+            selectors,
+            vec![
+                Felt252::from_bytes_be(b"StorageRead"),
+                Felt252::from_bytes_be(b"EmitEvent"),
+                Felt252::from_bytes_be(b"StorageRead"),
+                Felt252::from_bytes_be(b"StorageWrite"),
+            ]
+        );
+
+        let decoded = SyscallTrace::from_bytes(&trace.to_bytes()).unwrap();
+        assert_eq!(decoded, trace);
+        assert_eq!(decoded.to_bytes(), trace.to_bytes());
+    }
+}
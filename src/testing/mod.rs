@@ -7,7 +7,9 @@ use std::{collections::HashMap, sync::Arc};
 
 use cairo_vm::felt::{felt_str, Felt252};
 use lazy_static::lazy_static;
-use num_traits::Zero;
+use num_integer::Integer;
+use num_traits::{Pow, Zero};
+use starknet_crypto::{pedersen_hash, FieldElement};
 
 use crate::{
     definitions::{
@@ -19,7 +21,7 @@ use crate::{
         cached_state::CachedState, in_memory_state_reader::InMemoryStateReader,
         state_cache::StorageEntry, BlockInfo,
     },
-    utils::{felt_to_hash, Address, ClassHash},
+    utils::{calculate_sn_keccak, felt_to_hash, storage_key, Address, ClassHash},
 };
 
 pub const ACCOUNT_CONTRACT_PATH: &str = "starknet_programs/account_without_validation.json";
@@ -72,13 +74,25 @@ pub fn new_starknet_block_context_for_testing() -> BlockContext {
         0,
         DEFAULT_CAIRO_RESOURCE_FEE_WEIGHTS.clone(),
         1_000_000,
-        0,
+        1_000_000,
         BlockInfo::empty(TEST_SEQUENCER_ADDRESS.clone()),
         HashMap::default(),
         true,
     )
 }
 
+/// Returns the storage key of the lower 128 bits of `address`'s balance in the `ERC20_balances`
+/// storage variable of the Cairo 0 ERC20 contract. The upper bits are stored in the next key.
+pub fn erc20_balance_key(address: &Address) -> ClassHash {
+    let variable = FieldElement::from_bytes_be(&calculate_sn_keccak(b"ERC20_balances"))
+        .unwrap_or(FieldElement::ZERO);
+    let account =
+        FieldElement::from_bytes_be(&felt_to_hash(&address.0)).unwrap_or(FieldElement::ZERO);
+    let key = Felt252::from_bytes_be(&pedersen_hash(&variable, &account).to_bytes_be())
+        .mod_floor(&(Felt252::new(2).pow(251) - Felt252::new(256)));
+    felt_to_hash(&key)
+}
+
 pub fn create_account_tx_test_state(
 ) -> Result<(BlockContext, CachedState<InMemoryStateReader>), Box<dyn std::error::Error>> {
     let block_context = new_starknet_block_context_for_testing();
@@ -129,11 +143,9 @@ pub fn create_account_tx_test_state(
             for (contract_address, class_hash) in address_to_class_hash {
                 let storage_keys: HashMap<(Address, ClassHash), Felt252> = storage_view
                     .iter()
-                    .filter_map(|((address, storage_key), storage_value)| {
-                        (address == &contract_address).then_some((
-                            (address.clone(), felt_to_hash(storage_key)),
-                            storage_value.clone(),
-                        ))
+                    .filter_map(|((address, key), storage_value)| {
+                        (address == &contract_address)
+                            .then_some(((address.clone(), storage_key(key)), storage_value.clone()))
                     })
                     .collect();
 
@@ -1,29 +1,37 @@
-use super::{state_error::StarknetStateError, type_utils::ExecutionInfo};
+use super::{erc20_balance_key, state_error::StarknetStateError, type_utils::ExecutionInfo};
 use crate::execution::execution_entry_point::ExecutionResult;
+use crate::hash_utils::{current_hash_backend, with_shared_hash_backend};
 use crate::services::api::contract_classes::deprecated_contract_class::EntryPointType;
 use crate::{
+    core::contract_address::{compute_casm_class_hash, compute_deprecated_class_hash},
     definitions::{block_context::BlockContext, constants::TRANSACTION_VERSION},
+    estimate_fee,
     execution::{
         execution_entry_point::ExecutionEntryPoint, CallInfo, Event, TransactionExecutionContext,
         TransactionExecutionInfo,
     },
+    hash_utils::calculate_contract_address,
     services::api::{
-        contract_classes::deprecated_contract_class::ContractClass, messages::StarknetMessageToL1,
+        contract_classes::{
+            compiled_class::CompiledClass, deprecated_contract_class::ContractClass,
+        },
+        messages::StarknetMessageToL1,
     },
     state::{
-        cached_state::CachedState,
+        cached_state::{CachedState, UNINITIALIZED_CLASS_HASH},
         state_api::{State, StateReader},
     },
     state::{in_memory_state_reader::InMemoryStateReader, ExecutionResourcesManager},
     transaction::{
         error::TransactionError, invoke_function::InvokeFunction, Declare, Deploy, Transaction,
     },
-    utils::{Address, ClassHash},
+    utils::{calculate_sn_keccak, felt_to_hash, Address, ClassHash},
 };
 use cairo_vm::felt::Felt252;
 use num_traits::{One, Zero};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // ---------------------------------------------------------------------
 /// StarkNet testing object. Represents a state of a StarkNet network.
@@ -33,6 +41,9 @@ pub struct StarknetState {
     l2_to_l1_messages: HashMap<Vec<u8>, usize>,
     l2_to_l1_messages_log: Vec<StarknetMessageToL1>,
     events: Vec<Event>,
+    executed_tx_hashes: Option<HashSet<Felt252>>,
+    class_history: HashMap<Address, Vec<ClassHash>>,
+    max_retained_events: Option<usize>,
 }
 
 impl StarknetState {
@@ -52,6 +63,9 @@ impl StarknetState {
             l2_to_l1_messages,
             l2_to_l1_messages_log,
             events,
+            executed_tx_hashes: None,
+            class_history: HashMap::new(),
+            max_retained_events: None,
         }
     }
 
@@ -70,7 +84,40 @@ impl StarknetState {
             l2_to_l1_messages,
             l2_to_l1_messages_log,
             events,
+            executed_tx_hashes: None,
+            class_history: HashMap::new(),
+            max_retained_events: None,
+        }
+    }
+
+    /// Deploys `token_class`, an ERC20 contract, at the fee token address of the block context
+    /// and sets the balance of every address in `initial_balances`, so fee-charging transactions
+    /// can be executed. Balances must fit in 128 bits.
+    pub fn with_fee_token(
+        mut self,
+        token_class: ContractClass,
+        initial_balances: HashMap<Address, Felt252>,
+    ) -> Result<Self, StarknetStateError> {
+        let class_hash = felt_to_hash(
+            &compute_deprecated_class_hash(&token_class).map_err(TransactionError::from)?,
+        );
+        let fee_token_address = self
+            .block_context
+            .starknet_os_config
+            .fee_token_address
+            .clone();
+
+        self.state.set_contract_class(&class_hash, &token_class)?;
+        self.state
+            .deploy_contract(fee_token_address.clone(), class_hash)?;
+        for (address, balance) in initial_balances {
+            self.state.set_storage_at(
+                &(fee_token_address.clone(), erc20_balance_key(&address)),
+                balance,
+            );
         }
+
+        Ok(self)
     }
 
     // ------------------------------------------------------------------------------------
@@ -97,6 +144,38 @@ impl StarknetState {
         Ok((tx.class_hash, tx_execution_info))
     }
 
+    /// Makes a contract class available in the state without executing a declare transaction,
+    /// so it can be deployed afterwards. Returns the class hash of deprecated classes and the
+    /// compiled class hash of Casm classes.
+    pub fn register_class(
+        &mut self,
+        contract_class: CompiledClass,
+    ) -> Result<ClassHash, StarknetStateError> {
+        match contract_class {
+            CompiledClass::Deprecated(contract_class) => {
+                let class_hash = felt_to_hash(
+                    &compute_deprecated_class_hash(&contract_class)
+                        .map_err(TransactionError::from)?,
+                );
+                self.state
+                    .set_contract_class(&class_hash, &contract_class)?;
+                Ok(class_hash)
+            }
+            CompiledClass::Casm(casm_class) => {
+                let compiled_class_hash =
+                    compute_casm_class_hash(&casm_class).map_err(TransactionError::from)?;
+                self.state
+                    .casm_contract_classes
+                    .get_or_insert_with(HashMap::new)
+                    .insert(
+                        felt_to_hash(&compiled_class_hash),
+                        casm_class.as_ref().clone(),
+                    );
+                Ok(felt_to_hash(&compiled_class_hash))
+            }
+        }
+    }
+
     /// Invokes a contract function. Returns the execution info.
 
     #[allow(clippy::too_many_arguments)]
@@ -125,6 +204,56 @@ impl StarknetState {
         self.execute_tx(&mut tx, remaining_gas)
     }
 
+    /// Invokes the contract function named `function_name`, see [`Self::invoke_raw`].
+    /// Fails if the contract has no external entry point with that name.
+    #[allow(clippy::too_many_arguments)]
+    pub fn invoke_by_name(
+        &mut self,
+        contract_address: Address,
+        function_name: &str,
+        calldata: Vec<Felt252>,
+        max_fee: u128,
+        signature: Option<Vec<Felt252>>,
+        nonce: Option<Felt252>,
+        hash_value: Option<Felt252>,
+        remaining_gas: u128,
+    ) -> Result<TransactionExecutionInfo, StarknetStateError> {
+        let selector = Felt252::from_bytes_be(&calculate_sn_keccak(function_name.as_bytes()));
+
+        let class_hash = self.state.get_class_hash_at(&contract_address)?;
+        let has_entry_point = match StateReader::get_contract_class(&self.state, &class_hash)? {
+            CompiledClass::Deprecated(contract_class) => contract_class
+                .entry_points_by_type()
+                .get(&EntryPointType::External)
+                .map_or(false, |entry_points| {
+                    entry_points
+                        .iter()
+                        .any(|entry_point| entry_point.selector() == &selector)
+                }),
+            CompiledClass::Casm(contract_class) => contract_class
+                .entry_points_by_type
+                .external
+                .iter()
+                .any(|entry_point| Felt252::from(entry_point.selector.clone()) == selector),
+        };
+        if !has_entry_point {
+            return Err(StarknetStateError::EntryPointNotFound(
+                function_name.to_string(),
+            ));
+        }
+
+        self.invoke_raw(
+            contract_address,
+            selector,
+            calldata,
+            max_fee,
+            signature,
+            nonce,
+            hash_value,
+            remaining_gas,
+        )
+    }
+
     /// Builds the transaction execution context and executes the entry point.
     /// Returns the CallInfo.
     pub fn execute_entry_point_raw(
@@ -148,6 +277,7 @@ impl StarknetState {
         let mut resources_manager = ExecutionResourcesManager::default();
 
         let mut tx_execution_context = TransactionExecutionContext::default();
+        let class_hashes_before = self.state.cache.class_hash_writes.clone();
         let ExecutionResult { call_info, .. } = call.execute(
             &mut self.state,
             &self.block_context,
@@ -157,6 +287,7 @@ impl StarknetState {
             self.block_context.invoke_tx_max_n_steps,
             false,
         )?;
+        self.record_class_changes(&class_hashes_before);
 
         let call_info = call_info.ok_or(StarknetStateError::Transaction(
             TransactionError::CallInfoIsNone,
@@ -209,15 +340,235 @@ impl StarknetState {
         Ok((contract_address, tx_execution_info))
     }
 
+    /// Returns the address a contract of class `class_hash` gets when deployed by
+    /// `deployer_address` with the given salt and constructor calldata. Deploy transactions use
+    /// the zero address as deployer, the `deploy` syscall the calling contract's address
+    /// (or zero when `deploy_from_zero` is set).
+    pub fn predict_contract_address(
+        &self,
+        contract_address_salt: &Felt252,
+        class_hash: &ClassHash,
+        constructor_calldata: &[Felt252],
+        deployer_address: Address,
+    ) -> Result<Address, StarknetStateError> {
+        Ok(Address(calculate_contract_address(
+            contract_address_salt,
+            &Felt252::from_bytes_be(class_hash),
+            constructor_calldata,
+            deployer_address,
+        )?))
+    }
+
     pub fn execute_tx(
         &mut self,
         tx: &mut Transaction,
         remaining_gas: u128,
     ) -> Result<TransactionExecutionInfo, StarknetStateError> {
-        let tx = tx.execute(&mut self.state, &self.block_context, remaining_gas)?;
-        let tx_execution_info = ExecutionInfo::Transaction(Box::new(tx.clone()));
+        self.check_not_executed(tx)?;
+        let sender_nonce_before = self.sender_nonce(tx)?;
+        let class_hashes_before = self.state.cache.class_hash_writes.clone();
+        let mut tx_info = tx.execute(&mut self.state, &self.block_context, remaining_gas)?;
+        self.record_class_changes(&class_hashes_before);
+        tx_info.sender_nonce_before = sender_nonce_before;
+        tx_info.sender_nonce_after = self.sender_nonce(tx)?;
+        self.record_executed(tx);
+        let tx_execution_info = ExecutionInfo::Transaction(Box::new(tx_info.clone()));
         self.add_messages_and_events(&tx_execution_info)?;
-        Ok(tx)
+        Ok(tx_info)
+    }
+
+    /// Executes a transaction as `execute_tx` does, with `sequencer_address` replacing the
+    /// block's sequencer for its execution and fee transfer. The block's sequencer is restored
+    /// afterwards.
+    pub fn execute_tx_with_sequencer_address(
+        &mut self,
+        tx: &mut Transaction,
+        remaining_gas: u128,
+        sequencer_address: Address,
+    ) -> Result<TransactionExecutionInfo, StarknetStateError> {
+        let block_sequencer_address = std::mem::replace(
+            &mut self.block_context.block_info.sequencer_address,
+            sequencer_address,
+        );
+        let result = self.execute_tx(tx, remaining_gas);
+        self.block_context.block_info.sequencer_address = block_sequencer_address;
+        result
+    }
+
+    /// Makes `execute_tx` reject transactions whose hash was already executed successfully
+    /// by this state.
+    pub fn enable_replay_protection(&mut self) {
+        self.executed_tx_hashes.get_or_insert_with(HashSet::new);
+    }
+
+    fn check_not_executed(&self, tx: &Transaction) -> Result<(), StarknetStateError> {
+        match &self.executed_tx_hashes {
+            Some(hashes) if hashes.contains(&tx.hash_value()) => Err(
+                StarknetStateError::DuplicateTransactionHash(tx.hash_value()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_executed(&mut self, tx: &Transaction) {
+        if let Some(hashes) = &mut self.executed_tx_hashes {
+            hashes.insert(tx.hash_value());
+        }
+    }
+
+    /// Appends to the class history of every address whose class hash changed since
+    /// `class_hashes_before`, a snapshot of the class hash writes taken before an execution.
+    fn record_class_changes(&mut self, class_hashes_before: &HashMap<Address, ClassHash>) {
+        for (address, class_hash) in self.state.cache.class_hash_writes.iter() {
+            let previous = match class_hashes_before.get(address) {
+                Some(previous) => *previous,
+                None => self
+                    .state
+                    .state_reader
+                    .get_class_hash_at(address)
+                    .unwrap_or(*UNINITIALIZED_CLASS_HASH),
+            };
+            if previous == *class_hash {
+                continue;
+            }
+
+            let history = self.class_history.entry(address.clone()).or_default();
+            // The class may have been set outside of an execution, e.g. at genesis.
+            if previous != *UNINITIALIZED_CLASS_HASH && history.last() != Some(&previous) {
+                history.push(previous);
+            }
+            history.push(*class_hash);
+        }
+    }
+
+    /// Returns every class hash `address` has had, oldest first, so upgrades done through
+    /// `replace_class` can be verified. Changes are recorded by the executions performed
+    /// through this object; a class set directly on `state` since then is appended last.
+    pub fn class_history(&self, address: &Address) -> Vec<ClassHash> {
+        let mut history = self.class_history.get(address).cloned().unwrap_or_default();
+        if let Ok(class_hash) = self.state.get_class_hash_at(address) {
+            if class_hash != *UNINITIALIZED_CLASS_HASH && history.last() != Some(&class_hash) {
+                history.push(class_hash);
+            }
+        }
+        history
+    }
+
+    /// Writes the nonce of `address` to the cached state, overriding the nonce set by previous
+    /// executions, so accounts can be set up without executing transactions.
+    pub fn set_nonce(&mut self, address: Address, nonce: Felt252) {
+        self.state
+            .cache_mut()
+            .nonce_writes_mut()
+            .insert(address, nonce);
+    }
+
+    /// Returns the current nonce of `address`, zero if it has none.
+    pub fn nonce_of(&self, address: &Address) -> Felt252 {
+        self.state
+            .get_nonce_at(address)
+            .unwrap_or_else(|_| Felt252::zero())
+    }
+
+    /// Returns the current nonce of the transaction's sender, or `None` for transactions
+    /// without an L2 sender.
+    fn sender_nonce(&self, tx: &Transaction) -> Result<Option<Felt252>, StarknetStateError> {
+        match tx {
+            Transaction::Deploy(_) | Transaction::L1Handler(_) => Ok(None),
+            _ => Ok(Some(self.state.get_nonce_at(&tx.sender_address())?)),
+        }
+    }
+
+    /// Executes a transaction as `execute_tx` does, aborting it with
+    /// [`TransactionError::Timeout`] if it runs for longer than `timeout`. The deadline is checked
+    /// while the VM runs, the state is left untouched when the transaction times out.
+    pub fn execute_tx_with_timeout(
+        &mut self,
+        tx: &mut Transaction,
+        remaining_gas: u128,
+        timeout: Duration,
+    ) -> Result<TransactionExecutionInfo, StarknetStateError> {
+        let deadline = Instant::now() + timeout;
+        let state_before = self.state.clone();
+        let block_deadline = self.block_context.execution_deadline.replace(deadline);
+        let result = self.execute_tx(tx, remaining_gas);
+        self.block_context.execution_deadline = block_deadline;
+
+        // Aborted runs surface as VM errors, so tell timeouts apart by the deadline.
+        if result.is_err() && Instant::now() >= deadline {
+            self.state = state_before;
+            return Err(TransactionError::Timeout.into());
+        }
+        result
+    }
+
+    /// Simulates independent transactions concurrently, each one against its own read-only view
+    /// of the current state, which is left untouched. Messages and events aren't recorded.
+    /// Results are returned in the order of `txs`. The workers compute hashes with the calling
+    /// thread's hash backend, see [`with_hash_backend`].
+    ///
+    /// [`with_hash_backend`]: crate::hash_utils::with_hash_backend
+    pub fn execute_parallel(
+        &self,
+        txs: &[Transaction],
+        remaining_gas: u128,
+    ) -> Vec<Result<TransactionExecutionInfo, StarknetStateError>> {
+        if txs.is_empty() {
+            return Vec::new();
+        }
+        let n_workers = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(txs.len());
+        let chunk_size = (txs.len() + n_workers - 1) / n_workers;
+        let state = Arc::new(self.state.clone());
+        let block_context = &self.block_context;
+        let hash_backend = current_hash_backend();
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = txs
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let state = Arc::clone(&state);
+                    let hash_backend = hash_backend.clone();
+                    scope.spawn(move || {
+                        with_shared_hash_backend(hash_backend, || {
+                            chunk
+                                .iter()
+                                .map(|tx| {
+                                    let mut view = state.readonly_view();
+                                    tx.execute(&mut view, block_context, remaining_gas)
+                                        .map_err(StarknetStateError::from)
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| match worker.join() {
+                    Ok(results) => results,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                })
+                .collect()
+        })
+    }
+
+    /// Estimates the fee of a transaction on top of the current state, which is left
+    /// untouched, adding a `margin_percent` buffer to it. The result is rounded up.
+    pub fn estimate_fee_with_margin(
+        &self,
+        tx: &Transaction,
+        margin_percent: u128,
+    ) -> Result<u128, StarknetStateError> {
+        let (fee, _) = estimate_fee(
+            std::slice::from_ref(tx),
+            self.state.clone(),
+            &self.block_context,
+        )?[0];
+
+        Ok(fee.saturating_mul(100 + margin_percent).saturating_add(99) / 100)
     }
 
     pub fn add_messages_and_events(
@@ -241,9 +592,41 @@ impl StarknetState {
 
         let mut events = exec_info.get_sorted_events()?;
         self.events.append(&mut events);
+        self.trim_events();
         Ok(())
     }
 
+    /// Bounds the event log to the `max_retained_events` most recent events, dropping the
+    /// oldest ones as new events are added. `None` (the default) keeps every event. Execution
+    /// infos still report all the events they emitted.
+    pub fn set_max_retained_events(&mut self, max_retained_events: Option<usize>) {
+        self.max_retained_events = max_retained_events;
+        self.trim_events();
+    }
+
+    fn trim_events(&mut self) {
+        if let Some(max_retained_events) = self.max_retained_events {
+            let excess = self.events.len().saturating_sub(max_retained_events);
+            self.events.drain(..excess);
+        }
+    }
+
+    /// Returns every event emitted so far whose first key is `key`, in emission order.
+    pub fn events_with_key(&self, key: &Felt252) -> Vec<&Event> {
+        self.events
+            .iter()
+            .filter(|event| event.keys.first() == Some(key))
+            .collect()
+    }
+
+    /// Empties the event and L2 to L1 message logs, including the pending message counts
+    /// used by `consume_message_hash`. The contract state is left untouched.
+    pub fn clear_logs(&mut self) {
+        self.l2_to_l1_messages.clear();
+        self.l2_to_l1_messages_log.clear();
+        self.events.clear();
+    }
+
     /// Consumes the given message hash.
     pub fn consume_message_hash(
         &mut self,
@@ -252,10 +635,10 @@ impl StarknetState {
         let val = self
             .l2_to_l1_messages
             .get(&message_hash)
-            .ok_or(StarknetStateError::InvalidMessageHash)?;
+            .ok_or(StarknetStateError::MessageHashNotFound)?;
 
         if val.is_zero() {
-            Err(StarknetStateError::InvalidMessageHash)
+            Err(StarknetStateError::MessageAlreadyConsumed)
         } else {
             self.l2_to_l1_messages.insert(message_hash, val - 1);
             Ok(())
@@ -320,21 +703,46 @@ impl StarknetState {
 mod tests {
     use std::path::PathBuf;
 
+    use cairo_lang_starknet::casm_contract_class::CasmContractClass;
     use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
     use num_traits::Num;
 
     use super::*;
     use crate::{
-        core::contract_address::compute_deprecated_class_hash,
         definitions::{
-            constants::CONSTRUCTOR_ENTRY_POINT_SELECTOR, transaction_type::TransactionType,
+            block_context::StarknetChainId,
+            constants::{CONSTRUCTOR_ENTRY_POINT_SELECTOR, EXECUTE_ENTRY_POINT_SELECTOR},
+            transaction_type::TransactionType,
         },
-        execution::{CallType, OrderedL2ToL1Message},
+        execution::{CallType, OrderedEvent, OrderedL2ToL1Message},
         hash_utils::calculate_contract_address,
         state::state_cache::StorageEntry,
-        utils::{calculate_sn_keccak, felt_to_hash},
+        testing::{
+            create_account_tx_test_state, new_starknet_block_context_for_testing,
+            ACCOUNT_CONTRACT_PATH, ERC20_CONTRACT_PATH, TEST_ACCOUNT_CONTRACT_ADDRESS,
+            TEST_CONTRACT_ADDRESS, TEST_CONTRACT_PATH, TEST_ERC20_ACCOUNT_BALANCE_KEY,
+            TEST_ERC20_SEQUENCER_BALANCE_KEY, TEST_SEQUENCER_ADDRESS,
+        },
     };
 
+    /// Deploys the cairo2 `events` contract at address 1111.
+    fn deploy_events_contract(starknet_state: &mut StarknetState) -> Address {
+        let program_data = include_bytes!("../../starknet_programs/cairo2/events.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+        let class_hash: ClassHash = [1; 32];
+        let contract_address = Address(1111.into());
+        starknet_state
+            .state
+            .casm_contract_classes
+            .get_or_insert_with(HashMap::new)
+            .insert(class_hash, contract_class);
+        starknet_state
+            .state
+            .deploy_contract(contract_address.clone(), class_hash)
+            .unwrap();
+        contract_address
+    }
+
     #[test]
     fn test_deploy() {
         let mut starknet_state = StarknetState::new(None);
@@ -378,6 +786,7 @@ mod tests {
             actual_fee: 0,
             actual_resources,
             tx_type: Some(TransactionType::Deploy),
+            ..Default::default()
         };
 
         // check result is correct
@@ -408,6 +817,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_predict_contract_address() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        let class_hash = felt_to_hash(&compute_deprecated_class_hash(&contract_class).unwrap());
+        let contract_address_salt = Felt252::from(7);
+
+        let predicted_address = starknet_state
+            .predict_contract_address(
+                &contract_address_salt,
+                &class_hash,
+                &[],
+                Address(Felt252::zero()),
+            )
+            .unwrap();
+        let (contract_address, _exec_info) = starknet_state
+            .deploy(contract_class, vec![], contract_address_salt, None, 0)
+            .unwrap();
+
+        assert_eq!(predicted_address, contract_address);
+    }
+
     #[test]
     fn test_declare() {
         let path = PathBuf::from("starknet_programs/account_without_validation.json");
@@ -542,11 +973,7 @@ mod tests {
         .unwrap();
 
         // Statement **not** in blockifier.
-        starknet_state
-            .state
-            .cache_mut()
-            .nonce_initial_values_mut()
-            .insert(contract_address.clone(), Felt252::zero());
+        starknet_state.set_nonce(contract_address.clone(), Felt252::zero());
 
         let tx_info = starknet_state
             .invoke_raw(
@@ -601,12 +1028,493 @@ mod tests {
             }),
             actual_resources,
             tx_type: Some(TransactionType::InvokeFunction),
+            sender_nonce_before: Some(Felt252::zero()),
+            sender_nonce_after: Some(Felt252::one()),
             ..Default::default()
         };
 
         assert_eq!(tx_info, expected_info);
     }
 
+    #[test]
+    fn test_set_nonce_and_nonce_of() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+
+        let (contract_address, _exec_info) = starknet_state
+            .deploy(contract_class, vec![], 1.into(), None, 0)
+            .unwrap();
+        assert_eq!(starknet_state.nonce_of(&contract_address), Felt252::zero());
+
+        starknet_state.set_nonce(contract_address.clone(), 3.into());
+        assert_eq!(starknet_state.nonce_of(&contract_address), 3.into());
+
+        // Passing no nonce makes invoke_raw use the seeded one
+        starknet_state
+            .invoke_by_name(
+                contract_address.clone(),
+                "fib",
+                vec![1.into(), 1.into(), 10.into()],
+                0,
+                Some(Vec::new()),
+                None,
+                None,
+                0,
+            )
+            .unwrap();
+        assert_eq!(starknet_state.nonce_of(&contract_address), 4.into());
+
+        // Setting the nonce after an execution overrides the incremented one
+        starknet_state.set_nonce(contract_address.clone(), 10.into());
+        assert_eq!(starknet_state.nonce_of(&contract_address), 10.into());
+    }
+
+    #[test]
+    fn test_invoke_by_name() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+
+        let (contract_address, _exec_info) = starknet_state
+            .deploy(contract_class, vec![], 1.into(), None, 0)
+            .unwrap();
+        starknet_state.set_nonce(contract_address.clone(), Felt252::zero());
+
+        let tx_info = starknet_state
+            .invoke_by_name(
+                contract_address.clone(),
+                "fib",
+                vec![1.into(), 1.into(), 10.into()],
+                0,
+                Some(Vec::new()),
+                Some(Felt252::zero()),
+                None,
+                0,
+            )
+            .unwrap();
+        let call_info = tx_info.call_info.unwrap();
+        assert_eq!(
+            call_info.entry_point_selector,
+            Some(Felt252::from_bytes_be(&calculate_sn_keccak(b"fib")))
+        );
+        assert_eq!(call_info.retdata, vec![144.into()]);
+
+        let result = starknet_state.invoke_by_name(
+            contract_address,
+            "fibonacci",
+            vec![1.into(), 1.into(), 10.into()],
+            0,
+            Some(Vec::new()),
+            Some(Felt252::one()),
+            None,
+            0,
+        );
+        assert_matches!(
+            result,
+            Err(StarknetStateError::EntryPointNotFound(name)) if name == "fibonacci"
+        );
+    }
+
+    #[test]
+    fn test_invoke_twice_increments_nonce() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+
+        let (contract_address, _exec_info) = starknet_state
+            .deploy(contract_class, vec![], 1.into(), None, 0)
+            .unwrap();
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+
+        starknet_state.set_nonce(contract_address.clone(), Felt252::zero());
+
+        for nonce in 0..2 {
+            // Passing no nonce makes invoke_raw use the one currently stored for the contract
+            let tx_info = starknet_state
+                .invoke_raw(
+                    contract_address.clone(),
+                    selector.clone(),
+                    vec![1.into(), 1.into(), 10.into()],
+                    0,
+                    None,
+                    None,
+                    None,
+                    0,
+                )
+                .unwrap();
+
+            assert_eq!(tx_info.sender_nonce_before, Some(Felt252::from(nonce)));
+            assert_eq!(tx_info.sender_nonce_after, Some(Felt252::from(nonce + 1)));
+        }
+
+        assert_eq!(
+            starknet_state
+                .state
+                .get_nonce_at(&contract_address)
+                .unwrap(),
+            Felt252::from(2)
+        );
+    }
+
+    #[test]
+    fn test_execute_tx_with_timeout_leaves_state_untouched() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_address = deploy_events_contract(&mut starknet_state);
+
+        let tx = starknet_state
+            .create_invoke_function(
+                contract_address.clone(),
+                Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+                vec![1.into()],
+                0,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let mut tx = Transaction::InvokeFunction(tx);
+
+        // The deadline has already passed when the VM runs its first step
+        assert_matches!(
+            starknet_state.execute_tx_with_timeout(&mut tx, 10_000_000, Duration::ZERO),
+            Err(StarknetStateError::Transaction(TransactionError::Timeout))
+        );
+        assert_eq!(
+            starknet_state
+                .state
+                .get_nonce_at(&contract_address)
+                .unwrap(),
+            Felt252::zero()
+        );
+
+        let tx_info = starknet_state
+            .execute_tx_with_timeout(&mut tx, 10_000_000, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(tx_info.call_info.unwrap().events.len(), 1);
+    }
+
+    #[test]
+    fn test_execute_tx_with_timeout_aborts_running_loop() {
+        let mut starknet_state = StarknetState::new(None);
+        // Lift the step limit so that only the deadline can stop the loop
+        starknet_state.block_context.invoke_tx_max_n_steps = u64::MAX;
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        let (contract_address, _exec_info) = starknet_state
+            .deploy(contract_class, vec![], 1.into(), None, 0)
+            .unwrap();
+
+        // fibonacci selector, the loop runs no syscall
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let tx = starknet_state
+            .create_invoke_function(
+                contract_address,
+                selector,
+                vec![1.into(), 1.into(), 1_000_000_000.into()],
+                0,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let mut tx = Transaction::InvokeFunction(tx);
+
+        let start = Instant::now();
+        assert_matches!(
+            starknet_state.execute_tx_with_timeout(&mut tx, 0, Duration::from_millis(100)),
+            Err(StarknetStateError::Transaction(TransactionError::Timeout))
+        );
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_execute_tx_with_sequencer_address() {
+        let (mut block_context, state) = create_account_tx_test_state().unwrap();
+        let block_sequencer_address = Address(4099.into());
+        block_context.block_info.sequencer_address = block_sequencer_address.clone();
+        let mut starknet_state = StarknetState::new(Some(block_context));
+        starknet_state.state = state;
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let mut tx = Transaction::InvokeFunction(
+            InvokeFunction::new(
+                TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+                EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+                1_000_000,
+                1.into(),
+                vec![
+                    TEST_CONTRACT_ADDRESS.0.clone(),
+                    selector,
+                    3.into(),
+                    1.into(),
+                    1.into(),
+                    10.into(),
+                ],
+                vec![],
+                StarknetChainId::TestNet.to_felt(),
+                Some(0.into()),
+            )
+            .unwrap(),
+        );
+
+        let tx_info = starknet_state
+            .execute_tx_with_sequencer_address(&mut tx, 0, TEST_SEQUENCER_ADDRESS.clone())
+            .unwrap();
+        assert!(tx_info.actual_fee > 0);
+        assert_eq!(
+            tx_info.fee_transfer_info.unwrap().calldata[0],
+            TEST_SEQUENCER_ADDRESS.0
+        );
+
+        let fee_token_address = starknet_state
+            .block_context
+            .starknet_os_config()
+            .fee_token_address()
+            .clone();
+        assert_eq!(
+            starknet_state
+                .state
+                .get_storage_at(&(
+                    fee_token_address,
+                    felt_to_hash(&TEST_ERC20_SEQUENCER_BALANCE_KEY)
+                ))
+                .unwrap(),
+            Felt252::from(tx_info.actual_fee)
+        );
+        assert_eq!(
+            starknet_state.block_context.block_info.sequencer_address,
+            block_sequencer_address
+        );
+    }
+
+    #[test]
+    fn test_with_fee_token() {
+        let initial_balance = Felt252::from(10_000_000);
+        let mut starknet_state = StarknetState::new(Some(new_starknet_block_context_for_testing()))
+            .with_fee_token(
+                ContractClass::from_path(ERC20_CONTRACT_PATH).unwrap(),
+                HashMap::from([(
+                    TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+                    initial_balance.clone(),
+                )]),
+            )
+            .unwrap();
+        assert_eq!(
+            erc20_balance_key(&TEST_ACCOUNT_CONTRACT_ADDRESS),
+            felt_to_hash(&TEST_ERC20_ACCOUNT_BALANCE_KEY)
+        );
+
+        for (address, path) in [
+            (TEST_ACCOUNT_CONTRACT_ADDRESS.clone(), ACCOUNT_CONTRACT_PATH),
+            (TEST_CONTRACT_ADDRESS.clone(), TEST_CONTRACT_PATH),
+        ] {
+            let contract_class = ContractClass::from_path(path).unwrap();
+            let class_hash = felt_to_hash(&compute_deprecated_class_hash(&contract_class).unwrap());
+            starknet_state
+                .state
+                .set_contract_class(&class_hash, &contract_class)
+                .unwrap();
+            starknet_state
+                .state
+                .deploy_contract(address, class_hash)
+                .unwrap();
+        }
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let mut tx = Transaction::InvokeFunction(
+            InvokeFunction::new(
+                TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+                EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+                1_000_000,
+                1.into(),
+                vec![
+                    TEST_CONTRACT_ADDRESS.0.clone(),
+                    selector,
+                    3.into(),
+                    1.into(),
+                    1.into(),
+                    10.into(),
+                ],
+                vec![],
+                StarknetChainId::TestNet.to_felt(),
+                Some(0.into()),
+            )
+            .unwrap(),
+        );
+        let tx_info = starknet_state.execute_tx(&mut tx, 0).unwrap();
+        assert!(tx_info.actual_fee > 0);
+
+        let fee_token_address = starknet_state
+            .block_context
+            .starknet_os_config()
+            .fee_token_address()
+            .clone();
+        assert_eq!(
+            starknet_state
+                .state
+                .get_storage_at(&(
+                    fee_token_address,
+                    erc20_balance_key(&TEST_ACCOUNT_CONTRACT_ADDRESS)
+                ))
+                .unwrap(),
+            initial_balance - Felt252::from(tx_info.actual_fee)
+        );
+    }
+
+    #[test]
+    fn test_execute_parallel() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+
+        let (contract_address, _exec_info) = starknet_state
+            .deploy(contract_class, vec![], 1.into(), None, 0)
+            .unwrap();
+        starknet_state.set_nonce(contract_address.clone(), Felt252::zero());
+
+        let selector = Felt252::from_bytes_be(&calculate_sn_keccak(b"fib"));
+        let ns: Vec<u64> = (0..8).collect();
+        let txs: Vec<Transaction> = ns
+            .iter()
+            .map(|n| {
+                Transaction::InvokeFunction(
+                    starknet_state
+                        .create_invoke_function(
+                            contract_address.clone(),
+                            selector.clone(),
+                            vec![1.into(), 1.into(), (*n).into()],
+                            0,
+                            None,
+                            None,
+                            None,
+                        )
+                        .unwrap(),
+                )
+            })
+            .collect();
+
+        let original_state = starknet_state.state.clone();
+        let results = starknet_state.execute_parallel(&txs, 0);
+
+        assert_eq!(results.len(), ns.len());
+        for (n, result) in ns.into_iter().zip(results) {
+            let (mut a, mut b) = (1_u64, 1_u64);
+            for _ in 0..n {
+                (a, b) = (b, a + b);
+            }
+            let call_info = result.unwrap().call_info.unwrap();
+            assert_eq!(call_info.retdata, vec![b.into()]);
+        }
+        assert_eq!(starknet_state.state, original_state);
+    }
+
+    #[test]
+    fn test_estimate_fee_with_margin() {
+        let mut block_context = BlockContext::default();
+        block_context.starknet_os_config.gas_price = 1;
+        let mut starknet_state = StarknetState::new(Some(block_context.clone()));
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+
+        let (contract_address, _exec_info) = starknet_state
+            .deploy(contract_class, vec![], 1.into(), None, 0)
+            .unwrap();
+        starknet_state.set_nonce(contract_address.clone(), Felt252::zero());
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let tx = Transaction::InvokeFunction(
+            starknet_state
+                .create_invoke_function(
+                    contract_address,
+                    selector,
+                    vec![1.into(), 1.into(), 10.into()],
+                    0,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap(),
+        );
+
+        let (base_fee, _) = estimate_fee(
+            std::slice::from_ref(&tx),
+            starknet_state.state.clone(),
+            &block_context,
+        )
+        .unwrap()[0];
+        assert!(base_fee > 0);
+
+        let original_state = starknet_state.state.clone();
+        assert_eq!(
+            starknet_state.estimate_fee_with_margin(&tx, 0).unwrap(),
+            base_fee
+        );
+        assert_eq!(
+            starknet_state.estimate_fee_with_margin(&tx, 10).unwrap(),
+            (base_fee * 110 + 99) / 100
+        );
+        assert_eq!(starknet_state.state, original_state);
+    }
+
+    #[test]
+    fn test_register_class() {
+        let mut starknet_state = StarknetState::new(None);
+        let program_data = include_bytes!("../../starknet_programs/cairo2/fibonacci.casm");
+        let casm_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        let class_hash = starknet_state
+            .register_class(CompiledClass::Casm(Arc::new(casm_class.clone())))
+            .unwrap();
+        assert_eq!(
+            class_hash,
+            felt_to_hash(&compute_casm_class_hash(&casm_class).unwrap())
+        );
+
+        let contract_address = Address(1111.into());
+        starknet_state
+            .state
+            .deploy_contract(contract_address.clone(), class_hash)
+            .unwrap();
+        let call_info = starknet_state
+            .execute_entry_point_raw(
+                contract_address,
+                Felt252::from_bytes_be(&calculate_sn_keccak(b"fib")),
+                vec![1.into(), 1.into(), 10.into()],
+                Address(0.into()),
+            )
+            .unwrap();
+        assert_eq!(call_info.retdata, vec![144.into()]);
+
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        let class_hash = starknet_state
+            .register_class(CompiledClass::Deprecated(Arc::new(contract_class.clone())))
+            .unwrap();
+        assert_matches!(
+            StateReader::get_contract_class(&starknet_state.state, &class_hash),
+            Ok(CompiledClass::Deprecated(registered)) if *registered == contract_class
+        );
+    }
+
     #[test]
     fn test_execute_entry_point_raw() {
         let mut starknet_state = StarknetState::new(None);
@@ -662,6 +1570,237 @@ mod tests {
         assert_eq!(messages, expected_messages);
     }
 
+    #[test]
+    fn test_events_with_key() {
+        let mut starknet_state = StarknetState::new(None);
+        let exec_info = ExecutionInfo::Call(Box::new(CallInfo {
+            events: vec![
+                OrderedEvent::new(1, vec![1.into(), 10.into()], vec![0.into()]),
+                OrderedEvent::new(2, vec![2.into()], vec![1.into()]),
+                OrderedEvent::new(3, vec![], vec![2.into()]),
+                OrderedEvent::new(4, vec![1.into()], vec![3.into()]),
+            ],
+            ..Default::default()
+        }));
+        starknet_state.add_messages_and_events(&exec_info).unwrap();
+
+        let data: Vec<&Vec<Felt252>> = starknet_state
+            .events_with_key(&1.into())
+            .into_iter()
+            .map(|event| &event.data)
+            .collect();
+        assert_eq!(data, vec![&vec![0.into()], &vec![3.into()]]);
+        assert!(starknet_state.events_with_key(&3.into()).is_empty());
+    }
+
+    #[test]
+    fn test_events_with_key_after_emitting_two_event_types() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_address = deploy_events_contract(&mut starknet_state);
+
+        // emit_event(true) emits an IncrementalEvent with the counter, emit_event(false) a
+        // StaticEvent without data
+        for incremental in [true, false, true] {
+            let tx = starknet_state
+                .create_invoke_function(
+                    contract_address.clone(),
+                    Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+                    vec![Felt252::from(incremental as u8)],
+                    0,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            starknet_state
+                .execute_tx(&mut Transaction::InvokeFunction(tx), 10_000_000)
+                .unwrap();
+        }
+
+        let incremental_key = Felt252::from_bytes_be(&calculate_sn_keccak(b"IncrementalEvent"));
+        let data: Vec<&Vec<Felt252>> = starknet_state
+            .events_with_key(&incremental_key)
+            .into_iter()
+            .map(|event| &event.data)
+            .collect();
+        assert_eq!(data, vec![&vec![0.into()], &vec![1.into()]]);
+
+        let static_key = Felt252::from_bytes_be(&calculate_sn_keccak(b"StaticEvent"));
+        assert_eq!(starknet_state.events_with_key(&static_key).len(), 1);
+    }
+
+    #[test]
+    fn test_max_retained_events() {
+        let mut starknet_state = StarknetState::new(None);
+        starknet_state.set_max_retained_events(Some(2));
+        let contract_address = deploy_events_contract(&mut starknet_state);
+
+        // Each emit_event(true) call emits the counter before incrementing it
+        let mut emitted_events = Vec::new();
+        for _ in 0..5 {
+            let tx = starknet_state
+                .create_invoke_function(
+                    contract_address.clone(),
+                    Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+                    vec![1.into()],
+                    0,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let tx_info = starknet_state
+                .execute_tx(&mut Transaction::InvokeFunction(tx), 10_000_000)
+                .unwrap();
+            emitted_events.extend(tx_info.get_sorted_events().unwrap());
+        }
+
+        // Every execution reported its event, only the two most recent ones are retained
+        let emitted_data: Vec<Vec<Felt252>> = emitted_events
+            .iter()
+            .map(|event| event.data.clone())
+            .collect();
+        assert_eq!(
+            emitted_data,
+            (0..5).map(|i| vec![Felt252::from(i)]).collect::<Vec<_>>()
+        );
+        assert_eq!(starknet_state.events, emitted_events[3..].to_vec());
+
+        starknet_state.set_max_retained_events(Some(1));
+        assert_eq!(starknet_state.events, emitted_events[4..].to_vec());
+    }
+
+    #[test]
+    fn test_class_history_after_replacing_the_class_twice() {
+        let mut starknet_state = StarknetState::new(None);
+        let class_hash_a: ClassHash = [1; 32];
+        let class_hash_b: ClassHash = [2; 32];
+        for (class_hash, program_data) in [
+            (
+                class_hash_a,
+                &include_bytes!("../../starknet_programs/cairo2/get_number_a.casm")[..],
+            ),
+            (
+                class_hash_b,
+                &include_bytes!("../../starknet_programs/cairo2/get_number_b.casm")[..],
+            ),
+        ] {
+            let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+            starknet_state
+                .state
+                .casm_contract_classes
+                .get_or_insert_with(HashMap::new)
+                .insert(class_hash, contract_class);
+        }
+
+        let contract_address = Address(1111.into());
+        starknet_state
+            .state
+            .deploy_contract(contract_address.clone(), class_hash_a)
+            .unwrap();
+        assert_eq!(
+            starknet_state.class_history(&contract_address),
+            vec![class_hash_a]
+        );
+
+        for new_class_hash in [class_hash_b, class_hash_a] {
+            let tx = starknet_state
+                .create_invoke_function(
+                    contract_address.clone(),
+                    Felt252::from_bytes_be(&calculate_sn_keccak(b"upgrade")),
+                    vec![Felt252::from_bytes_be(&new_class_hash)],
+                    0,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            starknet_state
+                .execute_tx(&mut Transaction::InvokeFunction(tx), 10_000_000)
+                .unwrap();
+        }
+
+        assert_eq!(
+            starknet_state.class_history(&contract_address),
+            vec![class_hash_a, class_hash_b, class_hash_a]
+        );
+        assert!(starknet_state
+            .class_history(&Address(2222.into()))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_clear_logs() {
+        let mut starknet_state = StarknetState::new(None);
+        let contract_address = deploy_events_contract(&mut starknet_state);
+
+        let emit_event = |starknet_state: &mut StarknetState| {
+            let tx = starknet_state
+                .create_invoke_function(
+                    contract_address.clone(),
+                    Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+                    vec![1.into()],
+                    0,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            starknet_state
+                .execute_tx(&mut Transaction::InvokeFunction(tx), 10_000_000)
+                .unwrap()
+        };
+
+        emit_event(&mut starknet_state);
+        emit_event(&mut starknet_state);
+        assert_eq!(starknet_state.events.len(), 2);
+
+        starknet_state.clear_logs();
+        assert!(starknet_state.events.is_empty());
+        assert!(starknet_state.l2_to_l1_messages.is_empty());
+        assert!(starknet_state.l2_to_l1_messages_log.is_empty());
+
+        // Only the logs are cleared, the account nonce keeps increasing
+        let tx_info = emit_event(&mut starknet_state);
+        assert_eq!(starknet_state.events, tx_info.get_sorted_events().unwrap());
+        assert_eq!(starknet_state.events.len(), 1);
+        assert_eq!(
+            starknet_state
+                .state
+                .get_nonce_at(&contract_address)
+                .unwrap(),
+            Felt252::from(3)
+        );
+    }
+
+    #[test]
+    fn test_execute_tx_twice_with_replay_protection_should_fail() {
+        let mut starknet_state = StarknetState::new(None);
+        starknet_state.enable_replay_protection();
+        let contract_address = deploy_events_contract(&mut starknet_state);
+
+        let tx = starknet_state
+            .create_invoke_function(
+                contract_address,
+                Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+                vec![1.into()],
+                0,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let tx_hash = tx.hash_value().clone();
+        let mut tx = Transaction::InvokeFunction(tx);
+
+        starknet_state.execute_tx(&mut tx, 10_000_000).unwrap();
+        assert_matches!(
+            starknet_state.execute_tx(&mut tx, 10_000_000),
+            Err(StarknetStateError::DuplicateTransactionHash(hash)) if hash == tx_hash
+        );
+        assert_eq!(starknet_state.events.len(), 1);
+    }
+
     #[test]
     fn test_consume_message_hash() {
         let mut starknet_state = StarknetState::new(None);
@@ -689,6 +1828,11 @@ mod tests {
         starknet_state
             .consume_message_hash(msg_hash.clone())
             .unwrap();
+        let err = starknet_state
+            .consume_message_hash(vec![0; 32])
+            .unwrap_err();
+        assert_matches!(err, StarknetStateError::MessageHashNotFound);
+
         let messages = starknet_state.l2_to_l1_messages;
         let mut expected_messages = HashMap::new();
         expected_messages.insert(msg_hash, 1);
@@ -718,6 +1862,6 @@ mod tests {
             .consume_message_hash(msg_hash.clone())
             .unwrap();
         let err = starknet_state.consume_message_hash(msg_hash).unwrap_err();
-        assert_matches!(err, StarknetStateError::InvalidMessageHash);
+        assert_matches!(err, StarknetStateError::MessageAlreadyConsumed);
     }
 }
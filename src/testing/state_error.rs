@@ -1,3 +1,4 @@
+use cairo_vm::felt::Felt252;
 use thiserror::Error;
 
 use crate::{
@@ -7,8 +8,14 @@ use crate::{
 
 #[derive(Debug, Error)]
 pub enum StarknetStateError {
-    #[error("Invalid message hash key passed to l2 messages")]
-    InvalidMessageHash,
+    #[error("Message hash not found in l2 messages")]
+    MessageHashNotFound,
+    #[error("Every message with the given hash has already been consumed")]
+    MessageAlreadyConsumed,
+    #[error("Transaction with hash {0} was already executed")]
+    DuplicateTransactionHash(Felt252),
+    #[error("Contract has no external entry point named {0}")]
+    EntryPointNotFound(String),
     #[error(transparent)]
     Syscall(#[from] SyscallHandlerError),
     #[error(transparent)]
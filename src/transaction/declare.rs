@@ -4,8 +4,8 @@ use crate::services::api::contract_classes::deprecated_contract_class::EntryPoin
 use crate::state::cached_state::CachedState;
 use crate::{
     core::{
-        contract_address::compute_deprecated_class_hash,
-        transaction_hash::calculate_declare_transaction_hash,
+        contract_address::compute_deprecated_class_hash, errors::state_errors::StateError,
+        transaction_hash::calculate_declare_transaction_hash_with_class_hash,
     },
     definitions::{
         block_context::BlockContext, constants::VALIDATE_DECLARE_ENTRY_POINT_SELECTOR,
@@ -28,7 +28,8 @@ use cairo_vm::felt::Felt252;
 use num_traits::Zero;
 
 use super::fee::charge_fee;
-use super::{verify_version, Transaction};
+use super::invoke_function::verify_validation_n_steps;
+use super::{apply_validate_hook, verify_version, Transaction};
 
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 ///  Represents an internal transaction in the StarkNet network that is a declaration of a Cairo
@@ -65,10 +66,35 @@ impl Declare {
         nonce: Felt252,
     ) -> Result<Self, TransactionError> {
         let hash = compute_deprecated_class_hash(&contract_class)?;
-        let class_hash = felt_to_hash(&hash);
 
-        let hash_value = calculate_declare_transaction_hash(
-            &contract_class,
+        Self::new_with_class_hash(
+            contract_class,
+            felt_to_hash(&hash),
+            chain_id,
+            sender_address,
+            max_fee,
+            version,
+            signature,
+            nonce,
+        )
+    }
+
+    /// Creates a Declare transaction for a class whose hash was already computed, skipping the
+    /// class hashing done by [`Declare::new`]. The caller is responsible for `class_hash` being
+    /// the hash of `contract_class`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_class_hash(
+        contract_class: ContractClass,
+        class_hash: ClassHash,
+        chain_id: Felt252,
+        sender_address: Address,
+        max_fee: u128,
+        version: Felt252,
+        signature: Vec<Felt252>,
+        nonce: Felt252,
+    ) -> Result<Self, TransactionError> {
+        let hash_value = calculate_declare_transaction_hash_with_class_hash(
+            Felt252::from_bytes_be(&class_hash),
             chain_id,
             &sender_address,
             max_fee,
@@ -166,6 +192,7 @@ impl Declare {
         } else {
             self.run_validate_entrypoint(state, &mut resources_manager, block_context)?
         };
+        let syscall_counter = resources_manager.syscall_invocations();
         let changes = state.count_actual_storage_changes();
         let actual_resources = calculate_tx_resources(
             resources_manager,
@@ -183,7 +210,8 @@ impl Declare {
             None,
             actual_resources,
             Some(self.tx_type),
-        ))
+        )
+        .with_syscall_counter(syscall_counter))
     }
 
     // ~~~~~~~~~~~~~~~~~~~~~~~~~~~
@@ -210,6 +238,9 @@ impl Declare {
         if self.version.is_zero() || self.version == *QUERY_VERSION_BASE {
             return Ok(None);
         }
+        if apply_validate_hook(block_context)? {
+            return Ok(None);
+        }
 
         let calldata = self.get_calldata();
 
@@ -228,7 +259,7 @@ impl Declare {
             state,
             block_context,
             resources_manager,
-            &mut self.get_execution_context(block_context.invoke_tx_max_n_steps),
+            &mut self.get_execution_context(block_context.validate_max_n_steps),
             false,
             block_context.validate_max_n_steps,
             false,
@@ -238,6 +269,7 @@ impl Declare {
 
         verify_no_calls_to_other_contracts(&call_info)
             .map_err(|_| TransactionError::UnauthorizedActionOnValidate)?;
+        verify_validation_n_steps(&call_info, block_context.validate_max_n_steps)?;
 
         Ok(Some(call_info))
     }
@@ -263,11 +295,18 @@ impl Declare {
 
     /// Calculates actual fee used by the transaction using the execution
     /// info returned by apply(), then updates the transaction execution info with the data of the fee.
+    /// Redeclaring a class that is already in the state is rejected with
+    /// `ClassAlreadyDeclared`, except for version 0 declarations where it is a no-op.
     pub fn execute<S: StateReader>(
         &self,
         state: &mut CachedState<S>,
         block_context: &BlockContext,
     ) -> Result<TransactionExecutionInfo, TransactionError> {
+        let already_declared = is_class_declared(state, &self.class_hash)?;
+        if already_declared && !(self.version.is_zero() || self.version == *QUERY_VERSION_BASE) {
+            return Err(TransactionError::ClassAlreadyDeclared(self.class_hash));
+        }
+
         self.handle_nonce(state)?;
         let mut tx_exec_info = self.apply(state, block_context)?;
 
@@ -282,7 +321,9 @@ impl Declare {
             self.skip_fee_transfer,
         )?;
 
-        state.set_contract_class(&self.class_hash, &self.contract_class)?;
+        if !already_declared {
+            state.set_contract_class(&self.class_hash, &self.contract_class)?;
+        }
 
         tx_exec_info.set_fee_info(actual_fee, fee_transfer_info);
 
@@ -312,6 +353,19 @@ impl Declare {
     }
 }
 
+/// Returns whether `class_hash` is already declared in `state`. State readers report a missing
+/// class with different errors, so only a class that exists but can't be read is an error.
+fn is_class_declared<S: StateReader>(
+    state: &mut CachedState<S>,
+    class_hash: &ClassHash,
+) -> Result<bool, TransactionError> {
+    match state.get_contract_class(class_hash) {
+        Ok(_) => Ok(true),
+        Err(err @ (StateError::MalformedContractClass(..) | StateError::Io(_))) => Err(err.into()),
+        Err(_) => Ok(false),
+    }
+}
+
 // ---------------
 //     Tests
 // ---------------
@@ -333,10 +387,13 @@ mod tests {
             transaction_type::TransactionType,
         },
         execution::CallType,
-        services::api::contract_classes::deprecated_contract_class::ContractClass,
+        services::api::contract_classes::{
+            compiled_class::CompiledClass, deprecated_contract_class::ContractClass,
+        },
         state::cached_state::CachedState,
         state::in_memory_state_reader::InMemoryStateReader,
-        utils::{felt_to_hash, Address},
+        state::state_cache::StorageEntry,
+        utils::{felt_to_hash, Address, CompiledClassHash},
     };
 
     use super::Declare;
@@ -438,6 +495,10 @@ mod tests {
             actual_fee: 0,
             actual_resources,
             tx_type: Some(TransactionType::Declare),
+            sender_nonce_before: None,
+            sender_nonce_after: None,
+            total_gas_consumed: 0,
+            syscall_counter: HashMap::new(),
         };
 
         // ---------------------
@@ -624,7 +685,7 @@ mod tests {
     }
 
     #[test]
-    fn execute_class_already_declared_should_redeclare() {
+    fn execute_class_already_declared_should_fail() {
         // accounts contract class must be stored before running declaration of fibonacci
         let path = PathBuf::from("starknet_programs/account_without_validation.json");
         let contract_class = ContractClass::from_path(path).unwrap();
@@ -690,13 +751,134 @@ mod tests {
             .execute(&mut state, &BlockContext::default())
             .unwrap();
 
-        assert!(state.get_contract_class(&class_hash).is_ok());
+        assert!(state
+            .get_contract_class(&internal_declare.class_hash)
+            .is_ok());
+
+        assert_matches!(
+            second_internal_declare.execute(&mut state, &BlockContext::default()),
+            Err(TransactionError::ClassAlreadyDeclared(hash)) if hash == internal_declare.class_hash
+        );
+
+        // the rejected declaration doesn't consume the nonce
+        assert_eq!(
+            state.get_nonce_at(&Address(Felt252::one())).unwrap(),
+            Felt252::one()
+        );
+    }
+
+    #[test]
+    fn execute_class_already_declared_version_zero_is_noop() {
+        let fib_contract_class =
+            ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+
+        let mut state = CachedState::new(
+            Arc::new(InMemoryStateReader::default()),
+            Some(HashMap::new()),
+            None,
+        );
+
+        let internal_declare = Declare::new(
+            fib_contract_class,
+            StarknetChainId::TestNet.to_felt(),
+            Address(Felt252::one()),
+            0,
+            0.into(),
+            Vec::new(),
+            Felt252::zero(),
+        )
+        .unwrap();
 
-        second_internal_declare
+        // Version 0 declarations don't validate nor use nonces, so the same one can be repeated
+        internal_declare
+            .execute(&mut state, &BlockContext::default())
+            .unwrap();
+        let second_exec_info = internal_declare
             .execute(&mut state, &BlockContext::default())
             .unwrap();
 
-        assert!(state.get_contract_class(&class_hash).is_ok());
+        assert!(second_exec_info.validate_info.is_none());
+        assert!(state
+            .get_contract_class(&internal_declare.class_hash)
+            .is_ok());
+    }
+
+    /// Reader that fails every class lookup, either because the class is stored but can't be
+    /// deserialized or, like the RPC reader, with a custom error for a missing class.
+    struct FailingClassStateReader {
+        malformed: bool,
+    }
+
+    impl StateReader for FailingClassStateReader {
+        fn get_contract_class(&self, class_hash: &ClassHash) -> Result<CompiledClass, StateError> {
+            if self.malformed {
+                Err(StateError::MalformedContractClass(
+                    *class_hash,
+                    "invalid gzip header".to_string(),
+                ))
+            } else {
+                Err(StateError::CustomError("Class hash not found".to_string()))
+            }
+        }
+
+        fn get_class_hash_at(&self, contract_address: &Address) -> Result<ClassHash, StateError> {
+            Err(StateError::NoneContractState(contract_address.clone()))
+        }
+
+        fn get_nonce_at(&self, _contract_address: &Address) -> Result<Felt252, StateError> {
+            Ok(Felt252::zero())
+        }
+
+        fn get_storage_at(&self, storage_entry: &StorageEntry) -> Result<Felt252, StateError> {
+            Err(StateError::NoneStorage(storage_entry.clone()))
+        }
+
+        fn get_compiled_class_hash(
+            &self,
+            class_hash: &ClassHash,
+        ) -> Result<CompiledClassHash, StateError> {
+            Err(StateError::NoneCompiledHash(*class_hash))
+        }
+    }
+
+    #[test]
+    fn execute_propagates_class_lookup_errors() {
+        let fib_contract_class =
+            ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        let mut state = CachedState::new(
+            Arc::new(FailingClassStateReader { malformed: true }),
+            None,
+            None,
+        );
+
+        let internal_declare = Declare::new(
+            fib_contract_class,
+            StarknetChainId::TestNet.to_felt(),
+            Address(Felt252::one()),
+            0,
+            1.into(),
+            Vec::new(),
+            Felt252::zero(),
+        )
+        .unwrap();
+
+        // A class that can't be read isn't taken as undeclared
+        assert_matches!(
+            internal_declare.execute(&mut state, &BlockContext::default()),
+            Err(TransactionError::State(StateError::MalformedContractClass(hash, _)))
+                if hash == internal_declare.class_hash
+        );
+    }
+
+    #[test]
+    fn missing_class_is_not_declared_with_any_reader_error() {
+        let mut state = CachedState::new(
+            Arc::new(FailingClassStateReader { malformed: false }),
+            None,
+            None,
+        );
+
+        assert_matches!(is_class_declared(&mut state, &[1; 32]), Ok(false));
     }
 
     #[test]
@@ -768,6 +950,45 @@ mod tests {
         )
     }
 
+    #[test]
+    fn execute_validation_exceeding_max_n_steps_should_fail() {
+        let contract_class =
+            ContractClass::from_path("starknet_programs/account_without_validation.json").unwrap();
+        let class_hash = felt_to_hash(&compute_deprecated_class_hash(&contract_class).unwrap());
+
+        let sender_address = Address(1.into());
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(sender_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce_mut()
+            .insert(sender_address.clone(), Felt252::zero());
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            Some(HashMap::from([(class_hash, contract_class)])),
+            None,
+        );
+
+        let internal_declare = Declare::new(
+            ContractClass::from_path("starknet_programs/fibonacci.json").unwrap(),
+            StarknetChainId::TestNet.to_felt(),
+            sender_address,
+            0,
+            1.into(),
+            Vec::new(),
+            Felt252::zero(),
+        )
+        .unwrap();
+
+        // __validate_declare__ takes 12 steps
+        let block_context = BlockContext::default().with_validate_max_n_steps(1);
+        assert_matches!(
+            internal_declare.execute(&mut state, &block_context),
+            Err(TransactionError::ValidationStepsExceeded(12, 1))
+        );
+    }
+
     #[test]
     fn validate_transaction_should_fail() {
         // Instantiate CachedState
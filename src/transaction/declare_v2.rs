@@ -1,5 +1,5 @@
 use super::fee::charge_fee;
-use super::{verify_version, Transaction};
+use super::{apply_validate_hook, verify_version, Transaction};
 use crate::core::contract_address::{compute_casm_class_hash, compute_sierra_class_hash};
 use crate::definitions::constants::QUERY_VERSION_BASE;
 use crate::execution::execution_entry_point::ExecutionResult;
@@ -19,7 +19,10 @@ use crate::{
     },
     state::state_api::{State, StateReader},
     state::ExecutionResourcesManager,
-    transaction::{error::TransactionError, invoke_function::verify_no_calls_to_other_contracts},
+    transaction::{
+        error::TransactionError,
+        invoke_function::{verify_no_calls_to_other_contracts, verify_validation_n_steps},
+    },
     utils::{calculate_tx_resources, Address},
 };
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
@@ -301,6 +304,8 @@ impl DeclareV2 {
         block_context: &BlockContext,
     ) -> Result<TransactionExecutionInfo, TransactionError> {
         verify_version(&self.version, self.max_fee, &self.nonce, &self.signature)?;
+        // Reject a mismatching compiled class hash before validating or charging any fee.
+        let casm_class = self.compile_casm_class()?;
 
         let initial_gas = INITIAL_GAS_COST;
 
@@ -318,6 +323,7 @@ impl DeclareV2 {
             (info, gas)
         };
 
+        let syscall_counter = resources_manager.syscall_invocations();
         let storage_changes = state.count_actual_storage_changes();
         let actual_resources = calculate_tx_resources(
             resources_manager,
@@ -338,7 +344,7 @@ impl DeclareV2 {
             &mut tx_execution_context,
             self.skip_fee_transfer,
         )?;
-        self.compile_and_store_casm_class(state)?;
+        self.store_casm_class(state, casm_class)?;
 
         let mut tx_exec_info = TransactionExecutionInfo::new_without_fee_info(
             execution_result.call_info,
@@ -346,7 +352,8 @@ impl DeclareV2 {
             None,
             actual_resources,
             Some(self.tx_type),
-        );
+        )
+        .with_syscall_counter(syscall_counter);
         tx_exec_info.set_fee_info(actual_fee, fee_transfer_info);
 
         Ok(tx_exec_info)
@@ -356,6 +363,13 @@ impl DeclareV2 {
         &self,
         state: &mut S,
     ) -> Result<(), TransactionError> {
+        let casm_class = self.compile_casm_class()?;
+        self.store_casm_class(state, casm_class)
+    }
+
+    /// Returns the casm class being declared, compiling the sierra class if none was given, after
+    /// checking that its hash matches the declared `compiled_class_hash`.
+    pub(crate) fn compile_casm_class(&self) -> Result<CasmContractClass, TransactionError> {
         let casm_class = match &self.casm_class {
             None => {
                 CasmContractClass::from_contract_class(self.sierra_contract_class.clone(), true)
@@ -371,6 +385,15 @@ impl DeclareV2 {
                 self.compiled_class_hash.to_string(),
             ));
         }
+
+        Ok(casm_class)
+    }
+
+    fn store_casm_class<S: State + StateReader>(
+        &self,
+        state: &mut S,
+        casm_class: CasmContractClass,
+    ) -> Result<(), TransactionError> {
         state.set_compiled_class_hash(&self.sierra_class_hash, &self.compiled_class_hash)?;
         state.set_compiled_class(&self.compiled_class_hash, casm_class)?;
 
@@ -384,6 +407,10 @@ impl DeclareV2 {
         resources_manager: &mut ExecutionResourcesManager,
         block_context: &BlockContext,
     ) -> Result<(ExecutionResult, u128), TransactionError> {
+        if apply_validate_hook(block_context)? {
+            return Ok((ExecutionResult::default(), remaining_gas));
+        }
+
         let calldata = [self.compiled_class_hash.clone()].to_vec();
 
         let entry_point = ExecutionEntryPoint {
@@ -415,9 +442,10 @@ impl DeclareV2 {
             )?
         };
 
-        if execution_result.call_info.is_some() {
+        if let Some(call_info) = &execution_result.call_info {
             verify_no_calls_to_other_contracts(&execution_result.call_info)?;
-            remaining_gas -= execution_result.call_info.clone().unwrap().gas_consumed;
+            verify_validation_n_steps(call_info, block_context.validate_max_n_steps)?;
+            remaining_gas -= call_info.gas_consumed;
         }
 
         Ok((execution_result, remaining_gas))
@@ -456,9 +484,10 @@ mod tests {
 
     use super::DeclareV2;
     use crate::core::contract_address::{compute_casm_class_hash, compute_sierra_class_hash};
-    use crate::definitions::constants::QUERY_VERSION_BASE;
+    use crate::definitions::{block_context::BlockContext, constants::QUERY_VERSION_BASE};
     use crate::services::api::contract_classes::compiled_class::CompiledClass;
     use crate::state::state_api::StateReader;
+    use crate::transaction::error::TransactionError;
     use crate::{
         state::cached_state::CachedState, state::in_memory_state_reader::InMemoryStateReader,
         utils::Address,
@@ -605,6 +634,59 @@ mod tests {
         assert_eq!(expected_casm_class, casm_class);
     }
 
+    #[test]
+    fn declared_casm_class_is_cached_under_its_class_hash() {
+        let version;
+        let path;
+        #[cfg(not(feature = "cairo_1_tests"))]
+        {
+            version = Felt252::from(2);
+            path = PathBuf::from("starknet_programs/cairo2/fibonacci.sierra");
+        }
+
+        #[cfg(feature = "cairo_1_tests")]
+        {
+            version = Felt252::from(1);
+            path = PathBuf::from("starknet_programs/cairo1/fibonacci.sierra");
+        }
+
+        let file = File::open(path).unwrap();
+        let reader = BufReader::new(file);
+        let sierra_contract_class: cairo_lang_starknet::contract_class::ContractClass =
+            serde_json::from_reader(reader).unwrap();
+        let casm_class =
+            CasmContractClass::from_contract_class(sierra_contract_class.clone(), true).unwrap();
+        let casm_class_hash = compute_casm_class_hash(&casm_class).unwrap();
+
+        let internal_declare = DeclareV2::new_with_tx_hash(
+            &sierra_contract_class,
+            Some(casm_class),
+            casm_class_hash,
+            Address(1.into()),
+            0,
+            version,
+            [1.into()].to_vec(),
+            Felt252::zero(),
+            Felt252::one(),
+        )
+        .unwrap();
+
+        // The class only exists in the cache, under its compiled class hash
+        let mut state = CachedState::new(
+            Arc::new(InMemoryStateReader::default()),
+            None,
+            Some(HashMap::new()),
+        );
+        internal_declare
+            .compile_and_store_casm_class(&mut state)
+            .unwrap();
+
+        let class_hash = internal_declare.sierra_class_hash.to_be_bytes();
+        assert!(state.is_contract_class_cached(&class_hash));
+        // Prefetching it doesn't go to the state reader, which doesn't have it
+        state.prefetch(&[], &[class_hash], &[]).unwrap();
+    }
+
     #[test]
     fn create_declare_v2_test_with_version_query() {
         // read file to create sierra contract class
@@ -804,4 +886,76 @@ mod tests {
             expected_err
         );
     }
+
+    #[test]
+    fn execute_declare_v2_verifies_compiled_class_hash() {
+        let version;
+        let path;
+        #[cfg(not(feature = "cairo_1_tests"))]
+        {
+            version = Felt252::from(2);
+            path = PathBuf::from("starknet_programs/cairo2/fibonacci.sierra");
+        }
+
+        #[cfg(feature = "cairo_1_tests")]
+        {
+            version = Felt252::from(1);
+            path = PathBuf::from("starknet_programs/cairo1/fibonacci.sierra");
+        }
+
+        let file = File::open(path).unwrap();
+        let reader = BufReader::new(file);
+        let sierra_contract_class: cairo_lang_starknet::contract_class::ContractClass =
+            serde_json::from_reader(reader).unwrap();
+        let casm_class =
+            CasmContractClass::from_contract_class(sierra_contract_class.clone(), true).unwrap();
+        let casm_class_hash = compute_casm_class_hash(&casm_class).unwrap();
+
+        let declare = |compiled_class_hash: Felt252| {
+            let mut declare = DeclareV2::new_with_tx_hash(
+                &sierra_contract_class,
+                Some(casm_class.clone()),
+                compiled_class_hash,
+                Address(1.into()),
+                0,
+                version.clone(),
+                [1.into()].to_vec(),
+                Felt252::zero(),
+                Felt252::one(),
+            )
+            .unwrap();
+            declare.skip_validate = true;
+            declare
+        };
+
+        // A mismatching compiled class hash is rejected before touching the state
+        let mut state = CachedState::new(
+            Arc::new(InMemoryStateReader::default()),
+            None,
+            Some(HashMap::new()),
+        );
+        assert_matches!(
+            declare(Felt252::from(5)).execute(&mut state, &BlockContext::default()),
+            Err(TransactionError::InvalidCompiledClassHash(expected, received))
+                if expected == casm_class_hash.to_string() && received == "5"
+        );
+        assert!(state.cache().class_hash_to_compiled_class_hash.is_empty());
+
+        let internal_declare = declare(casm_class_hash.clone());
+        internal_declare
+            .execute(&mut state, &BlockContext::default())
+            .unwrap();
+        assert_eq!(
+            state
+                .get_contract_class(&casm_class_hash.to_be_bytes())
+                .unwrap(),
+            CompiledClass::Casm(Arc::new(casm_class))
+        );
+        assert_eq!(
+            state
+                .get_compiled_class_hash(&internal_declare.sierra_class_hash.to_be_bytes())
+                .unwrap(),
+            casm_class_hash.to_be_bytes()
+        );
+    }
 }
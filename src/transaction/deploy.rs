@@ -31,7 +31,7 @@ use crate::{
 use cairo_vm::felt::Felt252;
 use num_traits::Zero;
 
-use super::Transaction;
+use super::{check_constructor_calldata, Transaction};
 
 /// Represents a Deploy Transaction in the starknet network
 #[derive(Debug, Clone)]
@@ -61,7 +61,29 @@ impl Deploy {
             SyscallHandlerError::HashError(HashError::FailedToComputeHash(e.to_string()))
         })?;
 
-        let contract_hash: ClassHash = felt_to_hash(&class_hash);
+        Self::new_with_class_hash(
+            contract_address_salt,
+            contract_class,
+            felt_to_hash(&class_hash),
+            constructor_calldata,
+            chain_id,
+            version,
+        )
+    }
+
+    /// Creates a Deploy transaction for a class whose hash was already computed, skipping the
+    /// class hashing done by [`Deploy::new`]. The caller is responsible for `contract_hash`
+    /// being the hash of `contract_class`; the contract address and transaction hash are
+    /// still derived from it and the constructor calldata.
+    pub fn new_with_class_hash(
+        contract_address_salt: Felt252,
+        contract_class: ContractClass,
+        contract_hash: ClassHash,
+        constructor_calldata: Vec<Felt252>,
+        chain_id: Felt252,
+        version: Felt252,
+    ) -> Result<Self, SyscallHandlerError> {
+        let class_hash = Felt252::from_bytes_be(&contract_hash);
         let contract_address = Address(calculate_contract_address(
             &contract_address_salt,
             &class_hash,
@@ -151,6 +173,12 @@ impl Deploy {
         state: &mut CachedState<S>,
         block_context: &BlockContext,
     ) -> Result<TransactionExecutionInfo, TransactionError> {
+        check_constructor_calldata(
+            block_context,
+            &self.contract_class,
+            &self.constructor_calldata,
+        )?;
+
         match self.contract_class.clone() {
             CompiledClass::Casm(contract_class) => {
                 state.set_compiled_class(
@@ -247,6 +275,7 @@ impl Deploy {
             call_info,
             revert_error,
             n_reverted_steps,
+            ..
         } = call.execute(
             state,
             block_context,
@@ -257,6 +286,7 @@ impl Deploy {
             false,
         )?;
 
+        let syscall_counter = resources_manager.syscall_invocations();
         let changes = state.count_actual_storage_changes();
         let actual_resources = calculate_tx_resources(
             resources_manager,
@@ -273,7 +303,8 @@ impl Deploy {
             revert_error,
             actual_resources,
             Some(self.tx_type),
-        ))
+        )
+        .with_syscall_counter(syscall_counter))
     }
 
     /// Calculates actual fee used by the transaction using the execution
@@ -399,6 +430,39 @@ mod tests {
         assert_matches!(result.unwrap_err(), TransactionError::CairoRunner(..))
     }
 
+    #[test]
+    fn invoke_constructor_with_wrong_calldata_length_should_fail() {
+        let state_reader = Arc::new(InMemoryStateReader::default());
+        let mut state = CachedState::new(state_reader, Some(Default::default()), None);
+
+        let contract_class =
+            ContractClass::from_path("starknet_programs/constructor.json").unwrap();
+        assert_eq!(contract_class.constructor_calldata_len(), Some(1));
+
+        let block_context = BlockContext::default().with_validate_constructor_calldata(true);
+        for (constructor_calldata, expected) in
+            [(Vec::new(), (1, 0)), (vec![10.into(), 11.into()], (1, 2))]
+        {
+            let internal_deploy = Deploy::new(
+                0.into(),
+                contract_class.clone(),
+                constructor_calldata,
+                0.into(),
+                0.into(),
+            )
+            .unwrap();
+
+            let result = internal_deploy.execute(&mut state, &block_context);
+            assert_matches!(
+                result.unwrap_err(),
+                TransactionError::ConstructorArgumentMismatch(n_params, n_args)
+                    if (n_params, n_args) == expected
+            );
+        }
+        // The mismatch is caught before deploying the contract
+        assert!(state.cache().class_hash_writes.is_empty());
+    }
+
     #[test]
     fn deploy_contract_without_constructor_should_fail() {
         // Instantiate CachedState
@@ -462,4 +526,85 @@ mod tests {
             SyscallHandlerError::HashError(HashError::FailedToComputeHash(_))
         )
     }
+
+    #[test]
+    fn deploy_many_instances_with_precomputed_class_hash() {
+        let state_reader = Arc::new(InMemoryStateReader::default());
+        let mut state = CachedState::new(state_reader, Some(Default::default()), None);
+
+        let contract_class =
+            ContractClass::from_path("starknet_programs/constructor.json").unwrap();
+        // Hash the class once and reuse it for every deploy
+        let class_hash: Felt252 = compute_deprecated_class_hash(&contract_class).unwrap();
+        let class_hash_bytes = felt_to_hash(&class_hash);
+
+        let block_context = BlockContext::default();
+        let storage_key = calculate_sn_keccak("owner".as_bytes());
+
+        for salt in 0..10 {
+            let calldata = vec![Felt252::from(salt * 10)];
+            let internal_deploy = Deploy::new_with_class_hash(
+                salt.into(),
+                contract_class.clone(),
+                class_hash_bytes,
+                calldata.clone(),
+                0.into(),
+                0.into(),
+            )
+            .unwrap();
+
+            let expected_address = Address(
+                calculate_contract_address(
+                    &salt.into(),
+                    &class_hash,
+                    &calldata,
+                    Address(Felt252::zero()),
+                )
+                .unwrap(),
+            );
+            assert_eq!(internal_deploy.contract_address, expected_address);
+            assert_eq!(internal_deploy.class_hash(), class_hash_bytes);
+            assert_eq!(
+                internal_deploy.hash_value,
+                calculate_deploy_transaction_hash(0.into(), &expected_address, &calldata, 0.into())
+                    .unwrap()
+            );
+
+            internal_deploy.apply(&mut state, &block_context).unwrap();
+
+            assert_eq!(
+                state.get_class_hash_at(&expected_address).unwrap(),
+                class_hash_bytes
+            );
+            assert_eq!(
+                state
+                    .get_storage_at(&(expected_address, storage_key))
+                    .unwrap(),
+                Felt252::from(salt * 10)
+            );
+        }
+
+        // The result matches the one of hashing the class on construction
+        let internal_deploy = Deploy::new(
+            9.into(),
+            contract_class,
+            vec![90.into()],
+            0.into(),
+            0.into(),
+        )
+        .unwrap();
+        assert_eq!(internal_deploy.class_hash(), class_hash_bytes);
+        assert_eq!(
+            internal_deploy.contract_address,
+            Address(
+                calculate_contract_address(
+                    &9.into(),
+                    &class_hash,
+                    &[90.into()],
+                    Address(Felt252::zero())
+                )
+                .unwrap()
+            )
+        );
+    }
 }
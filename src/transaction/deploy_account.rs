@@ -1,5 +1,8 @@
 use super::fee::charge_fee;
-use super::{invoke_function::verify_no_calls_to_other_contracts, Transaction};
+use super::{
+    apply_validate_hook, check_constructor_calldata,
+    invoke_function::verify_no_calls_to_other_contracts, Transaction,
+};
 use crate::definitions::constants::QUERY_VERSION_BASE;
 use crate::execution::execution_entry_point::ExecutionResult;
 use crate::services::api::contract_classes::deprecated_contract_class::EntryPointType;
@@ -209,6 +212,7 @@ impl DeployAccount {
             self.run_validate_entrypoint(state, &mut resources_manager, block_context)?
         };
 
+        let syscall_counter = resources_manager.syscall_invocations();
         let actual_resources = calculate_tx_resources(
             resources_manager,
             &[Some(constructor_call_info.clone()), validate_info.clone()],
@@ -225,7 +229,8 @@ impl DeployAccount {
             None,
             actual_resources,
             Some(TransactionType::DeployAccount),
-        ))
+        )
+        .with_syscall_counter(syscall_counter))
     }
 
     pub fn handle_constructor<S: StateReader>(
@@ -235,6 +240,7 @@ impl DeployAccount {
         block_context: &BlockContext,
         resources_manager: &mut ExecutionResourcesManager,
     ) -> Result<CallInfo, TransactionError> {
+        check_constructor_calldata(block_context, &contract_class, &self.constructor_calldata)?;
         if self.constructor_entry_points_empty(contract_class)? {
             if !self.constructor_calldata.is_empty() {
                 return Err(TransactionError::EmptyConstructorCalldata);
@@ -324,6 +330,9 @@ impl DeployAccount {
         if self.version.is_zero() || self.version == *QUERY_VERSION_BASE {
             return Ok(None);
         }
+        if apply_validate_hook(block_context)? {
+            return Ok(None);
+        }
 
         let call = ExecutionEntryPoint::new(
             self.contract_address.clone(),
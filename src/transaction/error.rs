@@ -6,9 +6,10 @@ use crate::{
     definitions::transaction_type::TransactionType,
     execution::os_usage::OsResources,
     syscalls::syscall_handler_errors::SyscallHandlerError,
-    utils::ClassHash,
+    utils::{Address, ClassHash},
 };
 use cairo_vm::{
+    felt::Felt252,
     types::{
         errors::{math_errors::MathError, program_errors::ProgramError},
         relocatable::Relocatable,
@@ -38,8 +39,18 @@ pub enum TransactionError {
     InvokeFunctionZeroHasNonce,
     #[error("Invalid transaction nonce. Expected: {0} got {1}")]
     InvalidTransactionNonce(String, String),
+    #[error("Nonce would overflow the field modulus")]
+    NonceOverflow,
+    #[error("Transaction execution exceeded its wall-clock timeout")]
+    Timeout,
     #[error("Actual fee exceeds max fee. Actual: {0}, Max: {1}")]
     ActualFeeExceedsMaxFee(u128, u128),
+    #[error("Constructor expects {0} calldata felts, got {1}")]
+    ConstructorArgumentMismatch(usize, usize),
+    #[error("Syscall {syscall} ran out of gas")]
+    OutOfGas { syscall: String },
+    #[error("Max fee is below the minimum fee of the transaction. Minimum: {0}, Max: {1}")]
+    MaxFeeTooLow(u128, u128),
     #[error("Fee transfer failure: {0}")]
     FeeTransferError(Box<TransactionError>),
     #[error("{0}")]
@@ -80,6 +91,8 @@ pub enum TransactionError {
     NonUniqueEntryPoint,
     #[error("Requested entry point was not found")]
     EntryPointNotFound,
+    #[error("Contract at address {0:?} is not an account: missing __validate__ or __execute__ entry point")]
+    NotAnAccount(Address),
     #[error("Ptr result diverges after calculating final stacks")]
     OsContextPtrNotEqual,
     #[error("Empty OS context")]
@@ -98,10 +111,14 @@ pub enum TransactionError {
     InvalidEntryPoints,
     #[error("Expected a Felt value got a Relocatable")]
     NotAFelt,
-    #[error("Out of bounds write to a read-only segment.")]
-    OutOfBound,
+    #[error("Out of bounds write to read-only segment {0}; expected size {1}, used size {2}")]
+    OutOfBound(isize, Felt252, usize),
     #[error("Call to another contract has been done")]
     InvalidContractCall,
+    #[error("Validation took {0} steps, exceeding the validation limit of {1} steps")]
+    ValidationStepsExceeded(usize, u64),
+    #[error("Validation rejected by the block context's validate hook")]
+    ValidationRejected,
     #[error("The sender address field in Declare transactions of version 0")]
     InvalidSenderAddress,
     #[error(transparent)]
@@ -1,12 +1,15 @@
 use super::error::TransactionError;
-use crate::definitions::constants::{FEE_FACTOR, QUERY_VERSION_BASE};
+use crate::definitions::constants::{
+    FEE_FACTOR, FEE_TRANSFER_N_STORAGE_CHANGES_TO_CHARGE, QUERY_VERSION_BASE,
+};
 use crate::execution::execution_entry_point::ExecutionResult;
+use crate::execution::gas_usage::calculate_tx_gas_usage;
 use crate::execution::CallType;
 use crate::services::api::contract_classes::deprecated_contract_class::EntryPointType;
 use crate::state::cached_state::CachedState;
 use crate::{
     definitions::{
-        block_context::BlockContext,
+        block_context::{BlockContext, FeeToken},
         constants::{INITIAL_GAS_COST, TRANSFER_ENTRY_POINT_SELECTOR},
     },
     execution::{
@@ -38,7 +41,10 @@ pub(crate) fn execute_fee_transfer<S: StateReader>(
         ));
     }
 
-    let fee_token_address = block_context.starknet_os_config.fee_token_address.clone();
+    let fee_token_address = block_context
+        .starknet_os_config
+        .fee_token_address_for(tx_execution_context.fee_token)
+        .clone();
 
     let calldata = [
         block_context.block_info.sequencer_address.0.clone(),
@@ -95,6 +101,20 @@ pub fn calculate_tx_fee(
     Ok(total_l1_gas_usage.ceil() as u128 * gas_price)
 }
 
+/// Calculates a lower bound for the fee of any transaction paying fees in `fee_token`: the L1
+/// gas of the fee transfer storage update alone, without any execution resources.
+pub(crate) fn calculate_min_tx_fee(block_context: &BlockContext, fee_token: FeeToken) -> u128 {
+    let l1_gas_usage = calculate_tx_gas_usage(
+        Vec::new(),
+        0,
+        FEE_TRANSFER_N_STORAGE_CHANGES_TO_CHARGE,
+        None,
+        0,
+    );
+
+    l1_gas_usage as u128 * block_context.starknet_os_config.gas_price_for(fee_token)
+}
+
 // ----------------------------------------------------------------------------------------
 /// Calculates the L1 gas consumed when submitting the underlying Cairo program to SHARP.
 /// I.e., returns the heaviest Cairo resource weight (in terms of L1 gas), as the size of
@@ -151,7 +171,9 @@ pub fn charge_fee<S: StateReader>(
 
     let actual_fee = calculate_tx_fee(
         resources,
-        block_context.starknet_os_config.gas_price,
+        block_context
+            .starknet_os_config
+            .gas_price_for(tx_execution_context.fee_token),
         block_context,
     )?;
 
@@ -192,7 +214,10 @@ mod tests {
         definitions::block_context::BlockContext,
         execution::TransactionExecutionContext,
         state::{cached_state::CachedState, in_memory_state_reader::InMemoryStateReader},
-        transaction::{error::TransactionError, fee::charge_fee},
+        transaction::{
+            error::TransactionError,
+            fee::{calculate_tx_fee, charge_fee},
+        },
     };
 
     #[test]
@@ -249,4 +274,30 @@ mod tests {
 
         assert_matches!(result, TransactionError::ActualFeeExceedsMaxFee(_, _));
     }
+
+    #[test]
+    fn test_step_gas_cost_scales_step_fee() {
+        let block_context = BlockContext::default();
+        let gas_price = 1;
+        let l1_gas_usage = 200;
+        let resources = HashMap::from([
+            ("l1_gas_usage".to_string(), l1_gas_usage),
+            ("n_steps".to_string(), 100_000_usize),
+        ]);
+
+        let fee = calculate_tx_fee(&resources, gas_price, &block_context).unwrap();
+
+        let step_gas_cost = block_context.step_gas_cost();
+        let block_context = block_context.with_step_gas_cost(2.0 * step_gas_cost);
+        assert_eq!(
+            block_context.cairo_resource_fee_weights()["n_steps"],
+            2.0 * step_gas_cost
+        );
+        let doubled_fee = calculate_tx_fee(&resources, gas_price, &block_context).unwrap();
+
+        let step_fee = fee - l1_gas_usage as u128;
+        let doubled_step_fee = doubled_fee - l1_gas_usage as u128;
+        assert_ne!(step_fee, 0);
+        assert!(doubled_step_fee.abs_diff(2 * step_fee) <= 1);
+    }
 }
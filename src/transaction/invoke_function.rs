@@ -1,7 +1,7 @@
 use crate::{
-    core::transaction_hash::{calculate_transaction_hash_common, TransactionHashPrefix},
+    core::transaction_hash::calculate_invoke_transaction_hash,
     definitions::{
-        block_context::BlockContext,
+        block_context::{BlockContext, FeeToken},
         constants::{
             EXECUTE_ENTRY_POINT_SELECTOR, QUERY_VERSION_BASE, VALIDATE_ENTRY_POINT_SELECTOR,
         },
@@ -14,15 +14,29 @@ use crate::{
     state::state_api::{State, StateReader},
     state::{cached_state::CachedState, ExecutionResourcesManager},
     transaction::error::TransactionError,
-    utils::{calculate_tx_resources, Address},
+    utils::{calculate_tx_resources, split_query_version, Address},
 };
 
-use crate::services::api::contract_classes::deprecated_contract_class::EntryPointType;
+use crate::services::api::contract_classes::{
+    compiled_class::CompiledClass, deprecated_contract_class::EntryPointType,
+};
 use cairo_vm::felt::Felt252;
 use getset::Getters;
-use num_traits::Zero;
+use num_traits::{One, Zero};
+
+use super::{
+    apply_validate_hook,
+    fee::{calculate_min_tx_fee, charge_fee},
+    Transaction,
+};
 
-use super::{fee::charge_fee, Transaction};
+/// A call performed by the `__execute__` entry point of an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub to: Address,
+    pub selector: Felt252,
+    pub calldata: Vec<Felt252>,
+}
 
 /// Represents an InvokeFunction transaction in the starknet network.
 #[derive(Debug, Getters, Clone)]
@@ -32,6 +46,7 @@ pub struct InvokeFunction {
     entry_point_selector: Felt252,
     #[allow(dead_code)]
     entry_point_type: EntryPointType,
+    #[getset(get = "pub")]
     calldata: Vec<Felt252>,
     tx_type: TransactionType,
     version: Felt252,
@@ -46,6 +61,7 @@ pub struct InvokeFunction {
     skip_execute: bool,
     skip_fee_transfer: bool,
     skip_nonce_check: bool,
+    fee_token: FeeToken,
 }
 
 impl InvokeFunction {
@@ -60,20 +76,14 @@ impl InvokeFunction {
         chain_id: Felt252,
         nonce: Option<Felt252>,
     ) -> Result<Self, TransactionError> {
-        let (entry_point_selector_field, additional_data) = preprocess_invoke_function_fields(
-            entry_point_selector.clone(),
-            nonce.clone(),
-            version.clone(),
-        )?;
-        let hash_value = calculate_transaction_hash_common(
-            TransactionHashPrefix::Invoke,
+        let hash_value = calculate_invoke_transaction_hash(
             version.clone(),
             &contract_address,
-            entry_point_selector_field,
+            entry_point_selector.clone(),
             &calldata,
             max_fee,
+            nonce.clone(),
             chain_id,
-            &additional_data,
         )?;
 
         InvokeFunction::new_with_tx_hash(
@@ -88,6 +98,31 @@ impl InvokeFunction {
         )
     }
 
+    /// Creates a transaction invoking the `__execute__` entry point of `account` with the given
+    /// calls, encoded with the standard account calldata layout: the call array, one
+    /// `(to, selector, data_offset, data_len)` entry per call, followed by the calldata of all
+    /// the calls.
+    pub fn new_account_call(
+        account: Address,
+        calls: Vec<Call>,
+        max_fee: u128,
+        version: Felt252,
+        signature: Vec<Felt252>,
+        chain_id: Felt252,
+        nonce: Option<Felt252>,
+    ) -> Result<Self, TransactionError> {
+        InvokeFunction::new(
+            account,
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            max_fee,
+            version,
+            encode_account_calldata(&calls),
+            signature,
+            chain_id,
+            nonce,
+        )
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn new_with_tx_hash(
         contract_address: Address,
@@ -117,6 +152,7 @@ impl InvokeFunction {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         })
     }
 
@@ -124,7 +160,7 @@ impl InvokeFunction {
         &self,
         n_steps: u64,
     ) -> Result<TransactionExecutionContext, TransactionError> {
-        Ok(TransactionExecutionContext::new(
+        let mut tx_execution_context = TransactionExecutionContext::new(
             self.contract_address.clone(),
             self.hash_value.clone(),
             self.signature.clone(),
@@ -136,7 +172,9 @@ impl InvokeFunction {
             },
             n_steps,
             self.version.clone(),
-        ))
+        );
+        tx_execution_context.fee_token = self.fee_token;
+        Ok(tx_execution_context)
     }
 
     /// Execute the validation entrypoint of the contract and returns the call info.
@@ -149,6 +187,34 @@ impl InvokeFunction {
         state: &mut CachedState<S>,
         resources_manager: &mut ExecutionResourcesManager,
         block_context: &BlockContext,
+    ) -> Result<Option<CallInfo>, TransactionError> {
+        if self.skip_validation {
+            return Ok(None);
+        }
+        self.validate(state, resources_manager, block_context)
+    }
+
+    /// Runs only the `__validate__` entry point of the transaction, without executing it nor
+    /// charging the fee, e.g. to check whether a mempool would admit it. Validation runs even
+    /// if the transaction skips it when executed, and the nonce is neither checked nor bumped.
+    /// Returns `None` for transactions without a validation step, such as version 0 ones.
+    pub fn validate_only<S: StateReader>(
+        &self,
+        state: &mut CachedState<S>,
+        block_context: &BlockContext,
+    ) -> Result<Option<CallInfo>, TransactionError> {
+        self.validate(
+            state,
+            &mut ExecutionResourcesManager::default(),
+            block_context,
+        )
+    }
+
+    fn validate<S: StateReader>(
+        &self,
+        state: &mut CachedState<S>,
+        resources_manager: &mut ExecutionResourcesManager,
+        block_context: &BlockContext,
     ) -> Result<Option<CallInfo>, TransactionError> {
         if self.entry_point_selector != *EXECUTE_ENTRY_POINT_SELECTOR {
             return Ok(None);
@@ -156,10 +222,14 @@ impl InvokeFunction {
         if self.version.is_zero() || self.version == *QUERY_VERSION_BASE {
             return Ok(None);
         }
-        if self.skip_validation {
+        if apply_validate_hook(block_context)? {
             return Ok(None);
         }
 
+        let mut tx_execution_context =
+            self.get_execution_context(block_context.validate_max_n_steps)?;
+        self.verify_account_entry_points(state)?;
+
         let call = ExecutionEntryPoint::new(
             self.contract_address.clone(),
             self.calldata.clone(),
@@ -175,7 +245,7 @@ impl InvokeFunction {
             state,
             block_context,
             resources_manager,
-            &mut self.get_execution_context(block_context.validate_max_n_steps)?,
+            &mut tx_execution_context,
             false,
             block_context.validate_max_n_steps,
             false,
@@ -183,10 +253,53 @@ impl InvokeFunction {
 
         let call_info = verify_no_calls_to_other_contracts(&call_info)
             .map_err(|_| TransactionError::InvalidContractCall)?;
+        verify_validation_n_steps(&call_info, block_context.validate_max_n_steps)?;
 
         Ok(Some(call_info))
     }
 
+    /// Checks that the invoked contract exposes both the `__validate__` and `__execute__`
+    /// external entry points, returning `TransactionError::NotAnAccount` otherwise.
+    /// The check is part of the validation step, so it is skipped along with it when
+    /// `skip_validation` is set.
+    fn verify_account_entry_points<S: StateReader>(
+        &self,
+        state: &mut CachedState<S>,
+    ) -> Result<(), TransactionError> {
+        let class_hash = state.get_class_hash_at(&self.contract_address)?;
+        let selectors = [
+            &*VALIDATE_ENTRY_POINT_SELECTOR,
+            &*EXECUTE_ENTRY_POINT_SELECTOR,
+        ];
+
+        let is_account = match state.get_contract_class(&class_hash)? {
+            CompiledClass::Deprecated(contract_class) => contract_class
+                .entry_points_by_type
+                .get(&EntryPointType::External)
+                .map_or(false, |entry_points| {
+                    selectors.iter().all(|selector| {
+                        entry_points
+                            .iter()
+                            .any(|entry_point| entry_point.selector() == *selector)
+                    })
+                }),
+            CompiledClass::Casm(contract_class) => selectors.iter().all(|selector| {
+                contract_class
+                    .entry_points_by_type
+                    .external
+                    .iter()
+                    .any(|entry_point| entry_point.selector == selector.to_biguint())
+            }),
+        };
+
+        if !is_account {
+            return Err(TransactionError::NotAnAccount(
+                self.contract_address.clone(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Builds the transaction execution context and executes the entry point.
     /// Returns the CallInfo.
     fn run_execute_entrypoint<S: StateReader>(
@@ -237,6 +350,7 @@ impl InvokeFunction {
             call_info,
             revert_error,
             n_reverted_steps,
+            ..
         } = if self.skip_execute {
             ExecutionResult::default()
         } else {
@@ -247,6 +361,7 @@ impl InvokeFunction {
                 remaining_gas,
             )?
         };
+        let syscall_counter = resources_manager.syscall_invocations();
         let changes = state.count_actual_storage_changes();
         let actual_resources = calculate_tx_resources(
             resources_manager,
@@ -262,7 +377,8 @@ impl InvokeFunction {
             revert_error,
             actual_resources,
             Some(self.tx_type),
-        );
+        )
+        .with_syscall_counter(syscall_counter);
         Ok(transaction_execution_info)
     }
 
@@ -278,6 +394,13 @@ impl InvokeFunction {
         block_context: &BlockContext,
         remaining_gas: u128,
     ) -> Result<TransactionExecutionInfo, TransactionError> {
+        // Reject a max fee that can't cover even the cheapest transaction before running it.
+        if !self.max_fee.is_zero() {
+            let min_fee = calculate_min_tx_fee(block_context, self.fee_token);
+            if min_fee > self.max_fee {
+                return Err(TransactionError::MaxFeeTooLow(min_fee, self.max_fee));
+            }
+        }
         if !self.skip_nonce_check {
             self.handle_nonce(state)?;
         }
@@ -299,8 +422,12 @@ impl InvokeFunction {
         Ok(tx_exec_info)
     }
 
+    /// Checks the transaction nonce against the account nonce and bumps the latter.
+    /// The nonce is updated before running the transaction so that the change is accounted
+    /// for in the transaction's state diff. Query transactions never modify the nonce.
     fn handle_nonce<S: State + StateReader>(&self, state: &mut S) -> Result<(), TransactionError> {
-        if self.version.is_zero() || self.version == *QUERY_VERSION_BASE {
+        let (base_version, is_query) = split_query_version(&self.version);
+        if base_version.is_zero() || is_query {
             return Ok(());
         }
 
@@ -319,6 +446,11 @@ impl InvokeFunction {
                         nonce.to_string(),
                     ));
                 }
+                // Felt252 arithmetic wraps around the field modulus, so the largest
+                // representable nonce can't be bumped.
+                if (current_nonce + Felt252::one()).is_zero() {
+                    return Err(TransactionError::NonceOverflow);
+                }
                 state.increment_nonce(contract_address)?;
                 Ok(())
             }
@@ -327,6 +459,12 @@ impl InvokeFunction {
 
     // Simulation function
 
+    /// Sets the token the transaction pays its fee with, ETH by default.
+    pub fn with_fee_token(mut self, fee_token: FeeToken) -> Self {
+        self.fee_token = fee_token;
+        self
+    }
+
     pub fn create_for_simulation(
         &self,
         skip_validation: bool,
@@ -369,43 +507,54 @@ pub fn verify_no_calls_to_other_contracts(
     Ok(call_info)
 }
 
-// Performs validation on fields related to function invocation transaction.
-// InvokeFunction transaction.
-// Deduces and returns fields required for hash calculation of
+/// Encodes `calls` as the calldata of an account's `__execute__` entry point.
+fn encode_account_calldata(calls: &[Call]) -> Vec<Felt252> {
+    let mut call_array = vec![Felt252::from(calls.len())];
+    let mut calldata = Vec::new();
+    for call in calls {
+        call_array.extend([
+            call.to.0.clone(),
+            call.selector.clone(),
+            Felt252::from(calldata.len()),
+            Felt252::from(call.calldata.len()),
+        ]);
+        calldata.extend(call.calldata.iter().cloned());
+    }
 
-pub(crate) fn preprocess_invoke_function_fields(
-    entry_point_selector: Felt252,
-    nonce: Option<Felt252>,
-    version: Felt252,
-) -> Result<(Felt252, Vec<Felt252>), TransactionError> {
-    if version.is_zero() || version == *QUERY_VERSION_BASE {
-        match nonce {
-            Some(_) => Err(TransactionError::InvokeFunctionZeroHasNonce),
-            None => {
-                let additional_data = Vec::new();
-                let entry_point_selector_field = entry_point_selector;
-                Ok((entry_point_selector_field, additional_data))
-            }
-        }
-    } else {
-        match nonce {
-            Some(n) => {
-                let additional_data = vec![n];
-                let entry_point_selector_field = Felt252::zero();
-                Ok((entry_point_selector_field, additional_data))
-            }
-            None => Err(TransactionError::InvokeFunctionNonZeroMissingNonce),
-        }
+    call_array.push(Felt252::from(calldata.len()));
+    call_array.extend(calldata);
+    call_array
+}
+
+/// Checks that a validation call stayed within the `validate_max_n_steps` budget, which is
+/// separate from (and usually lower than) the execution one.
+pub(crate) fn verify_validation_n_steps(
+    call_info: &CallInfo,
+    validate_max_n_steps: u64,
+) -> Result<(), TransactionError> {
+    let n_steps = call_info.execution_resources.n_steps;
+    if n_steps as u64 > validate_max_n_steps {
+        return Err(TransactionError::ValidationStepsExceeded(
+            n_steps,
+            validate_max_n_steps,
+        ));
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        definitions::block_context::{StarknetChainId, ValidateHook},
         services::api::contract_classes::deprecated_contract_class::ContractClass,
-        state::cached_state::CachedState, state::in_memory_state_reader::InMemoryStateReader,
-        utils::calculate_sn_keccak,
+        state::cached_state::CachedState,
+        state::in_memory_state_reader::InMemoryStateReader,
+        testing::{
+            create_account_tx_test_state, ACTUAL_FEE, TEST_ACCOUNT_CONTRACT_ADDRESS,
+            TEST_CONTRACT_ADDRESS, TEST_ERC20_ACCOUNT_BALANCE_KEY, TEST_ERC20_CONTRACT_CLASS_HASH,
+        },
+        utils::{calculate_sn_keccak, felt_to_hash},
     };
     use cairo_lang_starknet::casm_contract_class::CasmContractClass;
     use num_traits::Num;
@@ -433,6 +582,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -480,6 +630,67 @@ mod tests {
         assert_eq!(result.call_info.unwrap().retdata, vec![Felt252::new(144)]);
     }
 
+    /// Applies `increase_balance(amount)` on a fresh state whose `balance` slot holds
+    /// `initial_balance`, returning the charged fee.
+    fn increase_balance_fee(initial_balance: Felt252, amount: Felt252) -> u128 {
+        let contract_address = Address(1111.into());
+        let internal_invoke_function = InvokeFunction {
+            contract_address: contract_address.clone(),
+            entry_point_selector: Felt252::from_bytes_be(&calculate_sn_keccak(b"increase_balance")),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![amount],
+            tx_type: TransactionType::InvokeFunction,
+            version: 0.into(),
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            nonce: Some(0.into()),
+            skip_validation: true,
+            skip_execute: false,
+            skip_fee_transfer: true,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        let mut state_reader = InMemoryStateReader::default();
+        let class_hash = [1; 32];
+        let contract_class =
+            ContractClass::from_path("starknet_programs/increase_balance.json").unwrap();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address.clone(), Felt252::zero());
+        state_reader.address_to_storage_mut().insert(
+            (contract_address, calculate_sn_keccak(b"balance")),
+            initial_balance,
+        );
+
+        let mut state = CachedState::new(Arc::new(state_reader), None, None);
+        state.set_contract_classes(HashMap::new()).unwrap();
+        state
+            .set_contract_class(&class_hash, &contract_class)
+            .unwrap();
+
+        let block_context = BlockContext::default();
+        let result = internal_invoke_function
+            .apply(&mut state, &block_context, 0)
+            .unwrap();
+        crate::transaction::fee::calculate_tx_fee(&result.actual_resources, 1, &block_context)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_clearing_storage_slot_is_charged() {
+        let set_fee = increase_balance_fee(0.into(), 5.into());
+
+        // Writing zero to a slot is published in the state diff like any other write
+        assert_eq!(increase_balance_fee(5.into(), -Felt252::from(5)), set_fee);
+        assert_eq!(increase_balance_fee(5.into(), 7.into()), set_fee);
+    }
+
     #[test]
     fn test_invoke_execute() {
         let internal_invoke_function = InvokeFunction {
@@ -502,6 +713,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -567,6 +779,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -626,6 +839,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -691,6 +905,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -725,6 +940,66 @@ mod tests {
         assert_matches!(expected_error.unwrap_err(), TransactionError::MissingNonce);
     }
 
+    #[test]
+    fn test_invoke_execute_on_non_account_contract() {
+        let mut internal_invoke_function = InvokeFunction {
+            contract_address: Address(0.into()),
+            entry_point_selector: (*EXECUTE_ENTRY_POINT_SELECTOR).clone(),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![1.into(), 1.into(), 10.into()],
+            tx_type: TransactionType::InvokeFunction,
+            version: 1.into(),
+            validate_entry_point_selector: (*VALIDATE_ENTRY_POINT_SELECTOR).clone(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            nonce: Some(0.into()),
+            skip_validation: false,
+            skip_execute: false,
+            skip_fee_transfer: false,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        // Instantiate CachedState
+        let mut state_reader = InMemoryStateReader::default();
+        // Set contract_class
+        let class_hash = [1; 32];
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        // Set contact_state
+        let contract_address = Address(0.into());
+        let nonce = Felt252::zero();
+
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address.clone(), nonce);
+
+        let mut state = CachedState::new(Arc::new(state_reader), None, None);
+
+        // Initialize state.contract_classes
+        state.set_contract_classes(HashMap::new()).unwrap();
+
+        state
+            .set_contract_class(&class_hash, &contract_class)
+            .unwrap();
+
+        // fibonacci.json has neither __validate__ nor __execute__
+        let result = internal_invoke_function.apply(&mut state, &BlockContext::default(), 0);
+        assert_matches!(
+            result,
+            Err(TransactionError::NotAnAccount(address)) if address == contract_address
+        );
+
+        // Skipping validation also skips the account check, so the invoke reaches
+        // the execution step and fails there instead
+        internal_invoke_function.skip_validation = true;
+        let result = internal_invoke_function.apply(&mut state, &BlockContext::default(), 0);
+        assert_matches!(result, Err(TransactionError::EntryPointNotFound));
+    }
+
     #[test]
     // Test fee calculation is done correctly but payment to sequencer fails due
     // to the token contract not being deployed
@@ -766,6 +1041,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         let mut state = CachedState::new(Arc::new(state_reader), None, None);
@@ -784,9 +1060,256 @@ mod tests {
         assert_matches!(result.unwrap_err(), TransactionError::FeeTransferError(_));
     }
 
+    #[test]
+    fn test_execute_invoke_fee_scales_with_gas_price() {
+        let (block_context, state) = create_account_tx_test_state().unwrap();
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let invoke = InvokeFunction::new(
+            TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            1_000_000,
+            1.into(),
+            vec![
+                TEST_CONTRACT_ADDRESS.0.clone(),
+                selector,
+                3.into(),
+                1.into(),
+                1.into(),
+                10.into(),
+            ],
+            vec![],
+            StarknetChainId::TestNet.to_felt(),
+            Some(0.into()),
+        )
+        .unwrap();
+
+        let actual_fee = |block_context: BlockContext| {
+            invoke
+                .execute(&mut state.clone(), &block_context, 0)
+                .unwrap()
+                .actual_fee
+        };
+        let fee = actual_fee(block_context.clone().with_gas_price_wei(1));
+        assert!(fee > 0);
+        assert_eq!(
+            actual_fee(block_context.clone().with_gas_price_wei(3)),
+            3 * fee
+        );
+        // The STRK gas price doesn't apply to fees paid in ETH
+        assert_eq!(
+            actual_fee(block_context.with_gas_price_wei(1).with_gas_price_fri(5)),
+            fee
+        );
+    }
+
+    #[test]
+    fn test_execute_invoke_paying_fee_in_strk() {
+        let (mut block_context, mut state) = create_account_tx_test_state().unwrap();
+        let eth_address = block_context
+            .starknet_os_config()
+            .fee_token_address()
+            .clone();
+        let strk_address = Address(4098.into());
+        *block_context
+            .starknet_os_config_mut()
+            .strk_fee_token_address_mut() = strk_address.clone();
+
+        // Deploy a second ERC20 as the STRK token, with the same balance for the account
+        let account_balance_key = felt_to_hash(&TEST_ERC20_ACCOUNT_BALANCE_KEY);
+        state
+            .deploy_contract(
+                strk_address.clone(),
+                felt_to_hash(&TEST_ERC20_CONTRACT_CLASS_HASH),
+            )
+            .unwrap();
+        state.set_storage_at(
+            &(strk_address.clone(), account_balance_key),
+            ACTUAL_FEE.clone(),
+        );
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let invoke = InvokeFunction::new(
+            TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            1_000_000,
+            1.into(),
+            vec![
+                TEST_CONTRACT_ADDRESS.0.clone(),
+                selector,
+                3.into(),
+                1.into(),
+                1.into(),
+                10.into(),
+            ],
+            vec![],
+            StarknetChainId::TestNet.to_felt(),
+            Some(0.into()),
+        )
+        .unwrap()
+        .with_fee_token(FeeToken::Strk);
+
+        let tx_info = invoke.execute(&mut state, &block_context, 0).unwrap();
+        assert!(tx_info.actual_fee > 0);
+        assert_eq!(
+            tx_info.fee_transfer_info.unwrap().contract_address,
+            strk_address
+        );
+
+        assert_eq!(
+            state
+                .get_storage_at(&(strk_address, account_balance_key))
+                .unwrap(),
+            ACTUAL_FEE.clone() - Felt252::from(tx_info.actual_fee)
+        );
+        assert_eq!(
+            state
+                .get_storage_at(&(eth_address, account_balance_key))
+                .unwrap(),
+            ACTUAL_FEE.clone()
+        );
+    }
+
+    #[test]
+    fn test_execute_invoke_validation_exceeding_max_n_steps_should_fail() {
+        let (mut block_context, mut state) = create_account_tx_test_state().unwrap();
+        // The execution budget is untouched, only validation is capped
+        block_context.validate_max_n_steps = 1;
+
+        let invoke = InvokeFunction::new(
+            TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            0,
+            1.into(),
+            vec![
+                TEST_CONTRACT_ADDRESS.0.clone(),
+                Felt252::from_str_radix(
+                    "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                    16,
+                )
+                .unwrap(),
+                3.into(),
+                1.into(),
+                1.into(),
+                10.into(),
+            ],
+            vec![],
+            StarknetChainId::TestNet.to_felt(),
+            Some(0.into()),
+        )
+        .unwrap();
+
+        assert_matches!(
+            invoke.execute(&mut state, &block_context, 0),
+            Err(TransactionError::ValidationStepsExceeded(n_steps, 1)) if n_steps > 1
+        );
+    }
+
+    #[test]
+    fn test_execute_invoke_with_validate_hook() {
+        let (mut block_context, state) = create_account_tx_test_state().unwrap();
+
+        let invoke = InvokeFunction::new(
+            TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            0,
+            1.into(),
+            vec![
+                TEST_CONTRACT_ADDRESS.0.clone(),
+                Felt252::from_str_radix(
+                    "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                    16,
+                )
+                .unwrap(),
+                3.into(),
+                1.into(),
+                1.into(),
+                10.into(),
+            ],
+            vec![],
+            StarknetChainId::TestNet.to_felt(),
+            Some(0.into()),
+        )
+        .unwrap();
+
+        *block_context.validate_hook_mut() = Some(ValidateHook::ForceReject);
+        assert_matches!(
+            invoke.execute(&mut state.clone(), &block_context, 0),
+            Err(TransactionError::ValidationRejected)
+        );
+
+        *block_context.validate_hook_mut() = Some(ValidateHook::ForceAccept);
+        let tx_info = invoke
+            .execute(&mut state.clone(), &block_context, 0)
+            .unwrap();
+        assert!(tx_info.validate_info.is_none());
+        assert_eq!(tx_info.call_info.unwrap().retdata, vec![144.into()]);
+    }
+
+    #[test]
+    fn test_validate_only_succeeds_when_execution_fails() {
+        let (block_context, state) = create_account_tx_test_state().unwrap();
+
+        // The account validates any call, but the called entry point doesn't exist
+        let mut invoke = InvokeFunction::new(
+            TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            0,
+            1.into(),
+            vec![
+                TEST_CONTRACT_ADDRESS.0.clone(),
+                Felt252::from_bytes_be(&calculate_sn_keccak(b"missing_entry_point")),
+                0.into(),
+            ],
+            vec![],
+            StarknetChainId::TestNet.to_felt(),
+            Some(0.into()),
+        )
+        .unwrap();
+
+        // The account is a Cairo 0 contract, so the failed call aborts the execution
+        assert!(invoke
+            .execute(&mut state.clone(), &block_context, 0)
+            .is_err());
+
+        let mut validate_state = state.clone();
+        let validate_info = invoke
+            .validate_only(&mut validate_state, &block_context)
+            .unwrap()
+            .unwrap();
+        assert!(!validate_info.failure_flag);
+        assert_eq!(
+            validate_info.entry_point_selector,
+            Some(VALIDATE_ENTRY_POINT_SELECTOR.clone())
+        );
+        // Neither the nonce nor the fee token balances are touched
+        assert!(validate_state.cache.nonce_writes.is_empty());
+        assert!(validate_state.cache.storage_writes.is_empty());
+
+        // Validation runs even if the transaction would skip it
+        invoke.skip_validation = true;
+        assert!(invoke
+            .validate_only(&mut state.clone(), &block_context)
+            .unwrap()
+            .is_some());
+    }
+
     #[test]
     fn test_execute_invoke_actual_fee_exceeded_max_fee_should_fail() {
-        let max_fee = 5;
+        let mut block_context = BlockContext::default();
+        block_context.starknet_os_config.gas_price = 1;
+        // Passes the up-front check, but can't cover the execution resources.
+        let max_fee = calculate_min_tx_fee(&block_context, FeeToken::Eth);
         let internal_invoke_function = InvokeFunction {
             contract_address: Address(0.into()),
             entry_point_selector: Felt252::from_str_radix(
@@ -807,6 +1330,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: true,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -834,13 +1358,49 @@ mod tests {
             .set_contract_class(&class_hash, &contract_class)
             .unwrap();
 
+        let tx = internal_invoke_function
+            .execute(&mut state, &block_context, 0)
+            .unwrap_err();
+        assert_matches!(tx, TransactionError::ActualFeeExceedsMaxFee(_, _));
+    }
+
+    #[test]
+    fn test_execute_invoke_max_fee_too_low_should_fail_before_execution() {
+        let contract_address = Address(0.into());
+        let internal_invoke_function = InvokeFunction {
+            contract_address: contract_address.clone(),
+            entry_point_selector: Felt252::from_str_radix(
+                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                16,
+            )
+            .unwrap(),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![1.into(), 1.into(), 10.into()],
+            tx_type: TransactionType::InvokeFunction,
+            version: 1.into(),
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 5,
+            nonce: Some(0.into()),
+            skip_validation: false,
+            skip_execute: false,
+            skip_fee_transfer: true,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        // No contract is deployed, so the transaction would fail if it was executed.
+        let mut state = CachedState::new(Arc::new(InMemoryStateReader::default()), None, None);
+
         let mut block_context = BlockContext::default();
         block_context.starknet_os_config.gas_price = 1;
 
-        let tx = internal_invoke_function
+        let error = internal_invoke_function
             .execute(&mut state, &block_context, 0)
             .unwrap_err();
-        assert_matches!(tx, TransactionError::ActualFeeExceedsMaxFee(_, _));
+        assert_matches!(error, TransactionError::MaxFeeTooLow(min_fee, 5) if min_fee == calculate_min_tx_fee(&block_context, FeeToken::Eth));
+        assert!(state.cache().nonce_writes.get(&contract_address).is_none());
     }
 
     #[test]
@@ -865,6 +1425,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -906,6 +1467,107 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_execute_invoke_nonce_overflow_should_fail() {
+        let internal_invoke_function = InvokeFunction {
+            contract_address: Address(0.into()),
+            entry_point_selector: Felt252::from_str_radix(
+                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                16,
+            )
+            .unwrap(),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![1.into(), 1.into(), 10.into()],
+            tx_type: TransactionType::InvokeFunction,
+            version: 1.into(),
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            // Largest felt, i.e. PRIME - 1
+            nonce: Some(Felt252::zero() - Felt252::one()),
+            skip_validation: false,
+            skip_execute: false,
+            skip_fee_transfer: false,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        let mut state_reader = InMemoryStateReader::default();
+        let class_hash = [1; 32];
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        let contract_address = Address(0.into());
+
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address, Felt252::zero() - Felt252::one());
+
+        let mut state = CachedState::new(Arc::new(state_reader), None, None);
+        state.set_contract_classes(HashMap::new()).unwrap();
+        state
+            .set_contract_class(&class_hash, &contract_class)
+            .unwrap();
+
+        let expected_error =
+            internal_invoke_function.execute(&mut state, &BlockContext::default(), 0);
+
+        assert_matches!(expected_error, Err(TransactionError::NonceOverflow));
+        assert!(state.cache.nonce_writes.is_empty());
+    }
+
+    #[test]
+    fn test_execute_query_invoke_does_not_increment_nonce() {
+        let internal_invoke_function = InvokeFunction {
+            contract_address: Address(0.into()),
+            entry_point_selector: Felt252::from_str_radix(
+                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+                16,
+            )
+            .unwrap(),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![1.into(), 1.into(), 10.into()],
+            tx_type: TransactionType::InvokeFunction,
+            version: &1.into() | &QUERY_VERSION_BASE.clone(),
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            nonce: Some(0.into()),
+            skip_validation: false,
+            skip_execute: false,
+            skip_fee_transfer: false,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        let mut state_reader = InMemoryStateReader::default();
+        let class_hash = [1; 32];
+        let contract_class = ContractClass::from_path("starknet_programs/fibonacci.json").unwrap();
+        let contract_address = Address(0.into());
+
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address, Felt252::zero());
+
+        let mut state = CachedState::new(Arc::new(state_reader), None, None);
+        state.set_contract_classes(HashMap::new()).unwrap();
+        state
+            .set_contract_class(&class_hash, &contract_class)
+            .unwrap();
+
+        internal_invoke_function
+            .execute(&mut state, &BlockContext::default(), 0)
+            .unwrap();
+
+        assert!(state.cache.nonce_writes.is_empty());
+    }
+
     #[test]
     fn test_execute_inovoke_nonce_missing_should_fail() {
         let internal_invoke_function = InvokeFunction {
@@ -928,6 +1590,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: false,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         // Instantiate CachedState
@@ -962,21 +1625,6 @@ mod tests {
         assert_matches!(expected_error.unwrap_err(), TransactionError::MissingNonce)
     }
 
-    #[test]
-    fn invoke_version_zero_with_non_zero_nonce_should_fail() {
-        let expected_error = preprocess_invoke_function_fields(
-            Felt252::from_str_radix(
-                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
-                16,
-            )
-            .unwrap(),
-            Some(1.into()),
-            0.into(),
-        )
-        .unwrap_err();
-        assert_matches!(expected_error, TransactionError::InvokeFunctionZeroHasNonce)
-    }
-
     #[test]
     // the test should try to make verify_no_calls_to_other_contracts fail
     fn verify_no_calls_to_other_contracts_should_fail() {
@@ -999,56 +1647,230 @@ mod tests {
     }
 
     #[test]
-    fn preprocess_invoke_function_fields_nonce_is_none() {
-        let entry_point_selector = Felt252::from_str_radix(
+    fn test_execute_account_call_forwards_to_the_target_contract() {
+        let (block_context, mut state) = create_account_tx_test_state().unwrap();
+
+        // Account whose __execute__ takes the standard call array layout
+        let account_class =
+            ContractClass::from_path("starknet_programs/raw_contract_classes/class_with_abi.json")
+                .unwrap();
+        let account_class_hash = [5; 32];
+        let account_address = Address(0x5555.into());
+        state
+            .set_contract_class(&account_class_hash, &account_class)
+            .unwrap();
+        state
+            .deploy_contract(account_address.clone(), account_class_hash)
+            .unwrap();
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
             "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
             16,
         )
         .unwrap();
-        let result =
-            preprocess_invoke_function_fields(entry_point_selector.clone(), None, 0.into());
+        let invoke = InvokeFunction::new_account_call(
+            account_address,
+            vec![Call {
+                to: TEST_CONTRACT_ADDRESS.clone(),
+                selector: selector.clone(),
+                calldata: vec![1.into(), 1.into(), 10.into()],
+            }],
+            0,
+            1.into(),
+            vec![],
+            StarknetChainId::TestNet.to_felt(),
+            Some(0.into()),
+        )
+        .unwrap();
+        assert_eq!(
+            invoke.calldata(),
+            &vec![
+                1.into(),
+                TEST_CONTRACT_ADDRESS.0.clone(),
+                selector,
+                0.into(),
+                3.into(),
+                3.into(),
+                1.into(),
+                1.into(),
+                10.into(),
+            ]
+        );
+
+        // The transaction isn't signed, so the validation is skipped
+        let tx_info = invoke
+            .create_for_simulation(true, false, true, false, false)
+            .execute(&mut state, &block_context, 0)
+            .unwrap();
 
-        let expected_additional_data: Vec<Felt252> = Vec::new();
-        let expected_entry_point_selector_field = entry_point_selector;
+        let call_info = tx_info.call_info.unwrap();
+        assert_eq!(call_info.internal_calls.len(), 1);
         assert_eq!(
-            result.unwrap(),
-            (
-                expected_entry_point_selector_field,
-                expected_additional_data
-            )
-        )
+            call_info.internal_calls[0].contract_address,
+            TEST_CONTRACT_ADDRESS.clone()
+        );
+        assert_eq!(call_info.internal_calls[0].retdata, vec![144.into()]);
     }
 
     #[test]
-    fn invoke_version_one_with_no_nonce_should_fail() {
-        let expected_error = preprocess_invoke_function_fields(
-            Felt252::from_str_radix(
-                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
-                16,
-            )
-            .unwrap(),
+    fn test_execute_invoke_counts_syscalls() {
+        let contract_address = Address(1111.into());
+        let internal_invoke_function = InvokeFunction {
+            contract_address: contract_address.clone(),
+            entry_point_selector: Felt252::from_bytes_be(&calculate_sn_keccak(b"emit_event")),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![1.into()],
+            tx_type: TransactionType::InvokeFunction,
+            version: 0.into(),
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            nonce: Some(0.into()),
+            skip_validation: true,
+            skip_execute: false,
+            skip_fee_transfer: true,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        let mut state_reader = InMemoryStateReader::default();
+        let class_hash = [1; 32];
+        let program_data = include_bytes!("../../starknet_programs/cairo2/events.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address, Felt252::zero());
+
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
             None,
-            1.into(),
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+
+        let result = internal_invoke_function
+            .execute(&mut state, &BlockContext::default(), 1_000_000)
+            .unwrap();
+
+        // emit_event(incremental: true) reads the counter, emits it and writes it back
+        assert_eq!(
+            result.syscall_counter,
+            HashMap::from([
+                ("storage_read".to_string(), 2),
+                ("emit_event".to_string(), 1),
+                ("storage_write".to_string(), 1),
+            ])
         );
-        assert!(expected_error.is_err());
-        assert_matches!(
-            expected_error.unwrap_err(),
-            TransactionError::InvokeFunctionNonZeroMissingNonce
-        )
     }
 
     #[test]
-    fn invoke_version_one_with_no_nonce_with_query_base_should_fail() {
-        let expected_error = preprocess_invoke_function_fields(
-            Felt252::from_str_radix(
-                "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
-                16,
-            )
-            .unwrap(),
+    fn test_reverted_transaction_returns_panic_data() {
+        let contract_address = Address(1111.into());
+        let internal_invoke_function = InvokeFunction {
+            contract_address: contract_address.clone(),
+            entry_point_selector: Felt252::from_bytes_be(&calculate_sn_keccak(b"square_root")),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![4.into()],
+            tx_type: TransactionType::InvokeFunction,
+            version: 0.into(),
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            nonce: Some(0.into()),
+            skip_validation: true,
+            skip_execute: false,
+            skip_fee_transfer: true,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        let mut state_reader = InMemoryStateReader::default();
+        let class_hash = [1; 32];
+        let program_data = include_bytes!("../../starknet_programs/cairo2/faulty_math_lib.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address, Felt252::zero());
+
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
             None,
-            &1.into() | &QUERY_VERSION_BASE.clone(),
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+
+        let result = internal_invoke_function
+            .execute(&mut state, &BlockContext::default(), 1_000_000)
+            .unwrap();
+
+        // square_root always panics with 'Unimplemented'
+        assert!(result.call_info.as_ref().unwrap().failure_flag);
+        assert_eq!(
+            result.revert_reason(),
+            Some(vec![Felt252::from_bytes_be(b"Unimplemented")])
+        );
+    }
+
+    #[test]
+    fn test_reverted_transaction_revert_reason() {
+        let contract_address = Address(1111.into());
+        let internal_invoke_function = InvokeFunction {
+            contract_address: contract_address.clone(),
+            entry_point_selector: Felt252::from_bytes_be(&calculate_sn_keccak(b"square_root")),
+            entry_point_type: EntryPointType::External,
+            calldata: vec![4.into()],
+            tx_type: TransactionType::InvokeFunction,
+            version: 0.into(),
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            nonce: Some(0.into()),
+            skip_validation: true,
+            skip_execute: false,
+            skip_fee_transfer: true,
+            skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
+        };
+
+        let mut state_reader = InMemoryStateReader::default();
+        let class_hash = [1; 32];
+        let program_data = include_bytes!("../../starknet_programs/cairo2/faulty_math_lib.casm");
+        let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address, Felt252::zero());
+
+        let mut state = CachedState::new(
+            Arc::new(state_reader),
+            None,
+            Some(HashMap::from([(class_hash, contract_class)])),
+        );
+
+        let result = internal_invoke_function
+            .execute(&mut state, &BlockContext::default(), 1_000_000)
+            .unwrap();
+
+        // square_root panics with 'Unimplemented', which isn't an execution error
+        assert!(result.is_reverted());
+        assert!(result.revert_error.is_none());
+        assert_eq!(
+            result.revert_reason(),
+            Some(vec![Felt252::from_bytes_be(b"Unimplemented")])
         );
-        assert!(expected_error.is_err());
     }
 
     #[test]
@@ -1071,6 +1893,7 @@ mod tests {
             skip_execute: false,
             skip_fee_transfer: true,
             skip_nonce_check: false,
+            fee_token: FeeToken::Eth,
         };
 
         let mut state_reader = InMemoryStateReader::default();
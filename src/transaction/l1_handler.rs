@@ -8,7 +8,7 @@ use getset::Getters;
 use num_traits::Zero;
 
 use crate::{
-    core::transaction_hash::{calculate_transaction_hash_common, TransactionHashPrefix},
+    core::transaction_hash::calculate_l1_handler_transaction_hash,
     definitions::{
         block_context::BlockContext, constants::L1_HANDLER_VERSION,
         transaction_type::TransactionType,
@@ -35,6 +35,7 @@ pub struct L1Handler {
     #[getset(get = "pub")]
     contract_address: Address,
     entry_point_selector: Felt252,
+    #[getset(get = "pub")]
     calldata: Vec<Felt252>,
     nonce: Option<Felt252>,
     paid_fee_on_l1: Option<Felt252>,
@@ -51,15 +52,12 @@ impl L1Handler {
         chain_id: Felt252,
         paid_fee_on_l1: Option<Felt252>,
     ) -> Result<L1Handler, TransactionError> {
-        let hash_value = calculate_transaction_hash_common(
-            TransactionHashPrefix::L1Handler,
-            L1_HANDLER_VERSION.into(),
+        let hash_value = calculate_l1_handler_transaction_hash(
             &contract_address,
             entry_point_selector.clone(),
             &calldata,
-            0,
+            nonce.clone(),
             chain_id,
-            &[nonce.clone()],
         )?;
 
         L1Handler::new_with_tx_hash(
@@ -115,6 +113,7 @@ impl L1Handler {
             call_info,
             revert_error,
             n_reverted_steps,
+            ..
         } = if self.skip_execute {
             ExecutionResult::default()
         } else {
@@ -129,6 +128,7 @@ impl L1Handler {
             )?
         };
 
+        let syscall_counter = resources_manager.syscall_invocations();
         let changes = state.count_actual_storage_changes();
         let actual_resources = calculate_tx_resources(
             resources_manager,
@@ -165,7 +165,8 @@ impl L1Handler {
             revert_error,
             actual_resources,
             Some(TransactionType::L1Handler),
-        ))
+        )
+        .with_syscall_counter(syscall_counter))
     }
 
     /// Returns the payload size of the corresponding L1-to-L2 message.
@@ -221,7 +222,7 @@ mod test {
 
     use crate::{
         definitions::{block_context::BlockContext, transaction_type::TransactionType},
-        execution::{CallInfo, TransactionExecutionInfo},
+        execution::{CallInfo, StorageWrite, TransactionExecutionInfo},
         services::api::contract_classes::deprecated_contract_class::ContractClass,
         state::{
             cached_state::CachedState, in_memory_state_reader::InMemoryStateReader,
@@ -327,6 +328,17 @@ mod test {
                 internal_calls: vec![],
                 gas_consumed: 0,
                 failure_flag: false,
+                storage_writes: vec![StorageWrite::new(
+                    [
+                        4, 40, 11, 247, 0, 35, 63, 18, 141, 159, 101, 81, 182, 2, 213, 216, 100,
+                        110, 5, 5, 101, 122, 13, 252, 204, 72, 77, 8, 58, 226, 194, 24,
+                    ],
+                    10.into(),
+                    0,
+                )],
+                syscall_trace: None,
+                deploy_from_zero: None,
+                out_of_gas_syscall: None,
             }),
             revert_error: None,
             fee_transfer_info: None,
@@ -338,6 +350,13 @@ mod test {
                 ("l1_gas_usage".to_string(), 19695),
             ]),
             tx_type: Some(TransactionType::L1Handler),
+            sender_nonce_before: None,
+            sender_nonce_after: None,
+            total_gas_consumed: 0,
+            syscall_counter: HashMap::from([
+                ("storage_read".to_string(), 1),
+                ("storage_write".to_string(), 1),
+            ]),
         }
     }
 }
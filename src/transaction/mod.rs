@@ -12,17 +12,77 @@ pub use declare::Declare;
 pub use declare_v2::DeclareV2;
 pub use deploy::Deploy;
 pub use deploy_account::DeployAccount;
-pub use invoke_function::InvokeFunction;
+pub use invoke_function::{Call, InvokeFunction};
 pub use l1_handler::L1Handler;
 pub use verify_version::verify_version;
 
 use crate::{
-    definitions::block_context::BlockContext,
+    definitions::block_context::{BlockContext, ValidateHook},
     execution::TransactionExecutionInfo,
+    services::api::contract_classes::compiled_class::CompiledClass,
     state::{cached_state::CachedState, state_api::StateReader},
     utils::Address,
 };
+use cairo_vm::felt::Felt252;
 use error::TransactionError;
+use num_traits::Zero;
+
+/// Applies the validate hook of the block context, if any. Returns whether the call to
+/// `__validate__` must be skipped.
+pub(crate) fn apply_validate_hook(block_context: &BlockContext) -> Result<bool, TransactionError> {
+    match block_context.validate_hook {
+        None => Ok(false),
+        Some(ValidateHook::ForceAccept) => Ok(true),
+        Some(ValidateHook::ForceReject) => Err(TransactionError::ValidationRejected),
+    }
+}
+
+/// Returns the number of constructor calldata felts declared in the ABI of a deprecated class
+/// when it doesn't match the length of `constructor_calldata`. Nothing is checked unless the
+/// block context enables [`BlockContext::validate_constructor_calldata`], nor for classes whose
+/// constructor parameters can't be counted or Cairo 1 classes, which carry no ABI.
+pub(crate) fn constructor_calldata_mismatch(
+    block_context: &BlockContext,
+    contract_class: &CompiledClass,
+    constructor_calldata: &[Felt252],
+) -> Option<usize> {
+    if !block_context.validate_constructor_calldata {
+        return None;
+    }
+    let CompiledClass::Deprecated(contract_class) = contract_class else {
+        return None;
+    };
+    contract_class
+        .constructor_calldata_len()
+        .filter(|expected| *expected != constructor_calldata.len())
+}
+
+/// Fails with [`TransactionError::ConstructorArgumentMismatch`] when the constructor calldata
+/// length doesn't match the class ABI, see [`constructor_calldata_mismatch`].
+pub(crate) fn check_constructor_calldata(
+    block_context: &BlockContext,
+    contract_class: &CompiledClass,
+    constructor_calldata: &[Felt252],
+) -> Result<(), TransactionError> {
+    match constructor_calldata_mismatch(block_context, contract_class, constructor_calldata) {
+        Some(expected) => Err(TransactionError::ConstructorArgumentMismatch(
+            expected,
+            constructor_calldata.len(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Static resource footprint of a transaction, computed from its contents without running it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceEstimate {
+    /// Number of felts in the calldata (constructor calldata for deploys).
+    pub calldata_length: usize,
+    /// Number of felts in the signature.
+    pub signature_length: usize,
+    /// Number of felts in the program of the declared or deployed class, zero otherwise.
+    pub class_size: usize,
+}
 
 /// Represents a transaction inside the starknet network.
 /// The transaction are actions that may modified the state of the network.
@@ -61,6 +121,68 @@ impl Transaction {
         }
     }
 
+    /// returns the address of the account that sent the transaction.
+    /// Deploy and L1Handler transactions have no L2 sender, so the zero address is returned.
+    pub fn sender_address(&self) -> Address {
+        match self {
+            Transaction::Deploy(_) => Address(Felt252::zero()),
+            Transaction::InvokeFunction(tx) => tx.contract_address().clone(),
+            Transaction::Declare(tx) => tx.sender_address.clone(),
+            Transaction::DeclareV2(tx) => tx.sender_address.clone(),
+            Transaction::DeployAccount(tx) => tx.contract_address().clone(),
+            Transaction::L1Handler(_) => Address(Felt252::zero()),
+        }
+    }
+
+    /// returns the hash of the transaction.
+    pub fn hash_value(&self) -> Felt252 {
+        match self {
+            Transaction::Deploy(tx) => tx.hash_value.clone(),
+            Transaction::InvokeFunction(tx) => tx.hash_value().clone(),
+            Transaction::Declare(tx) => tx.hash_value.clone(),
+            Transaction::DeclareV2(tx) => tx.hash_value.clone(),
+            Transaction::DeployAccount(tx) => tx.hash_value().clone(),
+            Transaction::L1Handler(tx) => tx.hash_value().clone(),
+        }
+    }
+
+    /// returns a cheap estimate of the resources the transaction will use, based only on its
+    /// calldata, signature and class sizes. Nothing is executed.
+    pub fn static_resource_estimate(&self) -> ResourceEstimate {
+        match self {
+            Transaction::Declare(tx) => ResourceEstimate {
+                calldata_length: tx.get_calldata().len(),
+                signature_length: tx.signature.len(),
+                class_size: tx.contract_class.program().data_len(),
+            },
+            Transaction::DeclareV2(tx) => ResourceEstimate {
+                calldata_length: tx.get_calldata().len(),
+                signature_length: tx.signature.len(),
+                class_size: tx.sierra_contract_class.sierra_program.len(),
+            },
+            Transaction::Deploy(tx) => ResourceEstimate {
+                calldata_length: tx.constructor_calldata.len(),
+                signature_length: 0,
+                class_size: tx.contract_class.bytecode_length(),
+            },
+            Transaction::DeployAccount(tx) => ResourceEstimate {
+                calldata_length: tx.constructor_calldata().len(),
+                signature_length: tx.signature().len(),
+                class_size: 0,
+            },
+            Transaction::InvokeFunction(tx) => ResourceEstimate {
+                calldata_length: tx.calldata().len(),
+                signature_length: tx.signature().len(),
+                class_size: 0,
+            },
+            Transaction::L1Handler(tx) => ResourceEstimate {
+                calldata_length: tx.calldata().len(),
+                signature_length: 0,
+                class_size: 0,
+            },
+        }
+    }
+
     /// execute the transaction in cairo-vm and returns a TransactionExecutionInfo structure.
     ///## Parameters:
     ///- state: a structure that implements State and StateReader traits.
@@ -81,6 +203,33 @@ impl Transaction {
             Transaction::L1Handler(tx) => tx.execute(state, block_context, remaining_gas),
         }
     }
+    /// Simulates the transaction on a copy of the given state, which is never modified.
+    /// Returns the execution info, including the revert error if the transaction reverted.
+    ///## Parameters:
+    ///- state: the state the transaction is simulated on.
+    ///- block_context: The block context of the transaction that is about to be simulated.
+    ///- remaining_gas: The gas supplied to execute the transaction.
+    ///- skip_validate: the transaction will not be verified.
+    ///- skip_fee_transfer: the transaction will not pay the fee.
+    pub fn simulate<S: StateReader>(
+        &self,
+        state: &CachedState<S>,
+        block_context: &BlockContext,
+        remaining_gas: u128,
+        skip_validate: bool,
+        skip_fee_transfer: bool,
+    ) -> Result<TransactionExecutionInfo, TransactionError> {
+        let mut simulation_state = CachedState::new(
+            state.state_reader.clone(),
+            state.contract_classes.clone(),
+            state.casm_contract_classes.clone(),
+        );
+        simulation_state.cache = state.cache.clone();
+
+        self.create_for_simulation(skip_validate, false, skip_fee_transfer, false, false)
+            .execute(&mut simulation_state, block_context, remaining_gas)
+    }
+
     /// It creates a new transaction structure modificating the skip flags. It is meant to be used only to run a simulation
     ///## Parameters:
     ///- skip_validate: the transaction will not be verified.
@@ -127,3 +276,115 @@ impl Transaction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::{block_context::StarknetChainId, constants::EXECUTE_ENTRY_POINT_SELECTOR},
+        services::api::contract_classes::deprecated_contract_class::ContractClass,
+        state::state_api::StateReader,
+        testing::{
+            create_account_tx_test_state, ERC20_CONTRACT_PATH, TEST_ACCOUNT_CONTRACT_ADDRESS,
+            TEST_CONTRACT_ADDRESS, TEST_CONTRACT_PATH,
+        },
+    };
+    use num_traits::Num;
+
+    #[test]
+    fn simulate_invoke_does_not_modify_the_state() {
+        let (block_context, state) = create_account_tx_test_state().unwrap();
+
+        // fibonacci selector
+        let selector = Felt252::from_str_radix(
+            "112e35f48499939272000bd72eb840e502ca4c3aefa8800992e8defb746e0c9",
+            16,
+        )
+        .unwrap();
+        let invoke = Transaction::InvokeFunction(
+            InvokeFunction::new(
+                TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+                EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+                1_000_000,
+                1.into(),
+                vec![
+                    TEST_CONTRACT_ADDRESS.0.clone(),
+                    selector,
+                    3.into(),
+                    1.into(),
+                    1.into(),
+                    10.into(),
+                ],
+                vec![],
+                StarknetChainId::TestNet.to_felt(),
+                Some(0.into()),
+            )
+            .unwrap(),
+        );
+
+        let original_state = state.clone();
+        let tx_info = invoke
+            .simulate(&state, &block_context, 0, false, false)
+            .unwrap();
+
+        assert!(tx_info.validate_info.is_some());
+        assert!(tx_info.fee_transfer_info.is_some());
+        assert_eq!(tx_info.call_info.unwrap().retdata, vec![144.into()]);
+        assert_eq!(state, original_state);
+        assert_eq!(
+            state.get_nonce_at(&TEST_ACCOUNT_CONTRACT_ADDRESS).unwrap(),
+            Felt252::zero()
+        );
+    }
+
+    #[test]
+    fn static_resource_estimate_scales_with_class_size_and_calldata() {
+        let declare_estimate = |path: &str| {
+            let contract_class = ContractClass::from_path(path).unwrap();
+            let class_size = contract_class.program().data_len();
+            let declare = Declare::new(
+                contract_class,
+                StarknetChainId::TestNet.to_felt(),
+                TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+                0,
+                0.into(),
+                vec![],
+                Felt252::zero(),
+            )
+            .unwrap();
+            let estimate = Transaction::Declare(declare).static_resource_estimate();
+            assert_eq!(estimate.class_size, class_size);
+            estimate
+        };
+        let fibonacci_estimate = declare_estimate(TEST_CONTRACT_PATH);
+        let erc20_estimate = declare_estimate(ERC20_CONTRACT_PATH);
+        assert!(erc20_estimate.class_size > fibonacci_estimate.class_size);
+        assert_eq!(erc20_estimate.calldata_length, 1);
+
+        let invoke_estimate = |calldata: Vec<Felt252>| {
+            Transaction::InvokeFunction(
+                InvokeFunction::new(
+                    TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+                    EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+                    0,
+                    1.into(),
+                    calldata,
+                    vec![1.into(), 2.into()],
+                    StarknetChainId::TestNet.to_felt(),
+                    Some(0.into()),
+                )
+                .unwrap(),
+            )
+            .static_resource_estimate()
+        };
+        assert_eq!(
+            invoke_estimate(vec![1.into(); 3]),
+            ResourceEstimate {
+                calldata_length: 3,
+                signature_length: 2,
+                class_size: 0,
+            }
+        );
+        assert_eq!(invoke_estimate(vec![1.into(); 30]).calldata_length, 30);
+    }
+}
@@ -1,11 +1,12 @@
 use crate::core::errors::hash_errors::HashError;
-use crate::definitions::constants::FEE_TRANSFER_N_STORAGE_CHANGES_TO_CHARGE;
+use crate::definitions::constants::{FEE_TRANSFER_N_STORAGE_CHANGES_TO_CHARGE, QUERY_VERSION_BASE};
 use crate::services::api::contract_classes::deprecated_contract_class::EntryPointType;
 use crate::state::state_api::State;
 use crate::{
     definitions::transaction_type::TransactionType,
     execution::{
         gas_usage::calculate_tx_gas_usage, os_usage::get_additional_os_resources, CallInfo,
+        L2toL1MessageInfo,
     },
     state::ExecutionResourcesManager,
     state::{cached_state::UNINITIALIZED_CLASS_HASH, state_cache::StorageEntry},
@@ -34,7 +35,7 @@ pub type CompiledClassHash = [u8; 32];
 //*      Address
 //* -------------------
 
-#[derive(Debug, Clone, PartialEq, Hash, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct Address(pub Felt252);
 
 //* -------------------
@@ -115,6 +116,12 @@ pub fn felt_to_hash(value: &Felt252) -> ClassHash {
     output
 }
 
+/// Encodes a felt as a storage key, the way it's used in a `StorageEntry`.
+/// All felt to storage key conversions should go through this function.
+pub fn storage_key(felt: &Felt252) -> [u8; 32] {
+    felt.to_be_bytes()
+}
+
 pub fn string_to_hash(class_string: &String) -> ClassHash {
     let parsed_felt = Felt252::from_str_radix(
         if &class_string[..2] == "0x" {
@@ -127,6 +134,37 @@ pub fn string_to_hash(class_string: &String) -> ClassHash {
     felt_to_hash(&parsed_felt.unwrap())
 }
 
+/// Decodes retdata made of Cairo short strings, such as the `'Out of gas'` or
+/// `'ENTRYPOINT_FAILED'` felts returned by failed calls, joining them with `", "`.
+/// Returns `None` if the retdata is empty or any of its felts isn't a printable short string.
+pub fn decode_error_retdata(retdata: &[Felt252]) -> Option<String> {
+    if retdata.is_empty() {
+        return None;
+    }
+
+    retdata
+        .iter()
+        .map(|felt| {
+            let bytes = felt.to_bytes_be();
+            if bytes.len() > 31 || !bytes.iter().all(|byte| (b' '..=b'~').contains(byte)) {
+                return None;
+            }
+            String::from_utf8(bytes).ok()
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|messages| messages.join(", "))
+}
+
+/// Splits a transaction version into its base version and whether it's a query version, i.e.
+/// offset by `QUERY_VERSION_BASE` to be simulated without modifying the state.
+pub(crate) fn split_query_version(version: &Felt252) -> (Felt252, bool) {
+    if *version >= *QUERY_VERSION_BASE {
+        (version.clone() - QUERY_VERSION_BASE.clone(), true)
+    } else {
+        (version.clone(), false)
+    }
+}
+
 // -------------------
 //    STATE UTILS
 // -------------------
@@ -171,8 +209,6 @@ pub fn calculate_tx_resources(
     l1_handler_payload_size: Option<usize>,
     n_reverted_steps: usize,
 ) -> Result<HashMap<String, usize>, TransactionError> {
-    let (n_modified_contracts, n_storage_changes) = storage_changes;
-
     let non_optional_calls: Vec<CallInfo> = call_info.iter().flatten().cloned().collect();
     let n_deployments = non_optional_calls.iter().map(get_call_n_deployments).sum();
 
@@ -182,40 +218,73 @@ pub fn calculate_tx_resources(
         l2_to_l1_messages.extend(call_info.get_sorted_l2_to_l1_messages()?)
     }
 
-    let l1_gas_usage = calculate_tx_gas_usage(
-        l2_to_l1_messages,
-        n_modified_contracts,
-        n_storage_changes + FEE_TRANSFER_N_STORAGE_CHANGES_TO_CHARGE,
-        l1_handler_payload_size,
-        n_deployments,
-    );
-
     let cairo_usage = resources_manager.cairo_usage.clone();
     let tx_syscall_counter = resources_manager.syscall_counter;
 
     // Add additional Cairo resources needed for the OS to run the transaction.
     let additional_resources = get_additional_os_resources(tx_syscall_counter, &tx_type)?;
     let new_resources = &cairo_usage + &additional_resources;
-    let mut filtered_builtins = new_resources.filter_unused_builtins();
+    let filtered_builtins = new_resources.filter_unused_builtins();
 
-    let n_steps = new_resources.n_steps
-        + n_reverted_steps
-        + 10 * filtered_builtins
-            .builtin_instance_counter
-            .remove(SEGMENT_ARENA_BUILTIN_NAME)
-            .unwrap_or(0);
+    Ok(build_tx_resources(
+        l2_to_l1_messages,
+        storage_changes,
+        l1_handler_payload_size,
+        n_deployments,
+        new_resources.n_steps + n_reverted_steps + filtered_builtins.n_memory_holes,
+        filtered_builtins.builtin_instance_counter,
+    ))
+}
 
-    let mut resources: HashMap<String, usize> = HashMap::new();
-    resources.insert("l1_gas_usage".to_string(), l1_gas_usage);
-    resources.insert(
-        "n_steps".to_string(),
-        n_steps + filtered_builtins.n_memory_holes,
+/// Computes the `actual_resources` of a transaction from raw inputs, without executing it.
+/// `storage_changes` is `(n_modified_contracts, n_storage_changes)` as returned by [`State::count_actual_storage_changes`], `n_steps` the steps already including
+/// the OS overhead and `builtins` the builtin instance counts. The transaction is assumed to
+/// send no messages and deploy no contracts.
+pub fn estimate_resources_from_changes(
+    storage_changes: (usize, usize),
+    n_steps: usize,
+    builtins: &HashMap<String, usize>,
+) -> HashMap<String, usize> {
+    build_tx_resources(
+        Vec::new(),
+        storage_changes,
+        None,
+        0,
+        n_steps,
+        builtins.clone(),
+    )
+}
+
+/// Builds the `actual_resources` map of a transaction: the L1 gas used by its on-chain data,
+/// its steps and its non-zero builtin counts. Segment arena builtins are charged as 10 steps
+/// each instead of being listed.
+fn build_tx_resources(
+    l2_to_l1_messages: Vec<L2toL1MessageInfo>,
+    storage_changes: (usize, usize),
+    l1_handler_payload_size: Option<usize>,
+    n_deployments: usize,
+    n_steps: usize,
+    builtins: HashMap<String, usize>,
+) -> HashMap<String, usize> {
+    let (n_modified_contracts, n_storage_changes) = storage_changes;
+
+    let l1_gas_usage = calculate_tx_gas_usage(
+        l2_to_l1_messages,
+        n_modified_contracts,
+        n_storage_changes + FEE_TRANSFER_N_STORAGE_CHANGES_TO_CHARGE,
+        l1_handler_payload_size,
+        n_deployments,
     );
-    for (builtin, value) in filtered_builtins.builtin_instance_counter {
-        resources.insert(builtin, value);
-    }
 
-    Ok(resources)
+    let mut resources: HashMap<String, usize> = builtins
+        .into_iter()
+        .filter(|(_, value)| *value != 0)
+        .collect();
+    let n_steps = n_steps + 10 * resources.remove(SEGMENT_ARENA_BUILTIN_NAME).unwrap_or(0);
+
+    resources.insert("l1_gas_usage".to_string(), l1_gas_usage);
+    resources.insert("n_steps".to_string(), n_steps);
+    resources
 }
 
 /// Returns a mapping containing key-value pairs from a that are not included in b (if
@@ -250,7 +319,7 @@ pub fn to_cache_state_storage_mapping(
     let mut storage_writes = HashMap::new();
     for (address, contract_storage) in map {
         for (key, value) in contract_storage {
-            storage_writes.insert((address.clone(), felt_to_hash(key)), value.clone());
+            storage_writes.insert((address.clone(), storage_key(key)), value.clone());
         }
     }
     storage_writes
@@ -557,6 +626,7 @@ pub mod test_utils {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::execution::OrderedL2ToL1Message;
     use cairo_vm::felt::{felt_str, Felt252};
     use num_traits::{One, Zero};
     use std::collections::HashMap;
@@ -688,4 +758,93 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn test_storage_key() {
+        let felts = [
+            Felt252::zero(),
+            Felt252::one(),
+            257.into(),
+            Felt252::zero() - Felt252::one(),
+            felt_str!(
+                "2151680050850558576753658069693146429350618838199373217695410689374331200218"
+            ),
+        ];
+
+        for felt in felts.iter() {
+            assert_eq!(storage_key(felt), felt.to_be_bytes());
+            assert_eq!(storage_key(felt), felt_to_hash(felt));
+        }
+    }
+
+    #[test]
+    fn test_estimate_resources_from_changes() {
+        let builtins = HashMap::from([
+            ("pedersen_builtin".to_string(), 3),
+            ("range_check_builtin".to_string(), 0),
+            (SEGMENT_ARENA_BUILTIN_NAME.to_string(), 2),
+        ]);
+
+        // 2 modified contracts and 3 storage changes: the on-chain data holds 2 words per
+        // contract and per storage change, including the fee transfer's, at 612 gas per word.
+        let resources = estimate_resources_from_changes((2, 3), 1000, &builtins);
+
+        assert_eq!(
+            resources,
+            HashMap::from([
+                ("l1_gas_usage".to_string(), 612 * (2 * 2 + 4 * 2)),
+                ("n_steps".to_string(), 1020),
+                ("pedersen_builtin".to_string(), 3),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_calculate_tx_resources_charges_sent_messages() {
+        let l1_gas_usage = |n_messages: usize| {
+            let call_info = CallInfo {
+                l2_to_l1_messages: (0..n_messages)
+                    .map(|order| OrderedL2ToL1Message {
+                        order,
+                        to_address: Address(1.into()),
+                        payload: vec![2.into(), 3.into()],
+                    })
+                    .collect(),
+                ..Default::default()
+            };
+            calculate_tx_resources(
+                ExecutionResourcesManager::default(),
+                &[Some(call_info)],
+                TransactionType::InvokeFunction,
+                (1, 1),
+                None,
+                0,
+            )
+            .unwrap()["l1_gas_usage"]
+        };
+
+        assert!(l1_gas_usage(1) > l1_gas_usage(0));
+        assert!(l1_gas_usage(3) > l1_gas_usage(1));
+    }
+
+    #[test]
+    fn test_decode_error_retdata() {
+        assert_eq!(
+            decode_error_retdata(&[Felt252::from_bytes_be(b"Out of gas")]),
+            Some("Out of gas".to_string())
+        );
+        assert_eq!(
+            decode_error_retdata(&[
+                Felt252::from_bytes_be(b"Block number out of range"),
+                Felt252::from_bytes_be(b"ENTRYPOINT_FAILED"),
+            ]),
+            Some("Block number out of range, ENTRYPOINT_FAILED".to_string())
+        );
+        assert_eq!(decode_error_retdata(&[]), None);
+        assert_eq!(decode_error_retdata(&[Felt252::zero()]), None);
+        assert_eq!(
+            decode_error_retdata(&[Felt252::from_bytes_be(b"Out of gas"), Felt252::one()]),
+            None
+        );
+    }
 }
@@ -0,0 +1,47 @@
+use starknet::ClassHash;
+
+#[starknet::interface]
+trait IDelegateAddresses<TContractState> {
+    fn emit_addresses(ref self: TContractState);
+    fn delegate_emit_addresses(ref self: TContractState, class_hash: ClassHash, selector: felt252);
+}
+
+#[starknet::contract]
+mod DelegateAddresses {
+    use starknet::{ClassHash, ContractAddress, get_caller_address, get_contract_address};
+    use starknet::syscalls::library_call_syscall;
+    use starknet::SyscallResultTrait;
+    use array::ArrayTrait;
+    use array::SpanTrait;
+
+    #[storage]
+    struct Storage {
+    }
+
+    #[derive(Copy, Drop, PartialEq, starknet::Event)]
+    struct ObservedAddresses {
+        contract_address: ContractAddress,
+        caller_address: ContractAddress,
+    }
+
+    #[event]
+    #[derive(Copy, Drop, PartialEq, starknet::Event)]
+    enum Event {
+        ObservedAddresses: ObservedAddresses,
+    }
+
+    #[external(v0)]
+    impl DelegateAddresses of super::IDelegateAddresses<ContractState> {
+        fn emit_addresses(ref self: ContractState) {
+            self.emit(Event::ObservedAddresses(ObservedAddresses {
+                contract_address: get_contract_address(),
+                caller_address: get_caller_address(),
+            }));
+        }
+
+        fn delegate_emit_addresses(ref self: ContractState, class_hash: ClassHash, selector: felt252) {
+            let calldata = ArrayTrait::<felt252>::new();
+            library_call_syscall(class_hash, selector, calldata.span()).unwrap_syscall();
+        }
+    }
+}
@@ -20,7 +20,10 @@ use starknet_in_rust::{
     utils::Address,
     CasmContractClass,
 };
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 lazy_static! {
     static ref TEST_ACCOUNT_COMPILED_CONTRACT_CLASS_HASH: Felt252 = felt_str!("1");
@@ -262,6 +265,10 @@ fn internal_deploy_account_cairo1() {
             .map(|(k, v)| (k.to_string(), v))
             .collect(),
             Some(TransactionType::DeployAccount),
-        ),
+        )
+        .with_syscall_counter(HashMap::from([
+            ("get_execution_info".to_string(), 1),
+            ("storage_write".to_string(), 1),
+        ])),
     );
 }
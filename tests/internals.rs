@@ -17,7 +17,7 @@ use starknet_in_rust::core::contract_address::{
 };
 use starknet_in_rust::core::errors::state_errors::StateError;
 use starknet_in_rust::definitions::constants::{
-    DEFAULT_CAIRO_RESOURCE_FEE_WEIGHTS, VALIDATE_ENTRY_POINT_SELECTOR,
+    DEFAULT_CAIRO_RESOURCE_FEE_WEIGHTS, STEP_GAS_COST, VALIDATE_ENTRY_POINT_SELECTOR,
 };
 use starknet_in_rust::execution::execution_entry_point::ExecutionEntryPoint;
 use starknet_in_rust::execution::TransactionExecutionContext;
@@ -38,7 +38,7 @@ use starknet_in_rust::{
         },
         transaction_type::TransactionType,
     },
-    execution::{CallInfo, CallType, OrderedEvent, TransactionExecutionInfo},
+    execution::{CallInfo, CallType, OrderedEvent, StorageWrite, TransactionExecutionInfo},
     state::in_memory_state_reader::InMemoryStateReader,
     state::{
         cached_state::{CachedState, ContractClassCache},
@@ -111,7 +111,7 @@ pub fn new_starknet_block_context_for_testing() -> BlockContext {
         0,
         DEFAULT_CAIRO_RESOURCE_FEE_WEIGHTS.clone(),
         1_000_000,
-        0,
+        1_000_000,
         BlockInfo::empty(TEST_SEQUENCER_ADDRESS.clone()),
         HashMap::default(),
         true,
@@ -606,6 +606,23 @@ fn expected_fee_transfer_info(fee: u128) -> CallInfo {
     CallInfo {
         failure_flag: false,
         gas_consumed: 0,
+        storage_writes: vec![
+            StorageWrite::new(
+                felt_to_hash(&TEST_ERC20_ACCOUNT_BALANCE_KEY),
+                INITIAL_BALANCE.clone() - Felt252::from(fee),
+                0,
+            ),
+            StorageWrite::new(felt_to_hash(&TEST_ERC20_BALANCE_KEY_1), Felt252::zero(), 0),
+            StorageWrite::new(
+                felt_to_hash(&TEST_ERC20_SEQUENCER_BALANCE_KEY),
+                Felt252::from(fee),
+                0,
+            ),
+            StorageWrite::new(felt_to_hash(&TEST_ERC20_BALANCE_KEY_2), Felt252::zero(), 0),
+        ],
+        syscall_trace: None,
+        deploy_from_zero: None,
+        out_of_gas_syscall: None,
         caller_address: TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
         call_type: Some(CallType::Call),
         contract_address: Address(Felt252::from(4097)),
@@ -666,6 +683,23 @@ fn expected_fib_fee_transfer_info(fee: u128) -> CallInfo {
     CallInfo {
         failure_flag: false,
         gas_consumed: 0,
+        storage_writes: vec![
+            StorageWrite::new(
+                felt_to_hash(&TEST_ERC20_ACCOUNT_BALANCE_KEY),
+                INITIAL_BALANCE.clone() - Felt252::from(1252 + fee),
+                0,
+            ),
+            StorageWrite::new(felt_to_hash(&TEST_ERC20_BALANCE_KEY_1), Felt252::zero(), 0),
+            StorageWrite::new(
+                felt_to_hash(&TEST_ERC20_SEQUENCER_BALANCE_KEY),
+                Felt252::from(1252 + fee),
+                0,
+            ),
+            StorageWrite::new(felt_to_hash(&TEST_ERC20_BALANCE_KEY_2), Felt252::zero(), 0),
+        ],
+        syscall_trace: None,
+        deploy_from_zero: None,
+        out_of_gas_syscall: None,
         caller_address: TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
         call_type: Some(CallType::Call),
         contract_address: Address(Felt252::from(4097)),
@@ -1247,6 +1281,7 @@ fn expected_transaction_execution_info(block_context: &BlockContext) -> Transact
         resources,
         Some(TransactionType::InvokeFunction),
     )
+    .with_syscall_counter(HashMap::from([("call_contract".to_string(), 1)]))
 }
 
 fn expected_fib_transaction_execution_info(
@@ -1277,6 +1312,7 @@ fn expected_fib_transaction_execution_info(
         resources,
         Some(TransactionType::InvokeFunction),
     )
+    .with_syscall_counter(HashMap::from([("call_contract".to_string(), 1)]))
 }
 
 #[test]
@@ -1299,6 +1335,32 @@ fn test_invoke_tx() {
     assert_eq!(result, expected_execution_info);
 }
 
+#[test]
+fn test_invoke_tx_fee_transfer_gas() {
+    let (block_context, state) = &mut create_account_tx_test_state().unwrap();
+    let Address(test_contract_address) = TEST_CONTRACT_ADDRESS.clone();
+    let calldata = vec![
+        test_contract_address,
+        Felt252::from_bytes_be(&calculate_sn_keccak(b"return_result")),
+        Felt252::from(1),
+        Felt252::from(2),
+    ];
+    let invoke_tx = invoke_tx(calldata);
+
+    let result = invoke_tx.execute(state, block_context, 0).unwrap();
+    assert!(result.actual_fee > 0);
+
+    // The Cairo 0 fee token is charged for the steps its transfer ran
+    let fee_transfer_info = result.fee_transfer_info.as_ref().unwrap();
+    assert_eq!(fee_transfer_info.gas_consumed, 0);
+    assert_eq!(
+        result.fee_transfer_gas(),
+        fee_transfer_info.execution_resources.n_steps as u128 * STEP_GAS_COST
+    );
+    assert!(result.fee_transfer_gas() > 0);
+    assert!(result.total_gas_consumed >= result.fee_transfer_gas());
+}
+
 #[test]
 fn test_invoke_tx_state() {
     let (starknet_general_context, state) = &mut create_account_tx_test_state().unwrap();